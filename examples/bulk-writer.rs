@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+pub fn config_env_var(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|e| format!("{}: {}", name, e))
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyTestStructure {
+    some_id: String,
+    some_string: String,
+    created_at: DateTime<Utc>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Logging with debug enabled
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter("firestore=debug")
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    // Create an instance
+    let db = FirestoreDb::new(&config_env_var("PROJECT_ID")?).await?;
+
+    const TEST_COLLECTION_NAME: &'static str = "test-bulk-write";
+
+    println!("Populating a test collection");
+    let bulk_writer = db
+        .create_bulk_writer_with_options(FirestoreBulkWriterOptions::new().with_max_batch_size(50))
+        .await?;
+
+    for idx in 0..5000 {
+        let my_struct = MyTestStructure {
+            some_id: format!("test-{}", idx),
+            some_string: "Test".to_string(),
+            created_at: Utc::now(),
+        };
+
+        bulk_writer
+            .update_object(
+                TEST_COLLECTION_NAME,
+                &my_struct.some_id,
+                &my_struct,
+                None,
+                None,
+                vec![],
+            )
+            .await?;
+    }
+
+    let summary = bulk_writer.finish().await?;
+    println!(
+        "Written {} documents, failed {}",
+        summary.succeeded,
+        summary.failures.len()
+    );
+
+    Ok(())
+}
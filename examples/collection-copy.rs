@@ -0,0 +1,43 @@
+use firestore::*;
+
+pub fn config_env_var(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|e| format!("{}: {}", name, e))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Logging with debug enabled
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter("firestore=debug")
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    // Create instances for the default and a target database
+    let db = FirestoreDb::new(&config_env_var("PROJECT_ID")?).await?;
+    let target_db = FirestoreDb::with_options(
+        FirestoreDbOptions::new(config_env_var("PROJECT_ID")?)
+            .with_database_id(config_env_var("DATABASE_ID")?),
+    )
+    .await?;
+
+    const TEST_COLLECTION_NAME: &'static str = "test-bulk-write";
+
+    let copier = db.create_collection_copier_with_options(
+        &target_db,
+        FirestoreCollectionCopierOptions::new()
+            .with_recursive(true)
+            .with_existing_documents(FirestoreCopyExistingPolicy::Skip),
+    );
+
+    let result = copier
+        .copy_collection(TEST_COLLECTION_NAME, TEST_COLLECTION_NAME)
+        .await?;
+    println!(
+        "Copied {} documents, skipped {}, failed {}",
+        result.copied_documents,
+        result.skipped_documents,
+        result.failures.len()
+    );
+
+    Ok(())
+}
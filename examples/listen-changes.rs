@@ -74,25 +74,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         .add_target(TEST_TARGET_ID_BY_DOC_IDS, &mut listener)?;
 
     listener
-        .start(|event| async move {
-            match event {
-                FirestoreListenEvent::DocumentChange(ref doc_change) => {
-                    println!("Doc changed: {doc_change:?}");
-
-                    if let Some(doc) = &doc_change.document {
-                        let obj: MyTestStructure =
-                            FirestoreDb::deserialize_doc_to::<MyTestStructure>(doc)
-                                .expect("Deserialized object");
-                        println!("As object: {obj:?}");
+        .start_obj(
+            |event: FirestoreListenerEvent<MyTestStructure>| async move {
+                match event {
+                    FirestoreListenerEvent::Added(doc_event)
+                    | FirestoreListenerEvent::Modified(doc_event) => {
+                        println!(
+                            "Doc changed: {} for targets {:?}",
+                            doc_event.document_name, doc_event.target_ids
+                        );
+                        println!("As object: {:?}", doc_event.doc);
+                    }
+                    FirestoreListenerEvent::DecodeError(decode_error) => {
+                        println!(
+                            "Unable to decode {}: {}",
+                            decode_error.document_name, decode_error.error
+                        );
+                    }
+                    _ => {
+                        println!("Received a listen event to handle: {event:?}");
                     }
                 }
-                _ => {
-                    println!("Received a listen response event to handle: {event:?}");
-                }
-            }
 
-            Ok(())
-        })
+                Ok(())
+            },
+        )
         .await?;
     // Wait any input until we shutdown
     println!(
@@ -0,0 +1,48 @@
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+pub fn config_env_var(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|e| format!("{}: {}", name, e))
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyTestStructure {
+    some_id: String,
+    some_string: String,
+    some_num: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Logging with debug enabled
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter("firestore=debug")
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    // Create an instance recording all the executed queries
+    let query_recorder = FirestoreQueryRecorder::new();
+    let db = FirestoreDb::new(&config_env_var("PROJECT_ID")?)
+        .await?
+        .clone_with_query_recorder(query_recorder.clone());
+
+    const TEST_COLLECTION_NAME: &'static str = "test-query";
+
+    let _: Vec<MyTestStructure> = db
+        .fluent()
+        .select()
+        .from(TEST_COLLECTION_NAME)
+        .filter(|q| q.for_all([q.field(path!(MyTestStructure::some_string)).eq("Test")]))
+        .order_by([(
+            path!(MyTestStructure::some_num),
+            FirestoreQueryDirection::Descending,
+        )])
+        .obj()
+        .query()
+        .await?;
+
+    // Contents for firestore.indexes.json
+    println!("{}", query_recorder.index_definitions().to_json_string()?);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+pub fn config_env_var(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|e| format!("{}: {}", name, e))
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyTestStructure {
+    some_id: String,
+    some_string: String,
+    some_num: u64,
+    created_at: DateTime<Utc>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Logging with debug enabled
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter("firestore=debug")
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    // Create an instance
+    let db = FirestoreDb::new(&config_env_var("PROJECT_ID")?).await?;
+
+    const TEST_COLLECTION_NAME: &'static str = "test-query";
+
+    let mut page_token: Option<FirestorePageToken> = None;
+    loop {
+        let page: FirestorePage<MyTestStructure> = db
+            .fluent()
+            .select()
+            .from(TEST_COLLECTION_NAME)
+            .order_by([(
+                path!(MyTestStructure::some_num),
+                FirestoreQueryDirection::Descending,
+            )])
+            .obj()
+            .paginate(10)
+            .opt_page_token(page_token)
+            .get_page()
+            .await?;
+
+        println!("Page with {} items", page.items.len());
+
+        // The token can be passed to a client and used later to continue
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+pub fn config_env_var(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|e| format!("{}: {}", name, e))
+}
+
+// Example structure to play with
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyTestStructure {
+    some_id: String,
+    some_num: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyAggTestStructure {
+    counter: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Logging with debug enabled
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter("firestore=debug")
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    // Create an instance
+    let db = FirestoreDb::new(&config_env_var("PROJECT_ID")?).await?;
+
+    const TEST_COLLECTION_NAME: &'static str = "test-snapshot";
+
+    println!("Populating a test collection");
+    for i in 0..10 {
+        let my_struct = MyTestStructure {
+            some_id: format!("test-{}", i),
+            some_num: i,
+        };
+
+        db.fluent()
+            .update()
+            .in_col(TEST_COLLECTION_NAME)
+            .document_id(&my_struct.some_id)
+            .object(&my_struct)
+            .execute::<()>()
+            .await?;
+    }
+
+    let read_time = chrono::Utc::now();
+
+    println!("Removing test-5 after the read time");
+    db.fluent()
+        .delete()
+        .from(TEST_COLLECTION_NAME)
+        .document_id("test-5")
+        .execute()
+        .await?;
+
+    // All the reads below see the collection as it was at `read_time`
+    let snapshot = db.snapshot_at(read_time).await?;
+
+    let objects: Vec<MyTestStructure> = snapshot
+        .fluent()
+        .select()
+        .from(TEST_COLLECTION_NAME)
+        .obj()
+        .query()
+        .await?;
+    println!("Objects at the read time: {}", objects.len());
+
+    let counts: Vec<MyAggTestStructure> = snapshot
+        .fluent()
+        .select()
+        .from(TEST_COLLECTION_NAME)
+        .aggregate(|a| a.fields([a.field(path!(MyAggTestStructure::counter)).count()]))
+        .obj()
+        .query()
+        .await?;
+    println!("Count at the read time: {:?}", counts);
+
+    snapshot.finish().await?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+pub fn config_env_var(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|e| format!("{}: {}", name, e))
+}
+
+// Example structure to play with
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyCounter {
+    value: u64,
+}
+
+const TEST_COLLECTION_NAME: &'static str = "test-typed-trans";
+const TEST_DOCUMENT_ID: &str = "test_counter";
+
+async fn increment_counter(
+    db: FirestoreDb,
+    transaction: &mut FirestoreTransaction<'_>,
+) -> FirestoreResult<u64> {
+    let counter: Option<MyCounter> = db
+        .fluent()
+        .select()
+        .by_id_in(TEST_COLLECTION_NAME)
+        .obj()
+        .one(TEST_DOCUMENT_ID)
+        .await?;
+
+    let new_value = counter.map(|counter| counter.value).unwrap_or(0) + 1;
+
+    db.fluent()
+        .update()
+        .in_col(TEST_COLLECTION_NAME)
+        .document_id(TEST_DOCUMENT_ID)
+        .object(&MyCounter { value: new_value })
+        .add_to_transaction(transaction)?;
+
+    Ok(new_value)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Logging with debug enabled
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter("firestore=debug")
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    // Create an instance
+    let db = FirestoreDb::new(&config_env_var("PROJECT_ID")?).await?;
+
+    db.fluent()
+        .update()
+        .in_col(TEST_COLLECTION_NAME)
+        .document_id(TEST_DOCUMENT_ID)
+        .object(&MyCounter { value: 0 })
+        .execute::<()>()
+        .await?;
+
+    // The function returns the usual `FirestoreResult`, and transient database errors are retried
+    let new_value: u64 = db
+        .run_typed_transaction_with_options(
+            increment_counter,
+            FirestoreTransactionOptions::new().with_max_attempts(5),
+        )
+        .await?;
+
+    println!("Counter value: {new_value}");
+
+    Ok(())
+}
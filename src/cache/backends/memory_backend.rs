@@ -5,7 +5,7 @@ use chrono::Utc;
 use futures::stream::BoxStream;
 use moka::future::{Cache, CacheBuilder};
 
-use crate::cache::cache_query_engine::FirestoreCacheQueryEngine;
+use crate::local_engine::FirestoreCacheQueryEngine;
 use futures::{future, StreamExt};
 use std::collections::HashMap;
 use tracing::*;
@@ -196,27 +196,32 @@ impl FirestoreCacheBackend for FirestoreMemoryCacheBackend {
         Ok(())
     }
 
-    async fn on_listen_event(&self, event: FirestoreListenEvent) -> FirestoreResult<()> {
+    async fn on_listen_event(
+        &self,
+        event: FirestoreListenerEvent<FirestoreDocument>,
+    ) -> FirestoreResult<()> {
         match event {
-            FirestoreListenEvent::DocumentChange(doc_change) => {
-                if let Some(doc) = doc_change.document {
-                    let (collection_path, document_id) = split_document_path(&doc.name);
-                    if let Some(mem_cache) = self.collection_caches.get(collection_path) {
-                        trace!(
-                            "Writing document to cache due to listener event: {:?}",
-                            doc.name
-                        );
-                        mem_cache.insert(document_id.to_string(), doc).await;
-                    }
+            FirestoreListenerEvent::Added(doc_event)
+            | FirestoreListenerEvent::Modified(doc_event) => {
+                let (collection_path, document_id) = split_document_path(&doc_event.document_name);
+                if let Some(mem_cache) = self.collection_caches.get(collection_path) {
+                    trace!(
+                        "Writing document to cache due to listener event: {:?}",
+                        doc_event.document_name
+                    );
+                    mem_cache
+                        .insert(document_id.to_string(), doc_event.doc)
+                        .await;
                 }
                 Ok(())
             }
-            FirestoreListenEvent::DocumentDelete(doc_deleted) => {
-                let (collection_path, document_id) = split_document_path(&doc_deleted.document);
+            FirestoreListenerEvent::Deleted(doc_deleted) => {
+                let (collection_path, document_id) =
+                    split_document_path(&doc_deleted.document_name);
                 if let Some(mem_cache) = self.collection_caches.get(collection_path) {
                     trace!(
                         "Removing document from cache due to listener event: {:?}",
-                        doc_deleted.document.as_str()
+                        doc_deleted.document_name.as_str()
                     );
                     mem_cache.remove(document_id).await;
                 }
@@ -3,7 +3,7 @@ use crate::*;
 use async_trait::async_trait;
 use futures::stream::BoxStream;
 
-use crate::cache::cache_query_engine::FirestoreCacheQueryEngine;
+use crate::local_engine::FirestoreCacheQueryEngine;
 use chrono::Utc;
 use futures::StreamExt;
 use gcloud_sdk::google::firestore::v1::Document;
@@ -305,26 +305,29 @@ impl FirestoreCacheBackend for FirestorePersistentCacheBackend {
         Ok(())
     }
 
-    async fn on_listen_event(&self, event: FirestoreListenEvent) -> FirestoreResult<()> {
+    async fn on_listen_event(
+        &self,
+        event: FirestoreListenerEvent<FirestoreDocument>,
+    ) -> FirestoreResult<()> {
         match event {
-            FirestoreListenEvent::DocumentChange(doc_change) => {
-                if let Some(doc) = doc_change.document {
-                    trace!(
-                        "Writing document to cache due to listener event: {:?}",
-                        doc.name
-                    );
-                    self.write_document(&doc)?;
-                }
+            FirestoreListenerEvent::Added(doc_event)
+            | FirestoreListenerEvent::Modified(doc_event) => {
+                trace!(
+                    "Writing document to cache due to listener event: {:?}",
+                    doc_event.document_name
+                );
+                self.write_document(&doc_event.doc)?;
                 Ok(())
             }
-            FirestoreListenEvent::DocumentDelete(doc_deleted) => {
-                let (collection_path, document_id) = split_document_path(&doc_deleted.document);
+            FirestoreListenerEvent::Deleted(doc_deleted) => {
+                let (collection_path, document_id) =
+                    split_document_path(&doc_deleted.document_name);
                 let write_txn = self.redb.begin_write()?;
                 let td: TableDefinition<&str, &[u8]> = TableDefinition::new(collection_path);
                 let mut table = write_txn.open_table(td)?;
                 trace!(
                     "Removing document from cache due to listener event: {:?}",
-                    doc_deleted.document.as_str()
+                    doc_deleted.document_name.as_str()
                 );
                 table.remove(document_id)?;
                 Ok(())
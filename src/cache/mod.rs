@@ -15,9 +15,6 @@ use futures::stream::BoxStream;
 use futures::StreamExt;
 use tracing::*;
 
-mod cache_filter_engine;
-mod cache_query_engine;
-
 pub struct FirestoreCache<B, LS>
 where
     B: FirestoreCacheBackend + Send + Sync + 'static,
@@ -104,7 +101,7 @@ where
         let backend = self.inner.backend.clone();
         self.inner
             .listener
-            .start(move |event| {
+            .start_events(move |event| {
                 let backend = backend.clone();
                 async move {
                     if let Err(err) = backend.on_listen_event(event).await {
@@ -144,7 +141,10 @@ pub trait FirestoreCacheBackend: FirestoreCacheDocsByPathSupport {
 
     async fn shutdown(&self) -> FirestoreResult<()>;
 
-    async fn on_listen_event(&self, event: FirestoreListenEvent) -> FirestoreResult<()>;
+    async fn on_listen_event(
+        &self,
+        event: FirestoreListenerEvent<FirestoreDocument>,
+    ) -> FirestoreResult<()>;
 }
 
 #[async_trait]
@@ -1,6 +1,11 @@
 #![allow(clippy::derive_partial_eq_without_eq)] // Since we may not be able to implement Eq for the changes coming from Firestore protos
 
-use crate::{FirestoreDb, FirestoreError, FirestoreQueryParams, FirestoreResult};
+use crate::db::explain_models::explain_unsupported_error;
+use crate::db::query_ext_api::{run_aggregation_query_ext, RunAggregationQueryExtRequest};
+use crate::{
+    FirestoreDb, FirestoreExplainOptions, FirestoreExplainResult, FirestoreQueryParams,
+    FirestoreResult, FirestoreRetryState,
+};
 use async_trait::async_trait;
 use chrono::prelude::*;
 use futures::future::BoxFuture;
@@ -139,6 +144,27 @@ pub trait FirestoreAggregatedQuerySupport {
     where
         for<'de> T: Deserialize<'de>,
         T: Send + 'b;
+
+    /// Explaining the query plan and optionally running the query to collect the execution statistics.
+    /// Not supported by default.
+    async fn explain_aggregated_query_doc(
+        &self,
+        _params: FirestoreAggregatedQueryParams,
+        _options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        Err(explain_unsupported_error())
+    }
+
+    async fn explain_aggregated_query_obj<T>(
+        &self,
+        _params: FirestoreAggregatedQueryParams,
+        _options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        Err(explain_unsupported_error())
+    }
 }
 
 #[async_trait]
@@ -155,7 +181,8 @@ impl FirestoreAggregatedQuerySupport for FirestoreDb {
             "/firestore/collection_name" = collection_str.as_str(),
             "/firestore/response_time" = field::Empty
         );
-        self.aggregated_query_doc_with_retries(params, 0, &span)
+        self.prepare_aggregated_query(&params)?;
+        self.aggregated_query_doc_with_retries(params, self.get_retry_policy().new_state(), &span)
             .await
     }
 
@@ -172,8 +199,13 @@ impl FirestoreAggregatedQuerySupport for FirestoreDb {
             "/firestore/response_time" = field::Empty
         );
 
+        self.prepare_aggregated_query(&params)?;
         let doc_stream = self
-            .stream_aggregated_query_doc_with_retries(params, 0, &span)
+            .stream_aggregated_query_doc_with_retries(
+                params,
+                self.get_retry_policy().new_state(),
+                &span,
+            )
             .await?;
 
         Ok(Box::pin(doc_stream.filter_map(|doc_res| {
@@ -201,8 +233,13 @@ impl FirestoreAggregatedQuerySupport for FirestoreDb {
             "/firestore/response_time" = field::Empty
         );
 
+        self.prepare_aggregated_query(&params)?;
         let doc_stream = self
-            .stream_aggregated_query_doc_with_retries(params, 0, &span)
+            .stream_aggregated_query_doc_with_retries(
+                params,
+                self.get_retry_policy().new_state(),
+                &span,
+            )
             .await?;
 
         Ok(Box::pin(doc_stream.filter_map(|doc_res| {
@@ -266,14 +303,139 @@ impl FirestoreAggregatedQuerySupport for FirestoreDb {
             future::ready(Self::deserialize_doc_to::<T>(&doc))
         })))
     }
+
+    async fn explain_aggregated_query_doc(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        let collection_str = params.query_params.collection_id.to_string();
+
+        let span = span!(
+            Level::DEBUG,
+            "Firestore Explain Aggregated Query",
+            "/firestore/collection_name" = collection_str.as_str(),
+            "/firestore/response_time" = field::Empty
+        );
+
+        self.prepare_aggregated_query(&params)?;
+        let explain_request = RunAggregationQueryExtRequest {
+            parent: params
+                .query_params
+                .parent
+                .as_ref()
+                .unwrap_or_else(|| self.get_documents_path())
+                .clone(),
+            consistency_selector: self
+                .session_params
+                .consistency_selector
+                .as_ref()
+                .map(|selector| selector.try_into())
+                .transpose()?,
+            query_type: Some(
+                run_aggregation_query_request::QueryType::StructuredAggregationQuery(params.into()),
+            ),
+            explain_options: Some(options.into()),
+        };
+
+        let begin_query_utc: DateTime<Utc> = Utc::now();
+
+        let explain_result = self
+            .with_retries(&span, || {
+                let request = self.create_request(explain_request.clone());
+                async move {
+                    let mut client = self.query_ext_client().await?;
+                    let mut response_stream = run_aggregation_query_ext(&mut client, request)
+                        .await?
+                        .into_inner();
+
+                    let mut results = Vec::new();
+                    let mut metrics = None;
+                    while let Some(response) = response_stream.message().await? {
+                        if let Some(result) = response.result {
+                            results.push(Document {
+                                name: "".to_string(),
+                                fields: result.aggregate_fields,
+                                create_time: None,
+                                update_time: None,
+                            });
+                        }
+                        if let Some(explain_metrics) = response.explain_metrics {
+                            metrics = Some(explain_metrics.into());
+                        }
+                    }
+                    Ok(FirestoreExplainResult::new(results).opt_metrics(metrics))
+                }
+            })
+            .await?;
+
+        let end_query_utc: DateTime<Utc> = Utc::now();
+        let query_duration = end_query_utc.signed_duration_since(begin_query_utc);
+
+        span.record(
+            "/firestore/response_time",
+            query_duration.num_milliseconds(),
+        );
+        span.in_scope(|| {
+            debug!(
+                "Explaining aggregated query in {} took {}ms",
+                collection_str,
+                query_duration.num_milliseconds()
+            );
+        });
+
+        Ok(explain_result)
+    }
+
+    async fn explain_aggregated_query_obj<T>(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let explain_result = self.explain_aggregated_query_doc(params, options).await?;
+        Ok(FirestoreExplainResult::new(
+            explain_result
+                .results
+                .iter()
+                .map(|doc| Self::deserialize_doc_to(doc))
+                .collect::<FirestoreResult<Vec<T>>>()?,
+        )
+        .opt_metrics(explain_result.metrics))
+    }
+}
+
+impl From<FirestoreAggregatedQueryParams> for StructuredAggregationQuery {
+    fn from(params: FirestoreAggregatedQueryParams) -> Self {
+        StructuredAggregationQuery {
+            aggregations: params.aggregations.iter().map(|agg| agg.into()).collect(),
+            query_type: Some(structured_aggregation_query::QueryType::StructuredQuery(
+                params.query_params.into(),
+            )),
+        }
+    }
 }
 
 impl FirestoreDb {
+    // Once per query rather than for every retry attempt
+    fn prepare_aggregated_query(
+        &self,
+        params: &FirestoreAggregatedQueryParams,
+    ) -> FirestoreResult<()> {
+        params
+            .query_params
+            .ensure_without_find_nearest("aggregation queries")?;
+        self.record_query(&params.query_params);
+        Ok(())
+    }
+
     fn create_aggregated_query_request(
         &self,
         params: FirestoreAggregatedQueryParams,
     ) -> FirestoreResult<gcloud_sdk::tonic::Request<RunAggregationQueryRequest>> {
-        Ok(gcloud_sdk::tonic::Request::new(RunAggregationQueryRequest {
+        Ok(self.create_request(RunAggregationQueryRequest {
             parent: params
                 .query_params
                 .parent
@@ -286,19 +448,16 @@ impl FirestoreDb {
                 .as_ref()
                 .map(|selector| selector.try_into())
                 .transpose()?,
-            query_type: Some(run_aggregation_query_request::QueryType::StructuredAggregationQuery(
-                StructuredAggregationQuery {
-                    aggregations: params.aggregations.iter().map(|agg| agg.into()).collect(),
-                    query_type: Some(gcloud_sdk::google::firestore::v1::structured_aggregation_query::QueryType::StructuredQuery(params.query_params.into())),
-                }
-            )),
+            query_type: Some(
+                run_aggregation_query_request::QueryType::StructuredAggregationQuery(params.into()),
+            ),
         }))
     }
 
     fn stream_aggregated_query_doc_with_retries<'a, 'b>(
         &'a self,
         params: FirestoreAggregatedQueryParams,
-        retries: usize,
+        mut retry_state: FirestoreRetryState,
         span: &'a Span,
     ) -> BoxFuture<'a, FirestoreResult<BoxStream<'b, FirestoreResult<Option<Document>>>>> {
         async move {
@@ -336,22 +495,14 @@ impl FirestoreDb {
 
                     Ok(query_stream)
                 }
-                Err(err) => match err {
-                    FirestoreError::DatabaseError(ref db_err)
-                        if db_err.retry_possible && retries < self.inner.options.max_retries =>
-                    {
-                        warn!(
-                            "Failed with {}. Retrying: {}/{}",
-                            db_err,
-                            retries + 1,
-                            self.inner.options.max_retries
-                        );
-
-                        self.stream_aggregated_query_doc_with_retries(params, retries + 1, span)
+                Err(err) => {
+                    if retry_state.wait_retry(&err, span).await {
+                        self.stream_aggregated_query_doc_with_retries(params, retry_state, span)
                             .await
+                    } else {
+                        Err(err)
                     }
-                    _ => Err(err),
-                },
+                }
             }
         }
         .boxed()
@@ -360,7 +511,7 @@ impl FirestoreDb {
     fn aggregated_query_doc_with_retries<'a>(
         &'a self,
         params: FirestoreAggregatedQueryParams,
-        retries: usize,
+        mut retry_state: FirestoreRetryState,
         span: &'a Span,
     ) -> BoxFuture<'a, FirestoreResult<Vec<Document>>> {
         async move {
@@ -400,21 +551,14 @@ impl FirestoreDb {
 
                     Ok(query_stream)
                 }
-                Err(err) => match err {
-                    FirestoreError::DatabaseError(ref db_err)
-                        if db_err.retry_possible && retries < self.inner.options.max_retries =>
-                    {
-                        warn!(
-                            "Failed with {}. Retrying: {}/{}",
-                            db_err,
-                            retries + 1,
-                            self.inner.options.max_retries
-                        );
-                        self.aggregated_query_doc_with_retries(params, retries + 1, span)
+                Err(err) => {
+                    if retry_state.wait_retry(&err, span).await {
+                        self.aggregated_query_doc_with_retries(params, retry_state, span)
                             .await
+                    } else {
+                        Err(err)
                     }
-                    _ => Err(err),
-                },
+                }
             }
         }
         .boxed()
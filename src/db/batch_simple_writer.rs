@@ -1,10 +1,9 @@
 use crate::errors::*;
 use crate::{
-    FirestoreBatch, FirestoreBatchWriteResponse, FirestoreBatchWriter, FirestoreDb,
-    FirestoreResult, FirestoreWriteResult,
+    retry_with_policy, FirestoreBatch, FirestoreBatchWriteResponse, FirestoreBatchWriter,
+    FirestoreDb, FirestoreResult, FirestoreWriteResult,
 };
 use async_trait::async_trait;
-use futures::TryFutureExt;
 use gcloud_sdk::google::firestore::v1::{BatchWriteRequest, Write};
 use rsb_derive::*;
 use std::collections::HashMap;
@@ -45,14 +44,13 @@ impl FirestoreBatchWriter for FirestoreSimpleBatchWriter {
     type WriteResult = FirestoreBatchWriteResponse;
 
     async fn write(&self, writes: Vec<Write>) -> FirestoreResult<FirestoreBatchWriteResponse> {
-        let backoff = backoff::ExponentialBackoffBuilder::new()
-            .with_max_elapsed_time(
-                self.options
-                    .retry_max_elapsed_time
-                    .map(|v| v.to_std())
-                    .transpose()?,
-            )
-            .build();
+        let retry_policy = match self.options.retry_max_elapsed_time {
+            Some(max_elapsed_time) => self
+                .db
+                .get_retry_policy()
+                .with_max_elapsed_time(max_elapsed_time.to_std()?),
+            None => self.db.get_retry_policy(),
+        };
 
         let request = BatchWriteRequest {
             database: self.db.get_database_path().to_string(),
@@ -60,31 +58,28 @@ impl FirestoreBatchWriter for FirestoreSimpleBatchWriter {
             labels: HashMap::new(),
         };
 
-        backoff::future::retry(backoff, || {
-            async {
-                let response = self
-                    .db
-                    .client()
-                    .get()
-                    .batch_write(request.clone())
-                    .await
-                    .map_err(FirestoreError::from)?;
+        retry_with_policy(&retry_policy, &self.batch_span, || async {
+            let response = self
+                .db
+                .client()
+                .get()
+                .batch_write(self.db.create_request(request.clone()))
+                .await
+                .map_err(FirestoreError::from)?;
 
-                let batch_response = response.into_inner();
+            let batch_response = response.into_inner();
 
-                let write_results: FirestoreResult<Vec<FirestoreWriteResult>> = batch_response
-                    .write_results
-                    .into_iter()
-                    .map(|s| s.try_into())
-                    .collect();
+            let write_results: FirestoreResult<Vec<FirestoreWriteResult>> = batch_response
+                .write_results
+                .into_iter()
+                .map(|s| s.try_into())
+                .collect();
 
-                Ok(FirestoreBatchWriteResponse::new(
-                    0,
-                    write_results?,
-                    batch_response.status,
-                ))
-            }
-            .map_err(firestore_err_to_backoff)
+            Ok(FirestoreBatchWriteResponse::new(
+                0,
+                write_results?,
+                batch_response.status,
+            ))
         })
         .await
     }
@@ -1,4 +1,7 @@
-use crate::db::transaction_ops::{TransformObjectOperation, UpdateObjectOperation};
+use crate::db::transaction_ops::{
+    object_merge_field_paths, CreateDocumentOperation, TransformObjectOperation,
+    UpdateObjectOperation,
+};
 use crate::db::DeleteOperation;
 use crate::errors::FirestoreError;
 use crate::{
@@ -7,7 +10,7 @@ use crate::{
 };
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use gcloud_sdk::google::firestore::v1::Write;
+use gcloud_sdk::google::firestore::v1::{Document, Write};
 use gcloud_sdk::google::rpc::Status;
 use rsb_derive::*;
 use serde::Serialize;
@@ -111,6 +114,180 @@ where
         })
     }
 
+    /// Creates the document, failing if it already exists.
+    pub fn create_doc<S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        document: Document,
+    ) -> FirestoreResult<&mut Self>
+    where
+        S: AsRef<str>,
+    {
+        self.create_doc_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            document,
+        )
+    }
+
+    pub fn create_doc_at<S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        document: Document,
+    ) -> FirestoreResult<&mut Self>
+    where
+        S: AsRef<str>,
+    {
+        self.add(CreateDocumentOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            document,
+        })
+    }
+
+    /// Same as `create_doc` for a serializable object.
+    pub fn create_object<T, S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.create_object_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            obj,
+            update_transforms,
+        )
+    }
+
+    pub fn create_object_at<T, S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.add(UpdateObjectOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            obj,
+            update_only: None,
+            precondition: Some(FirestoreWritePrecondition::Exists(false)),
+            update_transforms,
+        })
+    }
+
+    /// Creates or replaces the whole document.
+    pub fn set_object<T, S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.set_object_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            obj,
+            precondition,
+            update_transforms,
+        )
+    }
+
+    pub fn set_object_at<T, S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.update_object_at(
+            parent,
+            collection_id,
+            document_id,
+            obj,
+            None,
+            precondition,
+            update_transforms,
+        )
+    }
+
+    /// Creates or updates the document with only the fields present in the object.
+    pub fn set_object_merge<T, S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.set_object_merge_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            obj,
+            precondition,
+            update_transforms,
+        )
+    }
+
+    pub fn set_object_merge_at<T, S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.add(UpdateObjectOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            obj,
+            update_only: Some(object_merge_field_paths(obj)?),
+            precondition,
+            update_transforms,
+        })
+    }
+
     pub fn delete_by_id<S>(
         &mut self,
         collection_id: &str,
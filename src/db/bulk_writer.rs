@@ -0,0 +1,678 @@
+use crate::db::transaction_ops::{DeleteOperation, UpdateObjectOperation};
+use crate::errors::*;
+use crate::{
+    FirestoreDb, FirestoreFieldTransform, FirestoreResult, FirestoreRetryPolicy,
+    FirestoreWritePrecondition,
+};
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
+use gcloud_sdk::google::firestore::v1::{write, BatchWriteRequest, Write};
+use gcloud_sdk::google::rpc::Status;
+use rsb_derive::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::*;
+
+/// The throughput starts at `initial_ops_per_second` and grows by `ramp_up_percent`
+/// every `ramp_up_interval`, following the Firestore 500/50/5 rule.
+#[derive(Debug, PartialEq, Clone, Builder)]
+pub struct FirestoreBulkWriterOptions {
+    #[default = "500"]
+    pub initial_ops_per_second: u32,
+
+    pub max_ops_per_second: Option<u32>,
+
+    #[default = "50"]
+    pub ramp_up_percent: u32,
+
+    #[default = "Duration::from_secs(5 * 60)"]
+    pub ramp_up_interval: Duration,
+
+    #[default = "20"]
+    pub max_batch_size: usize,
+
+    #[default = "10"]
+    pub max_in_flight_batches: usize,
+
+    /// Adding writes waits when the queue is full.
+    #[default = "1000"]
+    pub max_queued_writes: usize,
+
+    /// Used for the whole requests and the failed writes. Defaults to the database retry policy.
+    pub retry_policy: Option<FirestoreRetryPolicy>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FirestoreBulkWriterSummary {
+    pub succeeded: usize,
+    pub failures: Vec<FirestoreBulkWriteFailure>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FirestoreBulkWriteFailure {
+    pub write: Write,
+    pub status: Status,
+    pub attempts: usize,
+}
+
+impl FirestoreBulkWriteFailure {
+    /// The write was rejected by its `exists = false` precondition because the document already exists.
+    /// The copier and the importer report these writes as skipped when they are asked to keep the existing documents.
+    pub fn is_existing_document(&self) -> bool {
+        self.status.code == gcloud_sdk::tonic::Code::AlreadyExists as i32
+    }
+}
+
+pub struct FirestoreBulkWriter {
+    pub db: FirestoreDb,
+    pub options: FirestoreBulkWriterOptions,
+    sender: mpsc::Sender<Write>,
+    worker: JoinHandle<FirestoreBulkWriterSummary>,
+}
+
+impl FirestoreBulkWriter {
+    pub async fn new(
+        db: FirestoreDb,
+        options: FirestoreBulkWriterOptions,
+    ) -> FirestoreResult<FirestoreBulkWriter> {
+        let (sender, receiver) = mpsc::channel(options.max_queued_writes.max(1));
+        let worker = tokio::spawn(Self::run_worker(db.clone(), options.clone(), receiver));
+
+        Ok(Self {
+            db,
+            options,
+            sender,
+            worker,
+        })
+    }
+
+    pub async fn add<I>(&self, write: I) -> FirestoreResult<()>
+    where
+        I: TryInto<Write, Error = FirestoreError>,
+    {
+        self.add_write(write.try_into()?).await
+    }
+
+    pub(crate) async fn add_write(&self, write: Write) -> FirestoreResult<()> {
+        Ok(self.sender.send(write).await?)
+    }
+
+    pub async fn update_object<T, S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        update_only: Option<Vec<String>>,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<()>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.update_object_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            obj,
+            update_only,
+            precondition,
+            update_transforms,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_object_at<T, S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        update_only: Option<Vec<String>>,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<()>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.add(UpdateObjectOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            obj,
+            update_only,
+            precondition,
+            update_transforms,
+        })
+        .await
+    }
+
+    pub async fn delete_by_id<S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> FirestoreResult<()>
+    where
+        S: AsRef<str>,
+    {
+        self.delete_by_id_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            precondition,
+        )
+        .await
+    }
+
+    pub async fn delete_by_id_at<S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> FirestoreResult<()>
+    where
+        S: AsRef<str>,
+    {
+        self.add(DeleteOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            precondition,
+        })
+        .await
+    }
+
+    /// Waits for all the queued writes to be completed.
+    pub async fn finish(self) -> FirestoreResult<FirestoreBulkWriterSummary> {
+        drop(self.sender);
+        self.worker.await.map_err(|err| {
+            FirestoreError::SystemError(FirestoreSystemError::new(
+                FirestoreErrorPublicGenericDetails::new("BULK_WRITER_ERROR".into()),
+                format!("Bulk writer failed: {err}"),
+            ))
+        })
+    }
+
+    async fn run_worker(
+        db: FirestoreDb,
+        options: FirestoreBulkWriterOptions,
+        mut receiver: mpsc::Receiver<Write>,
+    ) -> FirestoreBulkWriterSummary {
+        let span = span!(Level::DEBUG, "Firestore Bulk Write");
+        let retry_policy = options
+            .retry_policy
+            .clone()
+            .unwrap_or_else(|| db.get_retry_policy());
+        let mut summary = FirestoreBulkWriterSummary::default();
+        let max_in_flight_batches = options.max_in_flight_batches.max(1);
+        let in_flight = Arc::new(Semaphore::new(max_in_flight_batches));
+        let mut rate_limiter = FirestoreBulkWriterRateLimiter::new(&options, Instant::now());
+        let mut batches = FuturesUnordered::new();
+        let mut next_write: Option<Write> = None;
+
+        loop {
+            let first_write = match next_write.take() {
+                Some(write) => {
+                    // The same document is written again, so the previous writes must be applied first
+                    while let Some(batch_summary) = batches.next().await {
+                        summary.merge(batch_summary);
+                    }
+                    write
+                }
+                None => match receiver.recv().await {
+                    Some(write) => write,
+                    None => break,
+                },
+            };
+
+            let (writes, duplicate_write) =
+                collect_batch(first_write, &mut receiver, options.max_batch_size);
+            next_write = duplicate_write;
+
+            rate_limiter.acquire(writes.len()).await;
+
+            let permit = match in_flight.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+
+            let batch_db = db.clone();
+            let batch_retry_policy = retry_policy.clone();
+            let batch_span = span.clone();
+            let batch_writes = writes.clone();
+            batches.push(
+                tokio::spawn(async move {
+                    let result = write_batch_with_retries(
+                        &batch_retry_policy,
+                        writes,
+                        &batch_span,
+                        |writes| batch_write(&batch_db, writes),
+                    )
+                    .await;
+                    drop(permit);
+                    result
+                })
+                .map(move |result| {
+                    result.unwrap_or_else(|err| {
+                        error!("Bulk writer batch failed: {}", err);
+                        FirestoreBulkWriterSummary::failed(
+                            batch_writes,
+                            Status {
+                                code: gcloud_sdk::tonic::Code::Internal as i32,
+                                message: format!("Bulk writer batch failed: {err}"),
+                                details: vec![],
+                            },
+                            0,
+                        )
+                    })
+                }),
+            );
+
+            while let Some(Some(batch_summary)) = batches.next().now_or_never() {
+                summary.merge(batch_summary);
+            }
+        }
+
+        while let Some(batch_summary) = batches.next().await {
+            summary.merge(batch_summary);
+        }
+
+        span.in_scope(|| {
+            debug!(
+                "Bulk write finished with {} succeeded and {} failed writes",
+                summary.succeeded,
+                summary.failures.len()
+            );
+        });
+        summary
+    }
+}
+
+impl FirestoreBulkWriterSummary {
+    fn merge(&mut self, other: FirestoreBulkWriterSummary) {
+        self.succeeded += other.succeeded;
+        self.failures.extend(other.failures);
+    }
+
+    fn failed(writes: Vec<Write>, status: Status, attempts: usize) -> Self {
+        Self {
+            succeeded: 0,
+            failures: writes
+                .into_iter()
+                .map(|write| FirestoreBulkWriteFailure {
+                    write,
+                    status: status.clone(),
+                    attempts,
+                })
+                .collect(),
+        }
+    }
+}
+
+// Collects the queued writes for a batch until the document of a write is already in the batch,
+// since a batch can't write the same document twice. That write is returned separately.
+fn collect_batch(
+    first_write: Write,
+    receiver: &mut mpsc::Receiver<Write>,
+    max_batch_size: usize,
+) -> (Vec<Write>, Option<Write>) {
+    let mut document_names: HashSet<String> =
+        write_document_name(&first_write).into_iter().collect();
+    let mut writes = vec![first_write];
+    while writes.len() < max_batch_size {
+        match receiver.try_recv() {
+            Ok(write) => {
+                if let Some(document_name) = write_document_name(&write) {
+                    if !document_names.insert(document_name) {
+                        return (writes, Some(write));
+                    }
+                }
+                writes.push(write);
+            }
+            Err(_) => break,
+        }
+    }
+    (writes, None)
+}
+
+fn write_document_name(write: &Write) -> Option<String> {
+    match write.operation.as_ref()? {
+        write::Operation::Update(document) => Some(document.name.clone()),
+        write::Operation::Delete(document_name) => Some(document_name.clone()),
+        write::Operation::Transform(transform) => Some(transform.document.clone()),
+    }
+}
+
+// The request errors are reported as the statuses of all the writes
+async fn batch_write(db: &FirestoreDb, writes: Vec<Write>) -> Vec<Status> {
+    let writes_len = writes.len();
+    let request = BatchWriteRequest {
+        database: db.get_database_path().to_string(),
+        writes,
+        labels: HashMap::new(),
+    };
+
+    match db
+        .client()
+        .get()
+        .batch_write(db.create_request(request))
+        .await
+    {
+        Ok(response) => response.into_inner().status,
+        Err(err) => vec![
+            Status {
+                code: err.code() as i32,
+                message: err.message().to_string(),
+                details: vec![],
+            };
+            writes_len
+        ],
+    }
+}
+
+async fn write_batch_with_retries<FN, F>(
+    retry_policy: &FirestoreRetryPolicy,
+    writes: Vec<Write>,
+    span: &Span,
+    mut batch_write: FN,
+) -> FirestoreBulkWriterSummary
+where
+    FN: FnMut(Vec<Write>) -> F,
+    F: Future<Output = Vec<Status>>,
+{
+    let mut summary = FirestoreBulkWriterSummary::default();
+    let mut retry_state = retry_policy.new_state();
+    let mut pending = writes;
+    let mut attempts = 0;
+
+    while !pending.is_empty() {
+        attempts += 1;
+        let statuses = batch_write(pending.clone()).await;
+
+        let mut retryable = Vec::new();
+        let mut retry_error: Option<FirestoreError> = None;
+        for (index, write) in pending.into_iter().enumerate() {
+            let status = statuses.get(index).cloned().unwrap_or_else(|| Status {
+                code: gcloud_sdk::tonic::Code::Unknown as i32,
+                message: "No write status in the response".to_string(),
+                details: vec![],
+            });
+
+            if status.code == gcloud_sdk::tonic::Code::Ok as i32 {
+                summary.succeeded += 1;
+                continue;
+            }
+
+            let err: FirestoreError = gcloud_sdk::tonic::Status::new(
+                gcloud_sdk::tonic::Code::from_i32(status.code),
+                status.message.clone(),
+            )
+            .into();
+            if retry_policy.classifier.is_retryable(&err) {
+                retryable.push((write, status));
+                retry_error.get_or_insert(err);
+            } else {
+                summary.failures.push(FirestoreBulkWriteFailure {
+                    write,
+                    status,
+                    attempts,
+                });
+            }
+        }
+
+        pending = match retry_error {
+            Some(err) if retry_state.wait_retry(&err, span).await => {
+                retryable.into_iter().map(|(write, _)| write).collect()
+            }
+            _ => {
+                summary
+                    .failures
+                    .extend(retryable.into_iter().map(|(write, status)| {
+                        FirestoreBulkWriteFailure {
+                            write,
+                            status,
+                            attempts,
+                        }
+                    }));
+                vec![]
+            }
+        };
+    }
+
+    summary
+}
+
+struct FirestoreBulkWriterRateLimiter {
+    initial_ops_per_second: f64,
+    max_ops_per_second: Option<f64>,
+    ramp_up_multiplier: f64,
+    ramp_up_interval: Duration,
+    started: Instant,
+    available_ops: f64,
+    last_refill: Instant,
+}
+
+impl FirestoreBulkWriterRateLimiter {
+    fn new(options: &FirestoreBulkWriterOptions, now: Instant) -> Self {
+        let initial_ops_per_second = options.initial_ops_per_second.max(1) as f64;
+        Self {
+            initial_ops_per_second,
+            max_ops_per_second: options.max_ops_per_second.map(|max| max.max(1) as f64),
+            ramp_up_multiplier: 1.0 + options.ramp_up_percent as f64 / 100.0,
+            ramp_up_interval: options.ramp_up_interval,
+            started: now,
+            available_ops: initial_ops_per_second,
+            last_refill: now,
+        }
+    }
+
+    fn ops_per_second(&self, now: Instant) -> f64 {
+        let ramp_ups = if self.ramp_up_interval.is_zero() {
+            0
+        } else {
+            (now.duration_since(self.started).as_secs_f64() / self.ramp_up_interval.as_secs_f64())
+                .floor() as i32
+        };
+        let ops_per_second = self.initial_ops_per_second * self.ramp_up_multiplier.powi(ramp_ups);
+        match self.max_ops_per_second {
+            Some(max) => ops_per_second.min(max),
+            None => ops_per_second,
+        }
+    }
+
+    async fn acquire(&mut self, ops: usize) {
+        loop {
+            let now = Instant::now();
+            let ops_per_second = self.ops_per_second(now);
+            self.available_ops = (self.available_ops
+                + now.duration_since(self.last_refill).as_secs_f64() * ops_per_second)
+                .min(ops_per_second);
+            self.last_refill = now;
+
+            // Batches bigger than the rate are let through once the bucket is full
+            let required_ops = (ops as f64).min(ops_per_second);
+            if self.available_ops >= required_ops {
+                self.available_ops -= ops as f64;
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_secs_f64(
+                (required_ops - self.available_ops) / ops_per_second,
+            ))
+            .await;
+        }
+    }
+}
+
+impl FirestoreDb {
+    pub async fn create_bulk_writer(&self) -> FirestoreResult<FirestoreBulkWriter> {
+        self.create_bulk_writer_with_options(FirestoreBulkWriterOptions::new())
+            .await
+    }
+
+    pub async fn create_bulk_writer_with_options(
+        &self,
+        options: FirestoreBulkWriterOptions,
+    ) -> FirestoreResult<FirestoreBulkWriter> {
+        FirestoreBulkWriter::new(self.clone(), options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_document_failure() {
+        let failure = |code: gcloud_sdk::tonic::Code| FirestoreBulkWriteFailure {
+            write: Write::default(),
+            status: Status {
+                code: code as i32,
+                ..Default::default()
+            },
+            attempts: 1,
+        };
+
+        assert!(failure(gcloud_sdk::tonic::Code::AlreadyExists).is_existing_document());
+        assert!(!failure(gcloud_sdk::tonic::Code::FailedPrecondition).is_existing_document());
+        assert!(!failure(gcloud_sdk::tonic::Code::NotFound).is_existing_document());
+    }
+
+    #[test]
+    fn rate_limiter_ramp_up() {
+        let started = Instant::now();
+        let limiter = FirestoreBulkWriterRateLimiter::new(
+            &FirestoreBulkWriterOptions::new().with_max_ops_per_second(1000),
+            started,
+        );
+
+        assert_eq!(limiter.ops_per_second(started), 500.0);
+        assert_eq!(
+            limiter.ops_per_second(started + Duration::from_secs(4 * 60)),
+            500.0
+        );
+        assert_eq!(
+            limiter.ops_per_second(started + Duration::from_secs(5 * 60)),
+            750.0
+        );
+        assert_eq!(
+            limiter.ops_per_second(started + Duration::from_secs(10 * 60)),
+            1000.0
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_waits_for_capacity() {
+        let mut limiter = FirestoreBulkWriterRateLimiter::new(
+            &FirestoreBulkWriterOptions::new().with_initial_ops_per_second(1000),
+            Instant::now(),
+        );
+
+        let started = Instant::now();
+        limiter.acquire(1000).await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+        limiter.acquire(100).await;
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+
+    fn delete_write(document_name: &str) -> Write {
+        Write {
+            operation: Some(write::Operation::Delete(document_name.to_string())),
+            ..Default::default()
+        }
+    }
+
+    fn status(code: gcloud_sdk::tonic::Code) -> Status {
+        Status {
+            code: code as i32,
+            message: format!("{code:?}"),
+            details: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn write_batch_retries_only_failed_writes() {
+        let retry_policy =
+            FirestoreRetryPolicy::new().with_initial_interval(Duration::from_millis(1));
+        let mut requests: Vec<Vec<Write>> = vec![];
+
+        let summary = write_batch_with_retries(
+            &retry_policy,
+            vec![
+                delete_write("doc-1"),
+                delete_write("doc-2"),
+                delete_write("doc-3"),
+            ],
+            &Span::none(),
+            |writes| {
+                requests.push(writes);
+                let statuses = if requests.len() == 1 {
+                    vec![
+                        status(gcloud_sdk::tonic::Code::Ok),
+                        status(gcloud_sdk::tonic::Code::Unavailable),
+                        status(gcloud_sdk::tonic::Code::NotFound),
+                    ]
+                } else {
+                    vec![status(gcloud_sdk::tonic::Code::Ok)]
+                };
+                async move { statuses }
+            },
+        )
+        .await;
+
+        assert_eq!(
+            requests,
+            vec![
+                vec![
+                    delete_write("doc-1"),
+                    delete_write("doc-2"),
+                    delete_write("doc-3")
+                ],
+                vec![delete_write("doc-2")]
+            ]
+        );
+        assert_eq!(summary.succeeded, 2);
+        assert_eq!(
+            summary.failures,
+            vec![FirestoreBulkWriteFailure {
+                write: delete_write("doc-3"),
+                status: status(gcloud_sdk::tonic::Code::NotFound),
+                attempts: 1,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn collect_batch_splits_on_same_document() {
+        let (sender, mut receiver) = mpsc::channel(10);
+        for document_name in ["doc-2", "doc-3", "doc-1", "doc-4"] {
+            sender.send(delete_write(document_name)).await.unwrap();
+        }
+
+        assert_eq!(
+            collect_batch(delete_write("doc-1"), &mut receiver, 10),
+            (
+                vec![
+                    delete_write("doc-1"),
+                    delete_write("doc-2"),
+                    delete_write("doc-3")
+                ],
+                Some(delete_write("doc-1"))
+            )
+        );
+        assert_eq!(
+            collect_batch(delete_write("doc-5"), &mut receiver, 10),
+            (vec![delete_write("doc-5"), delete_write("doc-4")], None)
+        );
+    }
+}
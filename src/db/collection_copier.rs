@@ -0,0 +1,407 @@
+use crate::{
+    FirestoreBulkWriteFailure, FirestoreBulkWriter, FirestoreBulkWriterOptions, FirestoreDb,
+    FirestoreListCollectionIdsParams, FirestoreListDocParams, FirestoreListingSupport,
+    FirestorePartitionQueryParams, FirestoreQueryParams, FirestoreQuerySupport, FirestoreResult,
+};
+use futures::TryStreamExt;
+use gcloud_sdk::google::firestore::v1::{
+    precondition, value, write, Document, Precondition, Value, Write,
+};
+use rsb_derive::*;
+use std::collections::{HashSet, VecDeque};
+use tracing::*;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FirestoreCopyExistingPolicy {
+    Overwrite,
+    Skip,
+}
+
+#[derive(Debug, PartialEq, Clone, Builder)]
+pub struct FirestoreCollectionCopierOptions {
+    /// Copies the subcollections of the copied documents as well.
+    #[default = "false"]
+    pub recursive: bool,
+
+    #[default = "FirestoreCopyExistingPolicy::Overwrite"]
+    pub existing_documents: FirestoreCopyExistingPolicy,
+
+    /// Deletes the successfully copied source documents, which turns the copy into a move.
+    /// Only the copied documents are deleted, so without `recursive` the subcollections
+    /// of the moved documents stay in the source.
+    #[default = "false"]
+    pub delete_after_copy: bool,
+
+    #[default = "2"]
+    pub parallelism: usize,
+
+    #[default = "4"]
+    pub partition_count: u32,
+
+    #[default = "100"]
+    pub page_size: u32,
+
+    pub bulk_writer_options: Option<FirestoreBulkWriterOptions>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FirestoreCollectionCopyResult {
+    pub copied_documents: usize,
+    pub skipped_documents: usize,
+    pub deleted_documents: usize,
+    pub failures: Vec<FirestoreBulkWriteFailure>,
+}
+
+/// Copies or moves collection trees between parents and databases.
+/// Document names and `FirestoreReference` values pointing into the copied tree
+/// are rewritten to the new location. References to other documents are moved
+/// to the target database when the databases are different.
+pub struct FirestoreCollectionCopier {
+    pub source_db: FirestoreDb,
+    pub target_db: FirestoreDb,
+    pub options: FirestoreCollectionCopierOptions,
+}
+
+impl FirestoreCollectionCopier {
+    pub fn new(
+        source_db: FirestoreDb,
+        target_db: FirestoreDb,
+        options: FirestoreCollectionCopierOptions,
+    ) -> Self {
+        Self {
+            source_db,
+            target_db,
+            options,
+        }
+    }
+
+    pub async fn copy_collection(
+        &self,
+        collection_id: &str,
+        target_collection_id: &str,
+    ) -> FirestoreResult<FirestoreCollectionCopyResult> {
+        self.copy_collection_at(
+            self.source_db.get_documents_path(),
+            collection_id,
+            self.target_db.get_documents_path(),
+            target_collection_id,
+        )
+        .await
+    }
+
+    pub async fn copy_collection_at(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        target_parent: &str,
+        target_collection_id: &str,
+    ) -> FirestoreResult<FirestoreCollectionCopyResult> {
+        let span = span!(
+            Level::DEBUG,
+            "Firestore Collection Copy",
+            "/firestore/collection_name" = collection_id,
+            "/firestore/target_collection_name" = target_collection_id
+        );
+
+        let mapper = FirestoreDocumentNameMapper {
+            source_root: format!("{parent}/{collection_id}"),
+            target_root: format!("{target_parent}/{target_collection_id}"),
+            source_documents_path: self.source_db.get_documents_path().clone(),
+            target_documents_path: self.target_db.get_documents_path().clone(),
+        };
+
+        let bulk_writer = self.create_bulk_writer(&self.target_db).await?;
+
+        let mut copied: Vec<(String, String)> = Vec::new();
+        let mut collections: VecDeque<(String, String)> =
+            VecDeque::from([(parent.to_string(), collection_id.to_string())]);
+
+        while let Some((parent, collection_id)) = collections.pop_front() {
+            let mut docs = self
+                .source_db
+                .stream_partition_query_doc_with_errors(
+                    self.options.parallelism,
+                    FirestorePartitionQueryParams::new(
+                        FirestoreQueryParams::new(collection_id.as_str().into())
+                            .with_parent(parent.clone()),
+                        self.options.partition_count,
+                        self.options.page_size,
+                    ),
+                )
+                .await?;
+
+            while let Some((_, doc)) = docs.try_next().await? {
+                let source_name = doc.name.clone();
+                let target_doc = mapper.map_document(doc);
+                let target_name = target_doc.name.clone();
+                bulk_writer
+                    .add_write(Write {
+                        operation: Some(write::Operation::Update(target_doc)),
+                        current_document: match self.options.existing_documents {
+                            FirestoreCopyExistingPolicy::Overwrite => None,
+                            FirestoreCopyExistingPolicy::Skip => Some(Precondition {
+                                condition_type: Some(precondition::ConditionType::Exists(false)),
+                            }),
+                        },
+                        ..Default::default()
+                    })
+                    .await?;
+                copied.push((source_name, target_name));
+            }
+
+            if self.options.recursive {
+                // Missing documents aren't returned by queries, but may still have subcollections
+                let document_paths: Vec<String> = self
+                    .source_db
+                    .stream_list_doc_with_errors(
+                        FirestoreListDocParams::new(collection_id)
+                            .with_parent(parent)
+                            .with_page_size(self.options.page_size as usize)
+                            .with_return_only_fields(vec![])
+                            .with_show_missing(true),
+                    )
+                    .await?
+                    .map_ok(|doc| doc.name)
+                    .try_collect()
+                    .await?;
+
+                for document_path in document_paths {
+                    let mut collection_ids = self
+                        .source_db
+                        .stream_list_collection_ids_with_errors(
+                            FirestoreListCollectionIdsParams::new()
+                                .with_parent(document_path.clone()),
+                        )
+                        .await?;
+                    while let Some(collection_id) = collection_ids.try_next().await? {
+                        collections.push_back((document_path.clone(), collection_id));
+                    }
+                }
+            }
+        }
+
+        let summary = bulk_writer.finish().await?;
+
+        let mut result = FirestoreCollectionCopyResult {
+            copied_documents: summary.succeeded,
+            ..Default::default()
+        };
+        let mut not_copied: HashSet<String> = HashSet::new();
+        for failure in summary.failures {
+            if let Some(write::Operation::Update(doc)) = &failure.write.operation {
+                not_copied.insert(doc.name.clone());
+            }
+            if self.options.existing_documents == FirestoreCopyExistingPolicy::Skip
+                && failure.is_existing_document()
+            {
+                result.skipped_documents += 1;
+            } else {
+                result.failures.push(failure);
+            }
+        }
+
+        if self.options.delete_after_copy {
+            let delete_writer = self.create_bulk_writer(&self.source_db).await?;
+            for (source_name, target_name) in copied {
+                if !not_copied.contains(&target_name) {
+                    delete_writer
+                        .add_write(Write {
+                            operation: Some(write::Operation::Delete(source_name)),
+                            ..Default::default()
+                        })
+                        .await?;
+                }
+            }
+            let delete_summary = delete_writer.finish().await?;
+            result.deleted_documents = delete_summary.succeeded;
+            result.failures.extend(delete_summary.failures);
+        }
+
+        span.in_scope(|| {
+            debug!(
+                "Copied {} documents, skipped {}, deleted {}, {} failed",
+                result.copied_documents,
+                result.skipped_documents,
+                result.deleted_documents,
+                result.failures.len()
+            );
+        });
+
+        Ok(result)
+    }
+
+    async fn create_bulk_writer(&self, db: &FirestoreDb) -> FirestoreResult<FirestoreBulkWriter> {
+        db.create_bulk_writer_with_options(
+            self.options
+                .bulk_writer_options
+                .clone()
+                .unwrap_or_else(FirestoreBulkWriterOptions::new),
+        )
+        .await
+    }
+}
+
+struct FirestoreDocumentNameMapper {
+    source_root: String,
+    target_root: String,
+    source_documents_path: String,
+    target_documents_path: String,
+}
+
+impl FirestoreDocumentNameMapper {
+    fn map_name(&self, name: &str) -> String {
+        if let Some(rest) = strip_path_prefix(name, &self.source_root) {
+            format!("{}{}", self.target_root, rest)
+        } else if let Some(rest) = strip_path_prefix(name, &self.source_documents_path) {
+            format!("{}{}", self.target_documents_path, rest)
+        } else {
+            name.to_string()
+        }
+    }
+
+    fn map_document(&self, doc: Document) -> Document {
+        Document {
+            name: self.map_name(&doc.name),
+            fields: doc
+                .fields
+                .into_iter()
+                .map(|(field_name, field_value)| (field_name, self.map_value(field_value)))
+                .collect(),
+            create_time: None,
+            update_time: None,
+        }
+    }
+
+    fn map_value(&self, value: Value) -> Value {
+        let value_type = match value.value_type {
+            Some(value::ValueType::ReferenceValue(reference)) => {
+                Some(value::ValueType::ReferenceValue(self.map_name(&reference)))
+            }
+            Some(value::ValueType::ArrayValue(mut array_value)) => {
+                array_value.values = array_value
+                    .values
+                    .into_iter()
+                    .map(|value| self.map_value(value))
+                    .collect();
+                Some(value::ValueType::ArrayValue(array_value))
+            }
+            Some(value::ValueType::MapValue(mut map_value)) => {
+                map_value.fields = map_value
+                    .fields
+                    .into_iter()
+                    .map(|(field_name, field_value)| (field_name, self.map_value(field_value)))
+                    .collect();
+                Some(value::ValueType::MapValue(map_value))
+            }
+            other => other,
+        };
+        Value { value_type }
+    }
+}
+
+fn strip_path_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    path.strip_prefix(prefix)
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+impl FirestoreDb {
+    pub fn create_collection_copier(&self, target_db: &FirestoreDb) -> FirestoreCollectionCopier {
+        self.create_collection_copier_with_options(
+            target_db,
+            FirestoreCollectionCopierOptions::new(),
+        )
+    }
+
+    pub fn create_collection_copier_with_options(
+        &self,
+        target_db: &FirestoreDb,
+        options: FirestoreCollectionCopierOptions,
+    ) -> FirestoreCollectionCopier {
+        FirestoreCollectionCopier::new(self.clone(), target_db.clone(), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gcloud_sdk::google::firestore::v1::{ArrayValue, MapValue};
+
+    fn reference(name: &str) -> Value {
+        Value {
+            value_type: Some(value::ValueType::ReferenceValue(name.to_string())),
+        }
+    }
+
+    #[test]
+    fn mapper_rewrites_names_and_references() {
+        let mapper = FirestoreDocumentNameMapper {
+            source_root: "projects/p/databases/(default)/documents/parents/p1/children".into(),
+            target_root: "projects/p/databases/copy/documents/children".into(),
+            source_documents_path: "projects/p/databases/(default)/documents".into(),
+            target_documents_path: "projects/p/databases/copy/documents".into(),
+        };
+
+        let doc = Document {
+            name: "projects/p/databases/(default)/documents/parents/p1/children/c1".into(),
+            fields: [
+                (
+                    "sibling".to_string(),
+                    reference("projects/p/databases/(default)/documents/parents/p1/children/c2"),
+                ),
+                (
+                    "nested".to_string(),
+                    Value {
+                        value_type: Some(value::ValueType::MapValue(MapValue {
+                            fields: [(
+                                "refs".to_string(),
+                                Value {
+                                    value_type: Some(value::ValueType::ArrayValue(ArrayValue {
+                                        values: vec![
+                                            reference("projects/p/databases/(default)/documents/other/o1"),
+                                            reference("projects/p/databases/(default)/documents/parents/p1/children2/c3"),
+                                        ],
+                                    })),
+                                },
+                            )]
+                            .into(),
+                        })),
+                    },
+                ),
+            ]
+            .into(),
+            create_time: None,
+            update_time: None,
+        };
+
+        let mapped = mapper.map_document(doc);
+        assert_eq!(
+            mapped.name,
+            "projects/p/databases/copy/documents/children/c1"
+        );
+        assert_eq!(
+            mapped.fields.get("sibling"),
+            Some(&reference(
+                "projects/p/databases/copy/documents/children/c2"
+            ))
+        );
+        assert_eq!(
+            mapped.fields.get("nested"),
+            Some(&Value {
+                value_type: Some(value::ValueType::MapValue(MapValue {
+                    fields: [(
+                        "refs".to_string(),
+                        Value {
+                            value_type: Some(value::ValueType::ArrayValue(ArrayValue {
+                                values: vec![
+                                    reference("projects/p/databases/copy/documents/other/o1"),
+                                    reference("projects/p/databases/copy/documents/parents/p1/children2/c3"),
+                                ],
+                            })),
+                        },
+                    )]
+                    .into(),
+                })),
+            })
+        );
+    }
+}
@@ -1,10 +1,24 @@
-use crate::{FirestoreDb, FirestoreResult};
+use crate::db::retry_with_policy;
+use crate::{FirestoreDb, FirestoreError, FirestoreResult, FirestoreRetryPolicy};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use gcloud_sdk::google::firestore::v1::*;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use tracing::*;
 
+const GENERATED_DOCUMENT_ID_LEN: usize = 20;
+
+/// Generates a random document ID the same way as Firestore does.
+pub(crate) fn generate_document_id() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(GENERATED_DOCUMENT_ID_LEN)
+        .map(char::from)
+        .collect()
+}
+
 #[async_trait]
 pub trait FirestoreCreateSupport {
     async fn create_doc<S>(
@@ -95,7 +109,7 @@ impl FirestoreCreateSupport for FirestoreDb {
             "/firestore/document_name" = field::Empty,
         );
 
-        let create_document_request = gcloud_sdk::tonic::Request::new(CreateDocumentRequest {
+        let create_document_request = CreateDocumentRequest {
             parent: parent.into(),
             document_id: document_id
                 .as_ref()
@@ -106,15 +120,28 @@ impl FirestoreCreateSupport for FirestoreDb {
             }),
             collection_id: collection_id.into(),
             document: Some(input_doc),
-        });
+        };
 
         let begin_query_utc: DateTime<Utc> = Utc::now();
 
-        let create_response = self
-            .client()
-            .get()
-            .create_document(create_document_request)
-            .await?;
+        // Without a document ID, a retry after a lost response would create another document
+        let retry_policy = if document_id.is_some() {
+            self.get_retry_policy()
+        } else {
+            FirestoreRetryPolicy::no_retries()
+        };
+
+        let create_response = retry_with_policy(&retry_policy, &span, || {
+            let request = self.create_request(create_document_request.clone());
+            async move {
+                self.client()
+                    .get()
+                    .create_document(request)
+                    .await
+                    .map_err(FirestoreError::from)
+            }
+        })
+        .await?;
 
         let end_query_utc: DateTime<Utc> = Utc::now();
         let query_duration = end_query_utc.signed_duration_since(begin_query_utc);
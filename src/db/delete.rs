@@ -1,5 +1,5 @@
 use crate::db::safe_document_path;
-use crate::{FirestoreDb, FirestoreResult, FirestoreWritePrecondition};
+use crate::{FirestoreDb, FirestoreError, FirestoreResult, FirestoreWritePrecondition};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use gcloud_sdk::google::firestore::v1::*;
@@ -67,13 +67,23 @@ impl FirestoreDeleteSupport for FirestoreDb {
             "/firestore/document_name" = document_path.as_str(),
         );
 
-        let request = gcloud_sdk::tonic::Request::new(DeleteDocumentRequest {
+        let delete_document_request = DeleteDocumentRequest {
             name: document_path,
             current_document: precondition.map(|cond| cond.try_into()).transpose()?,
-        });
+        };
 
         let begin_query_utc: DateTime<Utc> = Utc::now();
-        self.client().get().delete_document(request).await?;
+        self.with_retries(&span, || {
+            let request = self.create_request(delete_document_request.clone());
+            async move {
+                self.client()
+                    .get()
+                    .delete_document(request)
+                    .await
+                    .map_err(FirestoreError::from)
+            }
+        })
+        .await?;
         let end_query_utc: DateTime<Utc> = Utc::now();
         let query_duration = end_query_utc.signed_duration_since(begin_query_utc);
 
@@ -0,0 +1,206 @@
+use crate::db::query_ext_api::{ExecutionStats, ExplainMetrics, ExplainOptions, PlanSummary};
+use crate::errors::{
+    FirestoreError, FirestoreInvalidParametersError, FirestoreInvalidParametersPublicDetails,
+};
+use gcloud_sdk::prost_types::value::Kind;
+use gcloud_sdk::prost_types::Struct;
+use rsb_derive::Builder;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreExplainOptions {
+    /// Executes the query and returns the execution stats together with the results.
+    /// Otherwise only the query plan is returned.
+    #[default = "false"]
+    pub analyze: bool,
+}
+
+impl Default for FirestoreExplainOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<FirestoreExplainOptions> for ExplainOptions {
+    fn from(options: FirestoreExplainOptions) -> Self {
+        ExplainOptions {
+            analyze: options.analyze,
+        }
+    }
+}
+
+/// Query results with the explain metrics returned by Firestore.
+/// The results are empty unless the query was analyzed.
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreExplainResult<T> {
+    pub results: Vec<T>,
+    pub metrics: Option<FirestoreExplainMetrics>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreExplainMetrics {
+    pub plan_summary: Option<FirestorePlanSummary>,
+    pub execution_stats: Option<FirestoreExecutionStats>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestorePlanSummary {
+    pub indexes_used: Vec<FirestoreIndexUsed>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreIndexUsed {
+    pub query_scope: Option<String>,
+    pub properties: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreExecutionStats {
+    pub results_returned: i64,
+    pub execution_duration: Option<chrono::Duration>,
+    pub read_operations: i64,
+    pub documents_scanned: Option<i64>,
+    pub index_entries_scanned: Option<i64>,
+    /// Other debug stats with nested keys joined by dots, e.g. `billing_details.small_ops`.
+    pub debug_stats: BTreeMap<String, String>,
+}
+
+impl From<ExplainMetrics> for FirestoreExplainMetrics {
+    fn from(metrics: ExplainMetrics) -> Self {
+        FirestoreExplainMetrics {
+            plan_summary: metrics.plan_summary.map(|summary| summary.into()),
+            execution_stats: metrics.execution_stats.map(|stats| stats.into()),
+        }
+    }
+}
+
+impl From<PlanSummary> for FirestorePlanSummary {
+    fn from(summary: PlanSummary) -> Self {
+        FirestorePlanSummary {
+            indexes_used: summary
+                .indexes_used
+                .into_iter()
+                .map(|index| {
+                    let index_fields = flatten_struct(index);
+                    FirestoreIndexUsed {
+                        query_scope: index_fields.get("query_scope").cloned(),
+                        properties: index_fields.get("properties").cloned(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<ExecutionStats> for FirestoreExecutionStats {
+    fn from(stats: ExecutionStats) -> Self {
+        let debug_stats = stats.debug_stats.map(flatten_struct).unwrap_or_default();
+        FirestoreExecutionStats {
+            results_returned: stats.results_returned,
+            execution_duration: stats.execution_duration.map(|duration| {
+                chrono::Duration::seconds(duration.seconds)
+                    + chrono::Duration::nanoseconds(duration.nanos as i64)
+            }),
+            read_operations: stats.read_operations,
+            documents_scanned: debug_stats
+                .get("documents_scanned")
+                .and_then(|value| value.parse().ok()),
+            index_entries_scanned: debug_stats
+                .get("index_entries_scanned")
+                .and_then(|value| value.parse().ok()),
+            debug_stats,
+        }
+    }
+}
+
+pub(crate) fn explain_unsupported_error() -> FirestoreError {
+    FirestoreError::InvalidParametersError(FirestoreInvalidParametersError::new(
+        FirestoreInvalidParametersPublicDetails::new(
+            "explain_options".to_string(),
+            "Explaining queries isn't supported by this database".to_string(),
+        ),
+    ))
+}
+
+fn flatten_struct(value: Struct) -> BTreeMap<String, String> {
+    let mut result = BTreeMap::new();
+    flatten_struct_to("", value, &mut result);
+    result
+}
+
+fn flatten_struct_to(prefix: &str, value: Struct, result: &mut BTreeMap<String, String>) {
+    for (key, field_value) in value.fields {
+        let path = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match field_value.kind {
+            Some(Kind::StructValue(nested)) => flatten_struct_to(&path, nested, result),
+            Some(Kind::StringValue(str_value)) => {
+                result.insert(path, str_value);
+            }
+            Some(Kind::NumberValue(num_value)) => {
+                result.insert(path, num_value.to_string());
+            }
+            Some(Kind::BoolValue(bool_value)) => {
+                result.insert(path, bool_value.to_string());
+            }
+            Some(Kind::ListValue(list_value)) => {
+                result.insert(path, format!("{:?}", list_value.values));
+            }
+            Some(Kind::NullValue(_)) | None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gcloud_sdk::prost_types::Value;
+
+    fn string_value(value: &str) -> Value {
+        Value {
+            kind: Some(Kind::StringValue(value.to_string())),
+        }
+    }
+
+    #[test]
+    fn convert_execution_stats() {
+        let stats = ExecutionStats {
+            results_returned: 5,
+            execution_duration: Some(gcloud_sdk::prost_types::Duration {
+                seconds: 1,
+                nanos: 500_000_000,
+            }),
+            read_operations: 6,
+            debug_stats: Some(Struct {
+                fields: [
+                    ("documents_scanned".to_string(), string_value("20")),
+                    (
+                        "billing_details".to_string(),
+                        Value {
+                            kind: Some(Kind::StructValue(Struct {
+                                fields: [("small_ops".to_string(), string_value("0"))].into(),
+                            })),
+                        },
+                    ),
+                ]
+                .into(),
+            }),
+        };
+
+        let converted: FirestoreExecutionStats = stats.into();
+        assert_eq!(converted.results_returned, 5);
+        assert_eq!(
+            converted.execution_duration,
+            Some(chrono::Duration::milliseconds(1500))
+        );
+        assert_eq!(converted.documents_scanned, Some(20));
+        assert_eq!(converted.index_entries_scanned, None);
+        assert_eq!(
+            converted.debug_stats.get("billing_details.small_ops"),
+            Some(&"0".to_string())
+        );
+    }
+}
@@ -196,7 +196,7 @@ impl FirestoreGetByIdSupport for FirestoreDb {
             collection_id.to_string(),
             document_path,
             return_only_fields,
-            0,
+            self.get_retry_policy().new_state(),
         )
         .await
     }
@@ -550,8 +550,8 @@ impl FirestoreDb {
         collection_id: String,
         document_path: String,
         return_only_fields: Option<Vec<String>>,
-        retries: usize,
-    ) -> BoxFuture<FirestoreResult<Document>> {
+        mut retry_state: FirestoreRetryState,
+    ) -> BoxFuture<'_, FirestoreResult<Document>> {
         async move {
             #[cfg(feature = "caching")]
             {
@@ -578,7 +578,7 @@ impl FirestoreDb {
             );
             let begin_query_utc: DateTime<Utc> = Utc::now();
 
-            let request = gcloud_sdk::tonic::Request::new(GetDocumentRequest {
+            let request = self.create_request(GetDocumentRequest {
                 name: document_path.clone(),
                 consistency_selector: self
                     .session_params
@@ -586,7 +586,7 @@ impl FirestoreDb {
                     .as_ref()
                     .map(|selector| selector.try_into())
                     .transpose()?,
-                mask: return_only_fields.map({
+                mask: return_only_fields.clone().map({
                     |vf| gcloud_sdk::google::firestore::v1::DocumentMask {
                         field_paths: vf.iter().map(|f| f.to_string()).collect(),
                     }
@@ -625,23 +625,19 @@ impl FirestoreDb {
                     }
                     Ok(doc)
                 }
-                Err(err) => match err {
-                    FirestoreError::DatabaseError(ref db_err)
-                        if db_err.retry_possible && retries < self.get_options().max_retries =>
-                    {
-                        span.in_scope(|| {
-                            warn!(
-                                "Failed with {}. Retrying: {}/{}",
-                                db_err,
-                                retries + 1,
-                                self.get_options().max_retries
-                            );
-                        });
-                        self.get_doc_by_path(collection_id, document_path, None, retries + 1)
-                            .await
+                Err(err) => {
+                    if retry_state.wait_retry(&err, &span).await {
+                        self.get_doc_by_path(
+                            collection_id,
+                            document_path,
+                            return_only_fields,
+                            retry_state,
+                        )
+                        .await
+                    } else {
+                        Err(err)
                     }
-                    _ => Err(err),
-                },
+                }
             }
         }
         .boxed()
@@ -674,7 +670,7 @@ impl FirestoreDb {
             "/firestore/ids_count" = full_doc_ids.len()
         );
 
-        let request = gcloud_sdk::tonic::Request::new(BatchGetDocumentsRequest {
+        let batch_get_request = BatchGetDocumentsRequest {
             database: self.get_database_path().clone(),
             documents: full_doc_ids,
             consistency_selector: self
@@ -688,9 +684,21 @@ impl FirestoreDb {
                     field_paths: vf.iter().map(|f| f.to_string()).collect(),
                 }
             }),
-        });
+        };
 
-        match self.client().get().batch_get_documents(request).await {
+        match self
+            .with_retries(&span, || {
+                let request = self.create_request(batch_get_request.clone());
+                async move {
+                    self.client()
+                        .get()
+                        .batch_get_documents(request)
+                        .await
+                        .map_err(FirestoreError::from)
+                }
+            })
+            .await
+        {
             Ok(response) => {
                 span.in_scope(|| debug!("Start consuming a batch of documents by ids"));
                 let stream = response
@@ -734,7 +742,7 @@ impl FirestoreDb {
                     .boxed();
                 Ok(stream)
             }
-            Err(err) => Err(err.into()),
+            Err(err) => Err(err),
         }
     }
 
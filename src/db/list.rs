@@ -25,6 +25,10 @@ pub struct FirestoreListDocParams {
     pub page_token: Option<String>,
     pub order_by: Option<Vec<FirestoreQueryOrder>>,
     pub return_only_fields: Option<Vec<String>>,
+
+    /// Also lists the missing documents that only have subcollections.
+    #[default = "false"]
+    pub show_missing: bool,
 }
 
 #[derive(Debug, PartialEq, Clone, Builder)]
@@ -108,7 +112,8 @@ impl FirestoreListingSupport for FirestoreDb {
             "/firestore/response_time" = field::Empty
         );
 
-        self.list_doc_with_retries(params, 0, &span).await
+        self.list_doc_with_retries(params, self.get_retry_policy().new_state(), &span)
+            .await
     }
 
     async fn stream_list_doc_with_errors(
@@ -135,7 +140,14 @@ impl FirestoreListingSupport for FirestoreDb {
                         "/firestore/response_time" = field::Empty
                     );
 
-                    match self.list_doc_with_retries(params.clone(), 0, &span).await {
+                    match self
+                        .list_doc_with_retries(
+                            params.clone(),
+                            self.get_retry_policy().new_state(),
+                            &span,
+                        )
+                        .await
+                    {
                         Ok(results) => {
                             if let Some(next_page_token) = results.page_token.clone() {
                                 Some((Ok(results), Some(params.with_page_token(next_page_token))))
@@ -230,7 +242,7 @@ impl FirestoreListingSupport for FirestoreDb {
             "/firestore/response_time" = field::Empty
         );
 
-        self.list_collection_ids_with_retries(params, 0, &span)
+        self.list_collection_ids_with_retries(params, self.get_retry_policy().new_state(), &span)
             .await
     }
 
@@ -264,7 +276,11 @@ impl FirestoreListingSupport for FirestoreDb {
                     );
 
                     match self
-                        .list_collection_ids_with_retries(params.clone(), 0, &span)
+                        .list_collection_ids_with_retries(
+                            params.clone(),
+                            self.get_retry_policy().new_state(),
+                            &span,
+                        )
                         .await
                     {
                         Ok(results) => {
@@ -304,7 +320,7 @@ impl FirestoreDb {
         &self,
         params: FirestoreListDocParams,
     ) -> FirestoreResult<gcloud_sdk::tonic::Request<ListDocumentsRequest>> {
-        Ok(gcloud_sdk::tonic::Request::new(ListDocumentsRequest {
+        Ok(self.create_request(ListDocumentsRequest {
             parent: params
                 .parent
                 .as_ref()
@@ -332,14 +348,14 @@ impl FirestoreDb {
                 .as_ref()
                 .map(|selector| selector.try_into())
                 .transpose()?,
-            show_missing: false,
+            show_missing: params.show_missing,
         }))
     }
 
     fn list_doc_with_retries<'a>(
         &'a self,
         params: FirestoreListDocParams,
-        retries: usize,
+        mut retry_state: FirestoreRetryState,
         span: &'a Span,
     ) -> BoxFuture<'a, FirestoreResult<FirestoreListDocResult>> {
         async move {
@@ -380,20 +396,13 @@ impl FirestoreDb {
 
                     Ok(result)
                 }
-                Err(err) => match err {
-                    FirestoreError::DatabaseError(ref db_err)
-                        if db_err.retry_possible && retries < self.inner.options.max_retries =>
-                    {
-                        warn!(
-                            "Listing failed with {}. Retrying: {}/{}",
-                            db_err,
-                            retries + 1,
-                            self.inner.options.max_retries
-                        );
-                        self.list_doc_with_retries(params, retries + 1, span).await
+                Err(err) => {
+                    if retry_state.wait_retry(&err, span).await {
+                        self.list_doc_with_retries(params, retry_state, span).await
+                    } else {
+                        Err(err)
                     }
-                    _ => Err(err),
-                },
+                }
             }
         }
         .boxed()
@@ -403,7 +412,7 @@ impl FirestoreDb {
         &self,
         params: &FirestoreListCollectionIdsParams,
     ) -> FirestoreResult<gcloud_sdk::tonic::Request<ListCollectionIdsRequest>> {
-        Ok(gcloud_sdk::tonic::Request::new(ListCollectionIdsRequest {
+        Ok(self.create_request(ListCollectionIdsRequest {
             parent: params
                 .parent
                 .as_ref()
@@ -423,7 +432,7 @@ impl FirestoreDb {
     fn list_collection_ids_with_retries<'a>(
         &'a self,
         params: FirestoreListCollectionIdsParams,
-        retries: usize,
+        mut retry_state: FirestoreRetryState,
         span: &'a Span,
     ) -> BoxFuture<'a, FirestoreResult<FirestoreListCollectionIdsResult>> {
         async move {
@@ -461,21 +470,14 @@ impl FirestoreDb {
 
                     Ok(result)
                 }
-                Err(err) => match err {
-                    FirestoreError::DatabaseError(ref db_err)
-                        if db_err.retry_possible && retries < self.inner.options.max_retries =>
-                    {
-                        warn!(
-                            "Listing failed with {}. Retrying: {}/{}",
-                            db_err,
-                            retries + 1,
-                            self.inner.options.max_retries
-                        );
-                        self.list_collection_ids_with_retries(params, retries + 1, span)
+                Err(err) => {
+                    if retry_state.wait_retry(&err, span).await {
+                        self.list_collection_ids_with_retries(params, retry_state, span)
                             .await
+                    } else {
+                        Err(err)
                     }
-                    _ => Err(err),
-                },
+                }
             }
         }
         .boxed()
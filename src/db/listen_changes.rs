@@ -1,22 +1,29 @@
 use crate::db::safe_document_path;
 use crate::errors::*;
 use crate::timestamp_utils::to_timestamp;
-use crate::{FirestoreDb, FirestoreQueryParams, FirestoreResult, FirestoreResumeStateStorage};
+use crate::{
+    firestore_document_to_serializable, FirestoreDb, FirestoreDocument, FirestoreListenerEvent,
+    FirestoreListenerEventsDecoder, FirestoreQueryParams, FirestoreResult,
+    FirestoreResumeStateStorage,
+};
 pub use async_trait::async_trait;
 use chrono::prelude::*;
+use futures::future;
 use futures::stream::BoxStream;
-use futures::StreamExt;
 use futures::TryFutureExt;
 use futures::TryStreamExt;
+use futures::{Stream, StreamExt};
 use gcloud_sdk::google::firestore::v1::*;
 use rsb_derive::*;
 pub use rvstruct::ValueStruct;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::future::Future;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::task::JoinHandle;
+use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
 use tracing::*;
 
 #[derive(Debug, Clone, Builder)]
@@ -31,6 +38,9 @@ pub struct FirestoreListenerTargetParams {
 impl FirestoreListenerTargetParams {
     pub fn validate(&self) -> FirestoreResult<()> {
         self.target.validate()?;
+        if let FirestoreTargetType::Query(query_params) = &self.target_type {
+            query_params.ensure_without_find_nearest("listen targets")?;
+        }
         Ok(())
     }
 }
@@ -54,12 +64,36 @@ pub enum FirestoreListenerTargetResumeType {
     ReadTime(DateTime<Utc>),
 }
 
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum FirestoreListenerTargetUpdate {
+    Add(FirestoreListenerTargetParams),
+    Remove(FirestoreListenerTarget),
+}
+
 #[async_trait]
 pub trait FirestoreListenSupport {
     async fn listen_doc_changes<'a, 'b>(
         &'a self,
         targets: Vec<FirestoreListenerTargetParams>,
     ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>>;
+
+    /// Listening with the targets added and removed on the opened stream.
+    /// By default the stream of `listen_doc_changes` ends on the first target update,
+    /// so the listener restarts it with the updated targets.
+    async fn listen_doc_changes_with_updates<'a, 'b>(
+        &'a self,
+        targets: Vec<FirestoreListenerTargetParams>,
+        target_updates: BoxStream<'static, FirestoreListenerTargetUpdate>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+        let listen_stream = self.listen_doc_changes(targets).await?;
+        Ok(listen_stream
+            .take_until(target_updates.into_future())
+            .boxed())
+    }
+
+    /// Called by the listeners once for every added target rather than for every reconnect.
+    fn record_listener_target(&self, _target_params: &FirestoreListenerTargetParams) {}
 }
 
 #[async_trait]
@@ -67,6 +101,15 @@ impl FirestoreListenSupport for FirestoreDb {
     async fn listen_doc_changes<'a, 'b>(
         &'a self,
         targets: Vec<FirestoreListenerTargetParams>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+        self.listen_doc_changes_with_updates(targets, futures::stream::empty().boxed())
+            .await
+    }
+
+    async fn listen_doc_changes_with_updates<'a, 'b>(
+        &'a self,
+        targets: Vec<FirestoreListenerTargetParams>,
+        target_updates: BoxStream<'static, FirestoreListenerTargetUpdate>,
     ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
         let listen_requests = targets
             .into_iter()
@@ -74,13 +117,19 @@ impl FirestoreListenSupport for FirestoreDb {
             .collect::<FirestoreResult<Vec<ListenRequest>>>()?;
 
         let request = gcloud_sdk::tonic::Request::new(
-            futures::stream::iter(listen_requests).chain(futures::stream::pending()),
+            self.create_listen_requests_stream(listen_requests, target_updates),
         );
 
         let response = self.client().get().listen(request).await?;
 
         Ok(response.into_inner().map_err(|e| e.into()).boxed())
     }
+
+    fn record_listener_target(&self, target_params: &FirestoreListenerTargetParams) {
+        if let FirestoreTargetType::Query(query_params) = &target_params.target_type {
+            self.record_query(query_params);
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, ValueStruct)]
@@ -172,10 +221,48 @@ impl FirestoreDb {
         FirestoreListener::new(self.clone(), storage, params).await
     }
 
+    fn create_listen_requests_stream(
+        &self,
+        listen_requests: Vec<ListenRequest>,
+        target_updates: BoxStream<'static, FirestoreListenerTargetUpdate>,
+    ) -> impl Stream<Item = ListenRequest> + Send + 'static {
+        let db = self.clone();
+        let update_requests = target_updates.filter_map(move |target_update| {
+            future::ready(
+                db.create_target_update_request(target_update)
+                    .map_err(|err| error!("Invalid listener target update: {:?}", err))
+                    .ok(),
+            )
+        });
+
+        futures::stream::iter(listen_requests)
+            .chain(update_requests)
+            .chain(futures::stream::pending())
+    }
+
+    fn create_target_update_request(
+        &self,
+        target_update: FirestoreListenerTargetUpdate,
+    ) -> FirestoreResult<ListenRequest> {
+        match target_update {
+            FirestoreListenerTargetUpdate::Add(target_params) => {
+                self.create_listen_request(target_params)
+            }
+            FirestoreListenerTargetUpdate::Remove(target) => Ok(ListenRequest {
+                database: self.get_database_path().to_string(),
+                labels: HashMap::new(),
+                target_change: Some(listen_request::TargetChange::RemoveTarget(
+                    target.try_into()?,
+                )),
+            }),
+        }
+    }
+
     fn create_listen_request(
         &self,
         target_params: FirestoreListenerTargetParams,
     ) -> FirestoreResult<ListenRequest> {
+        target_params.validate()?;
         Ok(ListenRequest {
             database: self.get_database_path().to_string(),
             labels: target_params.labels,
@@ -235,8 +322,11 @@ pub type FirestoreListenEvent = listen_response::ResponseType;
 #[derive(Debug, Clone, Eq, PartialEq, Builder)]
 pub struct FirestoreListenerParams {
     pub retry_delay: Option<std::time::Duration>,
+    pub stream_buffer_size: Option<usize>,
 }
 
+const FIRESTORE_LISTENER_DEFAULT_STREAM_BUFFER_SIZE: usize = 128;
+
 pub struct FirestoreListener<D, S>
 where
     D: FirestoreListenSupport,
@@ -249,6 +339,7 @@ where
     shutdown_flag: Arc<AtomicBool>,
     shutdown_handle: Option<JoinHandle<()>>,
     shutdown_writer: Option<Arc<UnboundedSender<i8>>>,
+    target_updates_writer: Option<UnboundedSender<FirestoreListenerTargetUpdate>>,
 }
 
 impl<D, S> FirestoreListener<D, S>
@@ -269,15 +360,38 @@ where
             shutdown_flag: Arc::new(AtomicBool::new(false)),
             shutdown_handle: None,
             shutdown_writer: None,
+            target_updates_writer: None,
         })
     }
 
+    /// Adds a target to listen to. The target is also sent to the running listener,
+    /// so it's possible to add targets after the listener has been started.
+    /// A target with the same ID replaces the existing one.
     pub fn add_target(
         &mut self,
         target_params: FirestoreListenerTargetParams,
     ) -> FirestoreResult<()> {
         target_params.validate()?;
-        self.targets.push(target_params);
+        self.targets
+            .retain(|existing| existing.target != target_params.target);
+        self.targets.push(target_params.clone());
+        if let Some(target_updates_writer) = &self.target_updates_writer {
+            target_updates_writer
+                .send(FirestoreListenerTargetUpdate::Add(target_params))
+                .ok();
+        }
+        Ok(())
+    }
+
+    /// Removes a target and stops listening to it on the running listener.
+    pub fn remove_target(&mut self, target: &FirestoreListenerTarget) -> FirestoreResult<()> {
+        target.validate()?;
+        self.targets.retain(|existing| &existing.target != target);
+        if let Some(target_updates_writer) = &self.target_updates_writer {
+            target_updates_writer
+                .send(FirestoreListenerTargetUpdate::Remove(target.clone()))
+                .ok();
+        }
         Ok(())
     }
 
@@ -285,6 +399,57 @@ where
     where
         FN: Fn(FirestoreListenEvent) -> F + Send + Sync + 'static,
         F: Future<Output = AnyBoxedErrResult<()>> + Send + 'static,
+    {
+        self.start_listener_loop(false, move |event| match event {
+            Ok(event) => future::Either::Left(cb(event)),
+            Err(_) => future::Either::Right(future::ready(Ok(()))),
+        })
+        .await
+    }
+
+    /// Starts the listener and returns its events as a stream.
+    /// Permanent listener errors are delivered as stream items before the stream ends,
+    /// and dropping the stream shuts the listener down.
+    /// Targets can still be added and removed using the returned stream.
+    pub async fn into_stream(mut self) -> FirestoreResult<FirestoreListenerStream> {
+        let (tx, rx) = tokio::sync::mpsc::channel(
+            self.listener_params
+                .stream_buffer_size
+                .unwrap_or(FIRESTORE_LISTENER_DEFAULT_STREAM_BUFFER_SIZE),
+        );
+
+        let shutdown_flag = self.shutdown_flag.clone();
+        self.start_listener_loop(false, move |event| {
+            let tx = tx.clone();
+            let shutdown_flag = shutdown_flag.clone();
+            async move {
+                if tx.send(event).await.is_err() {
+                    shutdown_flag.store(true, Ordering::Relaxed);
+                    return Err("Listener stream has been dropped".into());
+                }
+                Ok(())
+            }
+        })
+        .await?;
+
+        Ok(FirestoreListenerStream {
+            events: ReceiverStream::new(rx).boxed(),
+            shutdown_flag: self.shutdown_flag.clone(),
+            shutdown_writer: self.shutdown_writer.take(),
+            target_updates_writer: self.target_updates_writer.take(),
+        })
+    }
+
+    /// Target changes carrying a resume token are consumed by the listener itself,
+    /// unless `forward_resume_target_changes` is set for the typed events API.
+    async fn start_listener_loop<FN, F>(
+        &mut self,
+        forward_resume_target_changes: bool,
+        cb: FN,
+    ) -> FirestoreResult<()>
+    where
+        FN: Fn(FirestoreResult<FirestoreListenEvent>) -> F + Send + Sync + 'static,
+        F: Future<Output = AnyBoxedErrResult<()>> + Send + 'static,
     {
         info!(
             "Starting a Firestore listener for targets: {:?}...",
@@ -294,41 +459,23 @@ where
         let mut initial_states: HashMap<FirestoreListenerTarget, FirestoreListenerTargetParams> =
             HashMap::new();
         for target_params in &self.targets {
-            match &target_params.resume_type {
-                Some(resume_type) => {
-                    initial_states.insert(
-                        target_params.target.clone(),
-                        target_params.clone().with_resume_type(resume_type.clone()),
-                    );
-                }
-                None => {
-                    let resume_type = self
-                        .storage
-                        .read_resume_state(&target_params.target)
-                        .map_err(|err| {
-                            FirestoreError::SystemError(FirestoreSystemError::new(
-                                FirestoreErrorPublicGenericDetails::new("SystemError".into()),
-                                format!("Listener init error: {err}"),
-                            ))
-                        })
-                        .await?;
-                    initial_states.insert(
-                        target_params.target.clone(),
-                        target_params.clone().opt_resume_type(resume_type),
-                    );
-                }
-            }
+            self.db.record_listener_target(target_params);
+            initial_states.insert(
+                target_params.target.clone(),
+                Self::read_target_state(&self.storage, target_params.clone()).await?,
+            );
         }
 
         if initial_states.is_empty() {
-            warn!("No initial states for listener targets. Exiting...");
-            return Ok(());
+            debug!("No initial states for listener targets. Waiting for targets to be added...");
         }
 
         let (tx, rx): (UnboundedSender<i8>, UnboundedReceiver<i8>) =
             tokio::sync::mpsc::unbounded_channel();
+        let (target_updates_tx, target_updates_rx) = tokio::sync::mpsc::unbounded_channel();
 
         self.shutdown_writer = Some(Arc::new(tx));
+        self.target_updates_writer = Some(target_updates_tx);
         self.shutdown_handle = Some(tokio::spawn(Self::listener_loop(
             self.db.clone(),
             self.storage.clone(),
@@ -336,11 +483,63 @@ where
             initial_states,
             self.listener_params.clone(),
             rx,
+            target_updates_rx,
+            forward_resume_target_changes,
             cb,
         )));
         Ok(())
     }
 
+    pub async fn start_events<FN, F>(&mut self, cb: FN) -> FirestoreResult<()>
+    where
+        FN: Fn(FirestoreListenerEvent<FirestoreDocument>) -> F + Send + Sync + 'static,
+        F: Future<Output = AnyBoxedErrResult<()>> + Send + 'static,
+    {
+        self.start_with_decoder(cb, |doc| Ok(doc.clone())).await
+    }
+
+    pub async fn start_obj<T, FN, F>(&mut self, cb: FN) -> FirestoreResult<()>
+    where
+        for<'de> T: Deserialize<'de>,
+        T: Send + 'static,
+        FN: Fn(FirestoreListenerEvent<T>) -> F + Send + Sync + 'static,
+        F: Future<Output = AnyBoxedErrResult<()>> + Send + 'static,
+    {
+        self.start_with_decoder(cb, firestore_document_to_serializable::<T>)
+            .await
+    }
+
+    async fn start_with_decoder<T, FN, F>(
+        &mut self,
+        cb: FN,
+        decode_doc: fn(&FirestoreDocument) -> FirestoreResult<T>,
+    ) -> FirestoreResult<()>
+    where
+        T: Send + 'static,
+        FN: Fn(FirestoreListenerEvent<T>) -> F + Send + Sync + 'static,
+        F: Future<Output = AnyBoxedErrResult<()>> + Send + 'static,
+    {
+        let decoder = Arc::new(std::sync::Mutex::new(FirestoreListenerEventsDecoder::new()));
+        let cb = Arc::new(cb);
+
+        self.start_listener_loop(true, move |event| {
+            let decoded = event.ok().map(|event| {
+                decoder
+                    .lock()
+                    .map(|mut decoder| decoder.decode(event, decode_doc))
+                    .map_err(|err| format!("Listener decoder error: {err}"))
+            });
+            let cb = cb.clone();
+            async move {
+                for decoded_event in decoded.transpose()?.unwrap_or_default() {
+                    cb(decoded_event).await?;
+                }
+                Ok(())
+            }
+        })
+        .await
+    }
+
     pub async fn shutdown(&mut self) -> FirestoreResult<()> {
         debug!("Shutting down Firestore listener...");
         self.shutdown_flag.store(true, Ordering::Relaxed);
@@ -356,6 +555,70 @@ where
         Ok(())
     }
 
+    async fn read_target_state(
+        storage: &S,
+        target_params: FirestoreListenerTargetParams,
+    ) -> FirestoreResult<FirestoreListenerTargetParams> {
+        match target_params.resume_type {
+            Some(_) => Ok(target_params),
+            None => {
+                let resume_type = storage
+                    .read_resume_state(&target_params.target)
+                    .map_err(|err| {
+                        FirestoreError::SystemError(FirestoreSystemError::new(
+                            FirestoreErrorPublicGenericDetails::new("SystemError".into()),
+                            format!("Listener init error: {err}"),
+                        ))
+                    })
+                    .await?;
+                Ok(target_params.opt_resume_type(resume_type))
+            }
+        }
+    }
+
+    /// Returns the updates to send on the opened stream. Replacing an active target removes it first,
+    /// since Firestore doesn't allow adding a target ID that is already active.
+    async fn apply_target_update(
+        db: &D,
+        storage: &S,
+        targets_state: &mut HashMap<FirestoreListenerTarget, FirestoreListenerTargetParams>,
+        target_update: FirestoreListenerTargetUpdate,
+    ) -> Vec<FirestoreListenerTargetUpdate> {
+        match target_update {
+            FirestoreListenerTargetUpdate::Add(target_params) => {
+                match Self::read_target_state(storage, target_params).await {
+                    Ok(target_state) => {
+                        debug!("Adding listener target: {:?}", target_state.target);
+                        db.record_listener_target(&target_state);
+                        let mut updates = Vec::with_capacity(2);
+                        if targets_state
+                            .insert(target_state.target.clone(), target_state.clone())
+                            .is_some()
+                        {
+                            updates.push(FirestoreListenerTargetUpdate::Remove(
+                                target_state.target.clone(),
+                            ));
+                        }
+                        updates.push(FirestoreListenerTargetUpdate::Add(target_state));
+                        updates
+                    }
+                    Err(err) => {
+                        error!("Listener target state error occurred {:?}.", err);
+                        vec![]
+                    }
+                }
+            }
+            FirestoreListenerTargetUpdate::Remove(target) => {
+                debug!("Removing listener target: {:?}", target);
+                targets_state
+                    .remove(&target)
+                    .map(|_| FirestoreListenerTargetUpdate::Remove(target))
+                    .into_iter()
+                    .collect()
+            }
+        }
+    }
+
     async fn listener_loop<FN, F>(
         db: D,
         storage: S,
@@ -363,10 +626,12 @@ where
         mut targets_state: HashMap<FirestoreListenerTarget, FirestoreListenerTargetParams>,
         listener_params: FirestoreListenerParams,
         mut shutdown_receiver: UnboundedReceiver<i8>,
+        mut target_updates_receiver: UnboundedReceiver<FirestoreListenerTargetUpdate>,
+        forward_resume_target_changes: bool,
         cb: FN,
     ) where
         D: FirestoreListenSupport + Clone + Send + Sync,
-        FN: Fn(FirestoreListenEvent) -> F + Send + Sync,
+        FN: Fn(FirestoreResult<FirestoreListenEvent>) -> F + Send + Sync,
         F: Future<Output = AnyBoxedErrResult<()>> + Send,
     {
         let effective_delay = listener_params
@@ -374,15 +639,36 @@ where
             .unwrap_or_else(|| std::time::Duration::from_secs(5));
 
         while !shutdown_flag.load(Ordering::Relaxed) {
+            if targets_state.is_empty() {
+                tokio::select! {
+                    _ = shutdown_receiver.recv() => {
+                        debug!("Exiting from listener without targets...");
+                        shutdown_receiver.close();
+                        break;
+                    }
+                    Some(target_update) = target_updates_receiver.recv() => {
+                        Self::apply_target_update(&db, &storage, &mut targets_state, target_update).await;
+                    }
+                }
+                continue;
+            }
+
             debug!("Start listening on {} targets ... ", targets_state.len());
 
+            let (connection_updates_tx, connection_updates_rx) =
+                tokio::sync::mpsc::unbounded_channel();
+
             match db
-                .listen_doc_changes(targets_state.values().cloned().collect())
+                .listen_doc_changes_with_updates(
+                    targets_state.values().cloned().collect(),
+                    UnboundedReceiverStream::new(connection_updates_rx).boxed(),
+                )
                 .await
             {
                 Err(err) => {
-                    if Self::check_listener_if_permanent_error(err, effective_delay).await {
+                    if Self::check_listener_if_permanent_error(&err, effective_delay).await {
                         shutdown_flag.store(true, Ordering::Relaxed);
+                        cb(Err(err)).await.ok();
                     }
                 }
                 Ok(mut listen_stream) => loop {
@@ -392,6 +678,11 @@ where
                             shutdown_receiver.close();
                             break;
                         }
+                        Some(target_update) = target_updates_receiver.recv() => {
+                            for target_update in Self::apply_target_update(&db, &storage, &mut targets_state, target_update).await {
+                                connection_updates_tx.send(target_update).ok();
+                            }
+                        }
                         tried = listen_stream.try_next() => {
                             if shutdown_flag.load(Ordering::Relaxed) {
                                 break;
@@ -404,31 +695,40 @@ where
                                             Some(listen_response::ResponseType::TargetChange(ref target_change))
                                                 if !target_change.resume_token.is_empty() =>
                                             {
-                                                for target_id_num in &target_change.target_ids {
-                                                    match FirestoreListenerTarget::try_from(*target_id_num) {
-                                                        Ok(target_id) => {
-                                                            if let Some(target) = targets_state.get_mut(&target_id) {
-                                                                let new_token: FirestoreListenerToken = target_change.resume_token.clone().into();
-
-                                                                if let Err(err) = storage.update_resume_token(&target.target, new_token.clone()).await {
-                                                                    error!("Listener token storage error occurred {:?}.", err);
-                                                                    break;
-                                                                }
-                                                                else {
-                                                                    target.resume_type = Some(FirestoreListenerTargetResumeType::Token(new_token))
-                                                                }
-                                                            }
-                                                        },
-                                                        Err(err) => {
-                                                            error!("Listener system error - unexpected target ID: {} {:?}.", target_id_num, err);
+                                                // Empty target IDs mean the token applies to all targets
+                                                let target_ids: Vec<FirestoreListenerTarget> = if target_change.target_ids.is_empty() {
+                                                    targets_state.keys().cloned().collect()
+                                                } else {
+                                                    target_change.target_ids.iter().filter_map(|target_id_num| {
+                                                        FirestoreListenerTarget::try_from(*target_id_num)
+                                                            .map_err(|err| error!("Listener system error - unexpected target ID: {} {:?}.", target_id_num, err))
+                                                            .ok()
+                                                    }).collect()
+                                                };
+
+                                                for target_id in target_ids {
+                                                    if let Some(target) = targets_state.get_mut(&target_id) {
+                                                        let new_token: FirestoreListenerToken = target_change.resume_token.clone().into();
+
+                                                        if let Err(err) = storage.update_resume_token(&target.target, new_token.clone()).await {
+                                                            error!("Listener token storage error occurred {:?}.", err);
                                                             break;
                                                         }
+                                                        else {
+                                                            target.resume_type = Some(FirestoreListenerTargetResumeType::Token(new_token))
+                                                        }
                                                     }
                                                 }
 
+                                                if forward_resume_target_changes && target_change.target_change_type() != target_change::TargetChangeType::NoChange {
+                                                    if let Err(err) = cb(Ok(listen_response::ResponseType::TargetChange(target_change.clone()))).await {
+                                                        error!("Listener callback function error occurred {:?}.", err);
+                                                        break;
+                                                    }
+                                                }
                                             }
                                             Some(response_type) => {
-                                                if let Err(err) = cb(response_type).await {
+                                                if let Err(err) = cb(Ok(response_type)).await {
                                                     error!("Listener callback function error occurred {:?}.", err);
                                                     break;
                                                 }
@@ -438,8 +738,9 @@ where
                                     }
                                     Ok(None) => break,
                                     Err(err) => {
-                                        if Self::check_listener_if_permanent_error(err, effective_delay).await {
+                                        if Self::check_listener_if_permanent_error(&err, effective_delay).await {
                                             shutdown_flag.store(true, Ordering::Relaxed);
+                                            cb(Err(err)).await.ok();
                                         }
                                         break;
                                     }
@@ -453,7 +754,7 @@ where
     }
 
     async fn check_listener_if_permanent_error(
-        err: FirestoreError,
+        err: &FirestoreError,
         delay: std::time::Duration,
     ) -> bool {
         match err {
@@ -483,3 +784,605 @@ where
         }
     }
 }
+
+/// Adding and removing targets on a listener or on a listener stream.
+pub trait FirestoreListenerTargetsSupport {
+    fn add_target(&mut self, target_params: FirestoreListenerTargetParams) -> FirestoreResult<()>;
+
+    fn remove_target(&mut self, target: &FirestoreListenerTarget) -> FirestoreResult<()>;
+}
+
+impl<D, S> FirestoreListenerTargetsSupport for FirestoreListener<D, S>
+where
+    D: FirestoreListenSupport + Clone + Send + Sync + 'static,
+    S: FirestoreResumeStateStorage + Clone + Send + Sync + 'static,
+{
+    fn add_target(&mut self, target_params: FirestoreListenerTargetParams) -> FirestoreResult<()> {
+        FirestoreListener::add_target(self, target_params)
+    }
+
+    fn remove_target(&mut self, target: &FirestoreListenerTarget) -> FirestoreResult<()> {
+        FirestoreListener::remove_target(self, target)
+    }
+}
+
+/// Listener events stream created with `FirestoreListener::into_stream`.
+pub struct FirestoreListenerStream {
+    events: BoxStream<'static, FirestoreResult<FirestoreListenEvent>>,
+    shutdown_flag: Arc<AtomicBool>,
+    shutdown_writer: Option<Arc<UnboundedSender<i8>>>,
+    target_updates_writer: Option<UnboundedSender<FirestoreListenerTargetUpdate>>,
+}
+
+impl FirestoreListenerStream {
+    fn send_target_update(&self, target_update: FirestoreListenerTargetUpdate) {
+        if let Some(target_updates_writer) = &self.target_updates_writer {
+            target_updates_writer.send(target_update).ok();
+        }
+    }
+}
+
+impl FirestoreListenerTargetsSupport for FirestoreListenerStream {
+    fn add_target(&mut self, target_params: FirestoreListenerTargetParams) -> FirestoreResult<()> {
+        target_params.validate()?;
+        self.send_target_update(FirestoreListenerTargetUpdate::Add(target_params));
+        Ok(())
+    }
+
+    fn remove_target(&mut self, target: &FirestoreListenerTarget) -> FirestoreResult<()> {
+        target.validate()?;
+        self.send_target_update(FirestoreListenerTargetUpdate::Remove(target.clone()));
+        Ok(())
+    }
+}
+
+impl Stream for FirestoreListenerStream {
+    type Item = FirestoreResult<FirestoreListenEvent>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for FirestoreListenerStream {
+    fn drop(&mut self) {
+        debug!("Listener stream has been dropped. Shutting down Firestore listener...");
+        self.shutdown_flag.store(true, Ordering::Relaxed);
+        if let Some(shutdown_writer) = self.shutdown_writer.take() {
+            shutdown_writer.send(1).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FirestoreInMemoryDb, FirestoreMemListenStateStorage};
+
+    #[tokio::test]
+    async fn listener_into_stream_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        let mut listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+
+        db.fluent()
+            .select()
+            .from("test")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(1), &mut listener)?;
+
+        let mut events = listener.into_stream().await?;
+
+        db.fluent()
+            .insert()
+            .into("test")
+            .document_id("test-0")
+            .document(Document::default())
+            .execute()
+            .await?;
+
+        let mut changed_doc = None;
+        while let Some(event) = events.try_next().await? {
+            if let listen_response::ResponseType::DocumentChange(doc_change) = event {
+                changed_doc = doc_change.document;
+                break;
+            }
+        }
+
+        assert_eq!(
+            changed_doc.map(|doc| doc.name),
+            Some(format!("{}/test/test-0", db.get_documents_path()))
+        );
+
+        events.remove_target(&FirestoreListenerTarget::new(1))?;
+        while let Some(event) = events.try_next().await? {
+            if matches!(event, listen_response::ResponseType::TargetChange(ref target_change)
+                if target_change.target_change_type() == target_change::TargetChangeType::Remove)
+            {
+                break;
+            }
+        }
+
+        db.fluent()
+            .select()
+            .from("other")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(2), &mut events)?;
+
+        db.fluent()
+            .insert()
+            .into("test")
+            .document_id("test-1")
+            .document(Document::default())
+            .execute()
+            .await?;
+        db.fluent()
+            .insert()
+            .into("other")
+            .document_id("other-0")
+            .document(Document::default())
+            .execute()
+            .await?;
+
+        let mut changed_doc = None;
+        while let Some(event) = events.try_next().await? {
+            if let listen_response::ResponseType::DocumentChange(doc_change) = event {
+                changed_doc = doc_change.document;
+                break;
+            }
+        }
+
+        assert_eq!(
+            changed_doc.map(|doc| doc.name),
+            Some(format!("{}/other/other-0", db.get_documents_path()))
+        );
+
+        drop(events);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listener_dynamic_targets_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        let mut listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        listener
+            .start(move |event| {
+                let tx = tx.clone();
+                async move {
+                    match event {
+                        listen_response::ResponseType::DocumentChange(doc_change) => {
+                            tx.send(doc_change.document.map(|doc| doc.name))?;
+                        }
+                        listen_response::ResponseType::TargetChange(target_change)
+                            if target_change.target_change_type()
+                                == target_change::TargetChangeType::Remove =>
+                        {
+                            tx.send(None)?;
+                        }
+                        _ => {}
+                    }
+                    Ok(())
+                }
+            })
+            .await?;
+
+        db.fluent()
+            .select()
+            .from("test")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(1), &mut listener)?;
+
+        db.fluent()
+            .insert()
+            .into("test")
+            .document_id("test-0")
+            .document(Document::default())
+            .execute()
+            .await?;
+
+        assert_eq!(
+            rx.recv().await.flatten(),
+            Some(format!("{}/test/test-0", db.get_documents_path()))
+        );
+
+        listener.remove_target(&FirestoreListenerTarget::new(1))?;
+        assert_eq!(rx.recv().await, Some(None));
+
+        db.fluent()
+            .select()
+            .from("other")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(2), &mut listener)?;
+
+        db.fluent()
+            .insert()
+            .into("test")
+            .document_id("test-1")
+            .document(Document::default())
+            .execute()
+            .await?;
+        db.fluent()
+            .insert()
+            .into("other")
+            .document_id("other-0")
+            .document(Document::default())
+            .execute()
+            .await?;
+
+        assert_eq!(
+            rx.recv().await.flatten(),
+            Some(format!("{}/other/other-0", db.get_documents_path()))
+        );
+
+        listener.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listener_resume_target_changes_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        db.fluent()
+            .insert()
+            .into("test")
+            .document_id("test-0")
+            .document(Document::default())
+            .execute()
+            .await?;
+
+        let mut raw_listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+        db.fluent()
+            .select()
+            .from("test")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(1), &mut raw_listener)?;
+
+        let (raw_tx, mut raw_rx) = tokio::sync::mpsc::unbounded_channel();
+        raw_listener
+            .start(move |event| {
+                let raw_tx = raw_tx.clone();
+                async move {
+                    raw_tx.send(event)?;
+                    Ok(())
+                }
+            })
+            .await?;
+
+        let mut events_listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+        db.fluent()
+            .select()
+            .from("test")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(1), &mut events_listener)?;
+
+        let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+        events_listener
+            .start_events(move |event| {
+                let events_tx = events_tx.clone();
+                async move {
+                    if let FirestoreListenerEvent::Current(targets_event) = event {
+                        events_tx.send(targets_event.target_ids)?;
+                    }
+                    Ok(())
+                }
+            })
+            .await?;
+
+        assert_eq!(
+            events_rx.recv().await,
+            Some(vec![FirestoreListenerTarget::new(1)])
+        );
+
+        events_listener.shutdown().await?;
+
+        // Responses are delivered in order, so the next document change comes
+        // after the initial target changes of the raw listener
+        db.fluent()
+            .insert()
+            .into("test")
+            .document_id("test-1")
+            .document(Document::default())
+            .execute()
+            .await?;
+
+        let test_1_name = format!("{}/test/test-1", db.get_documents_path());
+        let mut raw_events = vec![];
+        while let Some(event) = raw_rx.recv().await {
+            let is_test_1 = matches!(
+                &event,
+                listen_response::ResponseType::DocumentChange(doc_change)
+                    if doc_change.document.as_ref().map(|doc| &doc.name) == Some(&test_1_name)
+            );
+            raw_events.push(event);
+            if is_test_1 {
+                break;
+            }
+        }
+        raw_listener.shutdown().await?;
+
+        assert!(!raw_events.iter().any(|event| matches!(
+            event,
+            listen_response::ResponseType::TargetChange(target_change)
+                if !target_change.resume_token.is_empty()
+        )));
+
+        Ok(())
+    }
+
+    #[derive(Clone)]
+    struct ListenOnlyDb {
+        db: FirestoreInMemoryDb,
+    }
+
+    #[async_trait]
+    impl FirestoreListenSupport for ListenOnlyDb {
+        async fn listen_doc_changes<'a, 'b>(
+            &'a self,
+            targets: Vec<FirestoreListenerTargetParams>,
+        ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+            self.db.listen_doc_changes(targets).await
+        }
+    }
+
+    #[tokio::test]
+    async fn listener_restarts_without_target_updates_support_test() -> FirestoreResult<()> {
+        let db = ListenOnlyDb {
+            db: FirestoreInMemoryDb::new("test-project"),
+        };
+        let mut listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+        db.db
+            .fluent()
+            .select()
+            .from("test")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(1), &mut listener)?;
+
+        db.db
+            .fluent()
+            .insert()
+            .into("other")
+            .document_id("other-0")
+            .document(Document::default())
+            .execute()
+            .await?;
+
+        let mut events = listener.into_stream().await?;
+        db.db
+            .fluent()
+            .select()
+            .from("other")
+            .listen()
+            .add_target(FirestoreListenerTarget::new(2), &mut events)?;
+
+        // The restarted stream sends the current documents of the added target
+        let mut changed_doc = None;
+        while let Some(event) = events.try_next().await? {
+            if let listen_response::ResponseType::DocumentChange(doc_change) = event {
+                changed_doc = doc_change.document;
+                break;
+            }
+        }
+
+        assert_eq!(
+            changed_doc.map(|doc| doc.name),
+            Some(format!("{}/other/other-0", db.db.get_documents_path()))
+        );
+
+        drop(events);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listener_rejects_find_nearest_targets_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        let mut listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+
+        let result = db
+            .fluent()
+            .select()
+            .from("test")
+            .find_nearest(
+                "embedding",
+                vec![1.0, 2.0],
+                crate::FirestoreFindNearestDistanceMeasure::Euclidean,
+                3,
+            )
+            .listen()
+            .add_target(FirestoreListenerTarget::new(1), &mut listener);
+
+        assert!(matches!(
+            result,
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+        Ok(())
+    }
+
+    #[derive(Clone)]
+    struct RecordingListenDb {
+        db: FirestoreInMemoryDb,
+        recorded_targets: Arc<std::sync::Mutex<Vec<FirestoreListenerTarget>>>,
+    }
+
+    #[async_trait]
+    impl FirestoreListenSupport for RecordingListenDb {
+        async fn listen_doc_changes<'a, 'b>(
+            &'a self,
+            targets: Vec<FirestoreListenerTargetParams>,
+        ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+            self.db.listen_doc_changes(targets).await
+        }
+
+        async fn listen_doc_changes_with_updates<'a, 'b>(
+            &'a self,
+            targets: Vec<FirestoreListenerTargetParams>,
+            target_updates: BoxStream<'static, FirestoreListenerTargetUpdate>,
+        ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+            self.db
+                .listen_doc_changes_with_updates(targets, target_updates)
+                .await
+        }
+
+        fn record_listener_target(&self, target_params: &FirestoreListenerTargetParams) {
+            self.recorded_targets
+                .lock()
+                .unwrap()
+                .push(target_params.target.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn listener_records_added_targets_test() -> FirestoreResult<()> {
+        let db = RecordingListenDb {
+            db: FirestoreInMemoryDb::new("test-project"),
+            recorded_targets: Arc::new(std::sync::Mutex::new(Vec::new())),
+        };
+        let target_params = |target: u32| {
+            FirestoreListenerTargetParams::new(
+                FirestoreListenerTarget::new(target),
+                FirestoreTargetType::Query(FirestoreQueryParams::new("test".into())),
+                HashMap::new(),
+            )
+        };
+
+        let mut listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+        listener.add_target(target_params(1))?;
+
+        let mut events = listener.into_stream().await?;
+        events.add_target(target_params(2))?;
+
+        for _ in 0..100 {
+            if db.recorded_targets.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            *db.recorded_targets.lock().unwrap(),
+            vec![
+                FirestoreListenerTarget::new(1),
+                FirestoreListenerTarget::new(2)
+            ]
+        );
+
+        drop(events);
+        Ok(())
+    }
+
+    #[derive(Clone)]
+    struct UpdatesRecordingDb {
+        db: FirestoreInMemoryDb,
+        sent_updates: Arc<std::sync::Mutex<Vec<(&'static str, FirestoreListenerTarget)>>>,
+    }
+
+    #[async_trait]
+    impl FirestoreListenSupport for UpdatesRecordingDb {
+        async fn listen_doc_changes<'a, 'b>(
+            &'a self,
+            targets: Vec<FirestoreListenerTargetParams>,
+        ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+            self.db.listen_doc_changes(targets).await
+        }
+
+        async fn listen_doc_changes_with_updates<'a, 'b>(
+            &'a self,
+            targets: Vec<FirestoreListenerTargetParams>,
+            target_updates: BoxStream<'static, FirestoreListenerTargetUpdate>,
+        ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+            let sent_updates = self.sent_updates.clone();
+            let target_updates = target_updates.inspect(move |target_update| {
+                sent_updates.lock().unwrap().push(match target_update {
+                    FirestoreListenerTargetUpdate::Add(target_params) => {
+                        ("add", target_params.target.clone())
+                    }
+                    FirestoreListenerTargetUpdate::Remove(target) => ("remove", target.clone()),
+                });
+            });
+            self.db
+                .listen_doc_changes_with_updates(targets, target_updates.boxed())
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn listener_replaces_active_targets_test() -> FirestoreResult<()> {
+        let db = UpdatesRecordingDb {
+            db: FirestoreInMemoryDb::new("test-project"),
+            sent_updates: Arc::new(std::sync::Mutex::new(Vec::new())),
+        };
+        let target_params = |target: u32, collection: &str| {
+            FirestoreListenerTargetParams::new(
+                FirestoreListenerTarget::new(target),
+                FirestoreTargetType::Query(FirestoreQueryParams::new(collection.into())),
+                HashMap::new(),
+            )
+        };
+
+        let mut listener = FirestoreListener::new(
+            db.clone(),
+            FirestoreMemListenStateStorage::new(),
+            FirestoreListenerParams::new(),
+        )
+        .await?;
+        listener.add_target(target_params(1, "test"))?;
+
+        let mut events = listener.into_stream().await?;
+        events.add_target(target_params(2, "test"))?;
+        events.add_target(target_params(1, "other"))?;
+
+        for _ in 0..100 {
+            if db.sent_updates.lock().unwrap().len() >= 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            *db.sent_updates.lock().unwrap(),
+            vec![
+                ("add", FirestoreListenerTarget::new(2)),
+                ("remove", FirestoreListenerTarget::new(1)),
+                ("add", FirestoreListenerTarget::new(1)),
+            ]
+        );
+
+        drop(events);
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use crate::timestamp_utils::from_timestamp;
+use crate::*;
+use chrono::prelude::*;
+use gcloud_sdk::google::firestore::v1::*;
+use std::collections::{HashMap, HashSet};
+use tracing::*;
+
+/// Typed listener event decoded from the raw `listen_response::ResponseType`.
+#[derive(Debug)]
+pub enum FirestoreListenerEvent<T> {
+    Added(FirestoreListenerDocEvent<T>),
+    Modified(FirestoreListenerDocEvent<T>),
+    Removed(FirestoreListenerDocRemovedEvent),
+    Deleted(FirestoreListenerDocRemovedEvent),
+    Current(FirestoreListenerTargetsEvent),
+    Reset(FirestoreListenerTargetsEvent),
+    DecodeError(FirestoreListenerDecodeErrorEvent),
+}
+
+#[derive(Debug, Clone)]
+pub struct FirestoreListenerDocEvent<T> {
+    pub document_name: String,
+    pub target_ids: Vec<FirestoreListenerTarget>,
+    pub doc: T,
+}
+
+#[derive(Debug, Clone)]
+pub struct FirestoreListenerDocRemovedEvent {
+    pub document_name: String,
+    pub target_ids: Vec<FirestoreListenerTarget>,
+    pub read_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FirestoreListenerTargetsEvent {
+    pub target_ids: Vec<FirestoreListenerTarget>,
+    pub read_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct FirestoreListenerDecodeErrorEvent {
+    pub document_name: String,
+    pub target_ids: Vec<FirestoreListenerTarget>,
+    pub error: FirestoreError,
+}
+
+// Tracks documents seen per target to tell additions from modifications.
+// The state lives only as long as the listener, so after a process restart
+// with a stored resume token modified documents are reported as added.
+#[derive(Debug, Default)]
+pub(crate) struct FirestoreListenerEventsDecoder {
+    known_docs: HashMap<i32, HashSet<String>>,
+}
+
+impl FirestoreListenerEventsDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn decode<T>(
+        &mut self,
+        event: FirestoreListenEvent,
+        decode_doc: fn(&Document) -> FirestoreResult<T>,
+    ) -> Vec<FirestoreListenerEvent<T>> {
+        match event {
+            listen_response::ResponseType::DocumentChange(doc_change) => {
+                let mut events = Vec::with_capacity(2);
+                if let Some(doc) = doc_change.document {
+                    let known = doc_change.target_ids.iter().any(|target_id| {
+                        self.known_docs
+                            .get(target_id)
+                            .iter()
+                            .any(|docs| docs.contains(&doc.name))
+                    });
+                    for target_id in &doc_change.target_ids {
+                        self.known_docs
+                            .entry(*target_id)
+                            .or_default()
+                            .insert(doc.name.clone());
+                    }
+
+                    let target_ids = Self::to_targets(&doc_change.target_ids);
+                    events.push(match decode_doc(&doc) {
+                        Ok(decoded) => {
+                            let doc_event = FirestoreListenerDocEvent {
+                                document_name: doc.name.clone(),
+                                target_ids,
+                                doc: decoded,
+                            };
+                            if known {
+                                FirestoreListenerEvent::Modified(doc_event)
+                            } else {
+                                FirestoreListenerEvent::Added(doc_event)
+                            }
+                        }
+                        Err(error) => {
+                            FirestoreListenerEvent::DecodeError(FirestoreListenerDecodeErrorEvent {
+                                document_name: doc.name.clone(),
+                                target_ids,
+                                error,
+                            })
+                        }
+                    });
+
+                    let removed_target_ids =
+                        self.forget_doc(&doc.name, &doc_change.removed_target_ids);
+                    if !removed_target_ids.is_empty() {
+                        events.push(FirestoreListenerEvent::Removed(
+                            FirestoreListenerDocRemovedEvent {
+                                document_name: doc.name,
+                                target_ids: Self::to_targets(&removed_target_ids),
+                                read_time: None,
+                            },
+                        ));
+                    }
+                }
+                events
+            }
+            listen_response::ResponseType::DocumentDelete(doc_delete) => {
+                self.forget_doc(&doc_delete.document, &doc_delete.removed_target_ids);
+                vec![FirestoreListenerEvent::Deleted(
+                    FirestoreListenerDocRemovedEvent {
+                        target_ids: Self::to_targets(&doc_delete.removed_target_ids),
+                        read_time: Self::to_read_time(doc_delete.read_time),
+                        document_name: doc_delete.document,
+                    },
+                )]
+            }
+            listen_response::ResponseType::DocumentRemove(doc_remove) => {
+                self.forget_doc(&doc_remove.document, &doc_remove.removed_target_ids);
+                vec![FirestoreListenerEvent::Removed(
+                    FirestoreListenerDocRemovedEvent {
+                        target_ids: Self::to_targets(&doc_remove.removed_target_ids),
+                        read_time: Self::to_read_time(doc_remove.read_time),
+                        document_name: doc_remove.document,
+                    },
+                )]
+            }
+            listen_response::ResponseType::TargetChange(target_change) => {
+                let targets_event = FirestoreListenerTargetsEvent {
+                    target_ids: Self::to_targets(&target_change.target_ids),
+                    read_time: Self::to_read_time(target_change.read_time.clone()),
+                };
+                match target_change.target_change_type() {
+                    target_change::TargetChangeType::Current => {
+                        vec![FirestoreListenerEvent::Current(targets_event)]
+                    }
+                    target_change::TargetChangeType::Reset => {
+                        self.forget_targets(&target_change.target_ids);
+                        vec![FirestoreListenerEvent::Reset(targets_event)]
+                    }
+                    target_change::TargetChangeType::Remove => {
+                        self.forget_targets(&target_change.target_ids);
+                        if let Some(cause) = target_change.cause {
+                            warn!(
+                                "Listener targets {:?} have been removed: {:?}",
+                                target_change.target_ids, cause
+                            );
+                        }
+                        vec![]
+                    }
+                    target_change::TargetChangeType::NoChange
+                    | target_change::TargetChangeType::Add => vec![],
+                }
+            }
+            listen_response::ResponseType::Filter(filter) => {
+                let known_count = self
+                    .known_docs
+                    .get(&filter.target_id)
+                    .map(|docs| docs.len())
+                    .unwrap_or(0);
+                if known_count as i32 != filter.count {
+                    debug!(
+                        "Listener target {} is out of sync: {} known documents, {} expected",
+                        filter.target_id, known_count, filter.count
+                    );
+                    vec![FirestoreListenerEvent::Reset(
+                        FirestoreListenerTargetsEvent {
+                            target_ids: Self::to_targets(&[filter.target_id]),
+                            read_time: None,
+                        },
+                    )]
+                } else {
+                    vec![]
+                }
+            }
+        }
+    }
+
+    fn forget_doc(&mut self, document_name: &str, target_ids: &[i32]) -> Vec<i32> {
+        target_ids
+            .iter()
+            .filter(|target_id| {
+                self.known_docs
+                    .get_mut(target_id)
+                    .map(|docs| docs.remove(document_name))
+                    .unwrap_or(false)
+            })
+            .copied()
+            .collect()
+    }
+
+    fn forget_targets(&mut self, target_ids: &[i32]) {
+        if target_ids.is_empty() {
+            self.known_docs.clear();
+        } else {
+            for target_id in target_ids {
+                self.known_docs.remove(target_id);
+            }
+        }
+    }
+
+    fn to_targets(target_ids: &[i32]) -> Vec<FirestoreListenerTarget> {
+        target_ids
+            .iter()
+            .filter_map(|target_id| FirestoreListenerTarget::try_from(*target_id).ok())
+            .collect()
+    }
+
+    fn to_read_time(
+        read_time: Option<gcloud_sdk::prost_types::Timestamp>,
+    ) -> Option<DateTime<Utc>> {
+        read_time.and_then(|ts| from_timestamp(ts).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::FirestoreSerializationError;
+
+    fn doc_change(name: &str, target_ids: Vec<i32>, removed: Vec<i32>) -> FirestoreListenEvent {
+        listen_response::ResponseType::DocumentChange(DocumentChange {
+            document: Some(Document {
+                name: name.to_string(),
+                ..Default::default()
+            }),
+            target_ids,
+            removed_target_ids: removed,
+        })
+    }
+
+    fn decode_doc(doc: &Document) -> FirestoreResult<Document> {
+        Ok(doc.clone())
+    }
+
+    #[test]
+    fn decode_added_and_modified() {
+        let mut decoder = FirestoreListenerEventsDecoder::new();
+
+        let events = decoder.decode(doc_change("doc1", vec![1], vec![]), decode_doc);
+        assert!(
+            matches!(events.as_slice(), [FirestoreListenerEvent::Added(ev)] if ev.document_name == "doc1")
+        );
+
+        let events = decoder.decode(doc_change("doc1", vec![1], vec![]), decode_doc);
+        assert!(matches!(
+            events.as_slice(),
+            [FirestoreListenerEvent::Modified(_)]
+        ));
+
+        let events = decoder.decode(doc_change("doc1", vec![2], vec![1]), decode_doc);
+        assert!(matches!(
+            events.as_slice(),
+            [
+                FirestoreListenerEvent::Added(_),
+                FirestoreListenerEvent::Removed(removed)
+            ] if removed.target_ids == vec![FirestoreListenerTarget::new(1)]
+        ));
+    }
+
+    #[test]
+    fn decode_reset_forgets_docs() {
+        let mut decoder = FirestoreListenerEventsDecoder::new();
+        decoder.decode(doc_change("doc1", vec![1], vec![]), decode_doc);
+
+        let events = decoder.decode(
+            listen_response::ResponseType::TargetChange(TargetChange {
+                target_change_type: target_change::TargetChangeType::Reset.into(),
+                target_ids: vec![1],
+                ..Default::default()
+            }),
+            decode_doc,
+        );
+        assert!(matches!(
+            events.as_slice(),
+            [FirestoreListenerEvent::Reset(_)]
+        ));
+
+        let events = decoder.decode(doc_change("doc1", vec![1], vec![]), decode_doc);
+        assert!(matches!(
+            events.as_slice(),
+            [FirestoreListenerEvent::Added(_)]
+        ));
+    }
+
+    #[test]
+    fn decode_errors_are_reported_per_event() {
+        let mut decoder = FirestoreListenerEventsDecoder::new();
+        let events = decoder.decode::<Document>(doc_change("doc1", vec![1], vec![]), |_| {
+            Err(FirestoreError::DeserializeError(
+                FirestoreSerializationError::from_message("test"),
+            ))
+        });
+        assert!(matches!(
+            events.as_slice(),
+            [FirestoreListenerEvent::DecodeError(_)]
+        ));
+
+        let events = decoder.decode(doc_change("doc1", vec![1], vec![]), decode_doc);
+        assert!(matches!(
+            events.as_slice(),
+            [FirestoreListenerEvent::Modified(_)]
+        ));
+    }
+}
@@ -21,6 +21,14 @@ pub use precondition_models::*;
 mod query;
 pub use query::*;
 
+mod query_pagination;
+pub use query_pagination::*;
+
+mod query_validation;
+
+mod query_indexes;
+pub use query_indexes::*;
+
 mod aggregated_query;
 pub use aggregated_query::*;
 
@@ -30,9 +38,17 @@ pub use list::*;
 mod listen_changes;
 pub use listen_changes::*;
 
+mod listen_changes_events;
+pub use listen_changes_events::*;
+
 mod listen_changes_state_storage;
 pub use listen_changes_state_storage::*;
 
+mod explain_models;
+pub use explain_models::*;
+
+mod query_ext_api;
+
 use crate::*;
 use gcloud_sdk::google::firestore::v1::firestore_client::FirestoreClient;
 use gcloud_sdk::google::firestore::v1::*;
@@ -50,7 +66,7 @@ mod transaction_models;
 pub use transaction_models::*;
 
 mod transaction_ops;
-use transaction_ops::*;
+pub(crate) use transaction_ops::*;
 
 mod session_params;
 pub use session_params::*;
@@ -58,6 +74,9 @@ pub use session_params::*;
 mod consistency_selector;
 pub use consistency_selector::*;
 
+mod snapshot;
+pub use snapshot::*;
+
 mod parent_path_builder;
 pub use parent_path_builder::*;
 
@@ -69,6 +88,20 @@ pub use batch_streaming_writer::*;
 mod batch_simple_writer;
 pub use batch_simple_writer::*;
 
+mod bulk_writer;
+pub use bulk_writer::*;
+
+mod recursive_delete;
+pub use recursive_delete::*;
+
+mod collection_copier;
+pub use collection_copier::*;
+
+#[cfg(feature = "json")]
+mod ndjson;
+#[cfg(feature = "json")]
+pub use ndjson::*;
+
 use crate::errors::{
     FirestoreError, FirestoreInvalidParametersError, FirestoreInvalidParametersPublicDetails,
 };
@@ -78,11 +111,25 @@ use std::sync::Arc;
 mod transform_models;
 pub use transform_models::*;
 
+mod retry_policy;
+pub use retry_policy::*;
+
+mod request_timeout;
+pub(crate) use request_timeout::*;
+
 struct FirestoreDbInner {
     database_path: String,
     doc_path: String,
     options: FirestoreDbOptions,
     client: GoogleApi<FirestoreClient<GoogleAuthMiddleware>>,
+    client_params: FirestoreDbClientParams,
+    query_ext_client: tokio::sync::OnceCell<GoogleApi<query_ext_api::FirestoreQueryExtClient>>,
+}
+
+struct FirestoreDbClientParams {
+    api_url: String,
+    token_scopes: Vec<String>,
+    token_source_type: TokenSourceType,
 }
 
 #[derive(Clone)]
@@ -156,10 +203,10 @@ impl FirestoreDb {
 
         let client = GoogleApiClient::from_function_with_token_source(
             FirestoreClient::new,
-            effective_firebase_api_url,
+            effective_firebase_api_url.clone(),
             Some(firestore_database_path.clone()),
-            token_scopes,
-            token_source_type,
+            token_scopes.clone(),
+            token_source_type.clone(),
         )
         .await?;
 
@@ -167,6 +214,12 @@ impl FirestoreDb {
             database_path: firestore_database_path,
             doc_path: firestore_database_doc_path,
             client,
+            client_params: FirestoreDbClientParams {
+                api_url: effective_firebase_api_url,
+                token_scopes,
+                token_source_type,
+            },
+            query_ext_client: tokio::sync::OnceCell::new(),
             options,
         };
 
@@ -209,7 +262,7 @@ impl FirestoreDb {
             "-ping-".to_string(),
             self.get_database_path().clone(),
             None,
-            0,
+            FirestoreRetryPolicy::no_retries().new_state(),
         )
         .await
         .ok();
@@ -257,6 +310,26 @@ impl FirestoreDb {
         &self.inner.client
     }
 
+    // Created on the first use, since only explain and vector search queries need it
+    pub(crate) async fn query_ext_client(
+        &self,
+    ) -> FirestoreResult<query_ext_api::FirestoreQueryExtClient> {
+        let client = self
+            .inner
+            .query_ext_client
+            .get_or_try_init(|| {
+                GoogleApiClient::from_function_with_token_source(
+                    gcloud_sdk::tonic::client::Grpc::new,
+                    self.inner.client_params.api_url.clone(),
+                    Some(self.inner.database_path.clone()),
+                    self.inner.client_params.token_scopes.clone(),
+                    self.inner.client_params.token_source_type.clone(),
+                )
+            })
+            .await?;
+        Ok(client.get())
+    }
+
     #[inline]
     pub fn clone_with_session_params(&self, session_params: FirestoreDbSessionParams) -> Self {
         Self {
@@ -285,6 +358,51 @@ impl FirestoreDb {
         )
     }
 
+    #[inline]
+    pub fn clone_with_retry_policy(&self, retry_policy: FirestoreRetryPolicy) -> Self {
+        let existing_session_params = (*self.session_params).clone();
+
+        self.clone_with_session_params(existing_session_params.with_retry_policy(retry_policy))
+    }
+
+    #[inline]
+    pub fn clone_with_query_recorder(&self, query_recorder: FirestoreQueryRecorder) -> Self {
+        let existing_session_params = (*self.session_params).clone();
+
+        self.clone_with_session_params(existing_session_params.with_query_recorder(query_recorder))
+    }
+
+    pub(crate) fn record_query(&self, params: &FirestoreQueryParams) {
+        if let Some(query_recorder) = &self.session_params.query_recorder {
+            query_recorder.record(params);
+        }
+    }
+
+    pub fn get_retry_policy(&self) -> FirestoreRetryPolicy {
+        current_request_retry_policy()
+            .or_else(|| self.session_params.retry_policy.clone())
+            .or_else(|| self.inner.options.retry_policy.clone())
+            .unwrap_or_else(|| {
+                FirestoreRetryPolicy::new().with_max_retries(self.inner.options.max_retries)
+            })
+    }
+
+    pub(crate) fn create_request<T>(&self, message: T) -> gcloud_sdk::tonic::Request<T> {
+        let mut request = gcloud_sdk::tonic::Request::new(message);
+        if let Some(timeout) = current_request_timeout().or(self.inner.options.timeout) {
+            request.set_timeout(timeout);
+        }
+        request
+    }
+
+    pub(crate) async fn with_retries<T, FN, F>(&self, span: &Span, op: FN) -> FirestoreResult<T>
+    where
+        FN: FnMut() -> F,
+        F: std::future::Future<Output = FirestoreResult<T>>,
+    {
+        retry_with_policy(&self.get_retry_policy(), span, op).await
+    }
+
     #[cfg(feature = "caching")]
     pub fn with_cache(&self, cache_mode: crate::FirestoreDbSessionCacheMode) -> Self {
         let existing_session_params = (*self.session_params).clone();
@@ -0,0 +1,529 @@
+use crate::errors::*;
+use crate::firestore_json::{firestore_fields_from_json, firestore_fields_to_json};
+use crate::{
+    FirestoreBulkWriteFailure, FirestoreBulkWriterOptions, FirestoreBulkWriterSummary, FirestoreDb,
+    FirestoreJsonMode, FirestoreListCollectionIdsParams, FirestoreListDocParams,
+    FirestoreListingSupport, FirestoreResult,
+};
+use futures::TryStreamExt;
+use gcloud_sdk::google::firestore::v1::{
+    precondition, value, write, Document, Precondition, Value, Write,
+};
+use rsb_derive::*;
+use std::collections::{HashMap, VecDeque};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::*;
+
+const NDJSON_NAME_FIELD: &str = "name";
+const NDJSON_FIELDS_FIELD: &str = "fields";
+
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreExportOptions {
+    /// Exports the subcollections of the exported documents as well.
+    #[default = "false"]
+    pub recursive: bool,
+
+    #[default = "100"]
+    pub page_size: usize,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct FirestoreExportResult {
+    pub exported_documents: usize,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FirestoreImportMode {
+    /// Imports only the documents that don't exist yet.
+    CreateOnly,
+    /// Creates or overwrites the documents.
+    Upsert,
+    /// Only reads and validates the input without writing anything.
+    DryRun,
+}
+
+#[derive(Debug, PartialEq, Clone, Builder)]
+pub struct FirestoreImportOptions {
+    #[default = "FirestoreImportMode::Upsert"]
+    pub mode: FirestoreImportMode,
+
+    pub bulk_writer_options: Option<FirestoreBulkWriterOptions>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FirestoreImportResult {
+    pub read_documents: usize,
+    pub imported_documents: usize,
+    pub skipped_documents: usize,
+    pub failures: Vec<FirestoreBulkWriteFailure>,
+}
+
+impl FirestoreImportResult {
+    fn add_bulk_writer_summary(
+        &mut self,
+        mode: FirestoreImportMode,
+        summary: FirestoreBulkWriterSummary,
+    ) {
+        self.imported_documents += summary.succeeded;
+        for failure in summary.failures {
+            if mode == FirestoreImportMode::CreateOnly && failure.is_existing_document() {
+                self.skipped_documents += 1;
+            } else {
+                self.failures.push(failure);
+            }
+        }
+    }
+}
+
+impl FirestoreDb {
+    /// Encodes a document as a single NDJSON line with the fields in the typed JSON encoding.
+    /// The document name and the references in the fields are written relative to the documents path,
+    /// so the documents can be imported into another database.
+    pub fn serialize_doc_to_ndjson(&self, doc: &Document) -> FirestoreResult<String> {
+        let name = self.relative_document_name(&doc.name)?;
+        let fields = map_reference_fields(doc.fields.clone(), &|reference| {
+            self.relative_document_name(&reference)
+                .map(|name| name.to_string())
+        })?;
+
+        let mut line = serde_json::Map::new();
+        line.insert(NDJSON_NAME_FIELD.to_string(), name.into());
+        line.insert(
+            NDJSON_FIELDS_FIELD.to_string(),
+            firestore_fields_to_json(&fields, FirestoreJsonMode::Typed)?,
+        );
+
+        serde_json::to_string(&line).map_err(|err| {
+            FirestoreError::SerializeError(FirestoreSerializationError::from_message(
+                err.to_string(),
+            ))
+        })
+    }
+
+    /// Decodes a document from a line produced by `serialize_doc_to_ndjson`,
+    /// resolving the name and the references against the documents path.
+    pub fn deserialize_doc_from_ndjson(&self, line: &str) -> FirestoreResult<Document> {
+        let json: serde_json::Value = serde_json::from_str(line).map_err(|err| {
+            FirestoreError::DeserializeError(FirestoreSerializationError::from_message(
+                err.to_string(),
+            ))
+        })?;
+
+        let name = json
+            .get(NDJSON_NAME_FIELD)
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| {
+                FirestoreError::DeserializeError(FirestoreSerializationError::from_message(
+                    "NDJSON document doesn't have a name",
+                ))
+            })?;
+
+        let fields = match json.get(NDJSON_FIELDS_FIELD) {
+            Some(fields) => firestore_fields_from_json(fields, FirestoreJsonMode::Typed)?,
+            None => Default::default(),
+        };
+
+        Ok(Document {
+            name: format!("{}/{}", self.get_documents_path(), name),
+            fields: map_reference_fields(fields, &|reference| {
+                Ok(format!("{}/{}", self.get_documents_path(), reference))
+            })?,
+            create_time: None,
+            update_time: None,
+        })
+    }
+
+    fn relative_document_name<'a>(&self, name: &'a str) -> FirestoreResult<&'a str> {
+        name.strip_prefix(self.get_documents_path().as_str())
+            .and_then(|name| name.strip_prefix('/'))
+            .ok_or_else(|| {
+                FirestoreError::SerializeError(FirestoreSerializationError::from_message(format!(
+                    "Document {} doesn't belong to {}",
+                    name,
+                    self.get_documents_path()
+                )))
+            })
+    }
+
+    pub async fn export_collection_ndjson<W>(
+        &self,
+        collection_id: &str,
+        writer: &mut W,
+        options: FirestoreExportOptions,
+    ) -> FirestoreResult<FirestoreExportResult>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.export_collection_ndjson_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            writer,
+            options,
+        )
+        .await
+    }
+
+    /// Writes the documents of the collection as NDJSON, one document per line.
+    pub async fn export_collection_ndjson_at<W>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        writer: &mut W,
+        options: FirestoreExportOptions,
+    ) -> FirestoreResult<FirestoreExportResult>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let span = span!(
+            Level::DEBUG,
+            "Firestore Export",
+            "/firestore/collection_name" = collection_id
+        );
+
+        let mut result = FirestoreExportResult::default();
+        let mut collections: VecDeque<(String, String)> =
+            VecDeque::from([(parent.to_string(), collection_id.to_string())]);
+
+        while let Some((parent, collection_id)) = collections.pop_front() {
+            let mut docs = self
+                .stream_list_doc_with_errors(
+                    FirestoreListDocParams::new(collection_id)
+                        .with_parent(parent)
+                        .with_page_size(options.page_size)
+                        .with_show_missing(options.recursive),
+                )
+                .await?;
+
+            let mut listed_names: Vec<String> = Vec::new();
+            while let Some(doc) = docs.try_next().await? {
+                // Missing documents are listed only to find their subcollections
+                if doc.create_time.is_some() {
+                    let mut line = self.serialize_doc_to_ndjson(&doc)?;
+                    line.push('\n');
+                    writer.write_all(line.as_bytes()).await?;
+                    result.exported_documents += 1;
+                }
+                if options.recursive {
+                    listed_names.push(doc.name);
+                }
+            }
+
+            for document_path in listed_names {
+                let mut collection_ids = self
+                    .stream_list_collection_ids_with_errors(
+                        FirestoreListCollectionIdsParams::new()
+                            .with_parent(document_path.clone())
+                            .with_page_size(options.page_size),
+                    )
+                    .await?;
+                while let Some(collection_id) = collection_ids.try_next().await? {
+                    collections.push_back((document_path.clone(), collection_id));
+                }
+            }
+        }
+        writer.flush().await?;
+
+        span.in_scope(|| debug!("Exported {} documents", result.exported_documents));
+
+        Ok(result)
+    }
+
+    /// Reads NDJSON documents produced by the export and writes them using a bulk writer.
+    pub async fn import_ndjson<R>(
+        &self,
+        reader: R,
+        options: FirestoreImportOptions,
+    ) -> FirestoreResult<FirestoreImportResult>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        let span = span!(Level::DEBUG, "Firestore Import");
+
+        let bulk_writer = match options.mode {
+            FirestoreImportMode::DryRun => None,
+            FirestoreImportMode::CreateOnly | FirestoreImportMode::Upsert => Some(
+                self.create_bulk_writer_with_options(
+                    options
+                        .bulk_writer_options
+                        .clone()
+                        .unwrap_or_else(FirestoreBulkWriterOptions::new),
+                )
+                .await?,
+            ),
+        };
+
+        let mut result = FirestoreImportResult::default();
+        let mut lines = reader.lines();
+        let mut line_number = 0;
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let doc = self.deserialize_doc_from_ndjson(&line).map_err(|err| {
+                FirestoreError::DeserializeError(FirestoreSerializationError::from_message(
+                    format!("Invalid document at line {line_number}: {err}"),
+                ))
+            })?;
+            result.read_documents += 1;
+
+            if let Some(bulk_writer) = &bulk_writer {
+                bulk_writer
+                    .add_write(Write {
+                        operation: Some(write::Operation::Update(doc)),
+                        current_document: match options.mode {
+                            FirestoreImportMode::CreateOnly => Some(Precondition {
+                                condition_type: Some(precondition::ConditionType::Exists(false)),
+                            }),
+                            _ => None,
+                        },
+                        ..Default::default()
+                    })
+                    .await?;
+            }
+        }
+
+        if let Some(bulk_writer) = bulk_writer {
+            result.add_bulk_writer_summary(options.mode, bulk_writer.finish().await?);
+        }
+
+        span.in_scope(|| {
+            debug!(
+                "Read {} documents, imported {}, skipped {}, {} failed",
+                result.read_documents,
+                result.imported_documents,
+                result.skipped_documents,
+                result.failures.len()
+            )
+        });
+
+        Ok(result)
+    }
+}
+
+fn map_reference_fields<F>(
+    fields: HashMap<String, Value>,
+    map_reference: &F,
+) -> FirestoreResult<HashMap<String, Value>>
+where
+    F: Fn(String) -> FirestoreResult<String>,
+{
+    fields
+        .into_iter()
+        .map(|(field_name, field_value)| {
+            Ok((field_name, map_reference_value(field_value, map_reference)?))
+        })
+        .collect()
+}
+
+fn map_reference_value<F>(value: Value, map_reference: &F) -> FirestoreResult<Value>
+where
+    F: Fn(String) -> FirestoreResult<String>,
+{
+    let value_type = match value.value_type {
+        Some(value::ValueType::ReferenceValue(reference)) => {
+            Some(value::ValueType::ReferenceValue(map_reference(reference)?))
+        }
+        Some(value::ValueType::ArrayValue(mut array_value)) => {
+            array_value.values = array_value
+                .values
+                .into_iter()
+                .map(|value| map_reference_value(value, map_reference))
+                .collect::<FirestoreResult<Vec<Value>>>()?;
+            Some(value::ValueType::ArrayValue(array_value))
+        }
+        Some(value::ValueType::MapValue(mut map_value)) => {
+            map_value.fields = map_reference_fields(map_value.fields, map_reference)?;
+            Some(value::ValueType::MapValue(map_value))
+        }
+        other => other,
+    };
+    Ok(Value { value_type })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gcloud_sdk::google::firestore::v1::{ArrayValue, MapValue};
+    use gcloud_sdk::google::rpc::Status;
+
+    async fn offline_db() -> FirestoreDb {
+        // The client connects eagerly, so it needs something to connect to
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        FirestoreDb::with_options_token_source(
+            crate::FirestoreDbOptions::new("test-project".to_string())
+                .with_firebase_api_url(format!("http://{addr}")),
+            vec![],
+            gcloud_sdk::TokenSourceType::Json(
+                r#"{"type":"authorized_user","client_id":"id","client_secret":"secret","refresh_token":"token"}"#
+                    .to_string(),
+            ),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn test_value(value_type: value::ValueType) -> Value {
+        Value {
+            value_type: Some(value_type),
+        }
+    }
+
+    fn test_doc(db: &FirestoreDb, name: &str) -> Document {
+        Document {
+            name: format!("{}/{name}", db.get_documents_path()),
+            fields: [
+                (
+                    "some_string".to_string(),
+                    test_value(value::ValueType::StringValue("test".to_string())),
+                ),
+                (
+                    "some_num".to_string(),
+                    test_value(value::ValueType::IntegerValue(42)),
+                ),
+                (
+                    "some_double".to_string(),
+                    test_value(value::ValueType::DoubleValue(42.5)),
+                ),
+                (
+                    "created_at".to_string(),
+                    test_value(value::ValueType::TimestampValue(
+                        gcloud_sdk::prost_types::Timestamp {
+                            seconds: 1672628645,
+                            nanos: 123456000,
+                        },
+                    )),
+                ),
+                (
+                    "nested".to_string(),
+                    test_value(value::ValueType::MapValue(MapValue {
+                        fields: [(
+                            "refs".to_string(),
+                            test_value(value::ValueType::ArrayValue(ArrayValue {
+                                values: vec![
+                                    test_value(value::ValueType::ReferenceValue(format!(
+                                        "{}/parents/parent",
+                                        db.get_documents_path()
+                                    ))),
+                                    test_value(value::ValueType::NullValue(0)),
+                                ],
+                            })),
+                        )]
+                        .into(),
+                    })),
+                ),
+            ]
+            .into(),
+            create_time: None,
+            update_time: None,
+        }
+    }
+
+    fn test_failure(code: gcloud_sdk::tonic::Code) -> FirestoreBulkWriteFailure {
+        FirestoreBulkWriteFailure {
+            write: Write::default(),
+            status: Status {
+                code: code as i32,
+                ..Default::default()
+            },
+            attempts: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn ndjson_round_trip_test() {
+        let db = offline_db().await;
+        let docs = vec![
+            test_doc(&db, "parents/parent"),
+            test_doc(&db, "parents/parent/children/child"),
+        ];
+
+        let mut exported = String::new();
+        for doc in &docs {
+            exported.push_str(&db.serialize_doc_to_ndjson(doc).unwrap());
+            exported.push_str("\n\n");
+        }
+        assert!(exported.contains(r#"{"$reference":"parents/parent"}"#));
+        assert!(!exported.contains(db.get_documents_path().as_str()));
+
+        let imported: Vec<Document> = exported
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| db.deserialize_doc_from_ndjson(line).unwrap())
+            .collect();
+        assert_eq!(imported, docs);
+
+        let dry_run = db
+            .import_ndjson(
+                exported.as_bytes(),
+                FirestoreImportOptions::new().with_mode(FirestoreImportMode::DryRun),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            dry_run,
+            FirestoreImportResult {
+                read_documents: 2,
+                ..Default::default()
+            }
+        );
+
+        assert!(matches!(
+            db.import_ndjson(
+                "{\"fields\":{}}\n".as_bytes(),
+                FirestoreImportOptions::new().with_mode(FirestoreImportMode::DryRun),
+            )
+            .await,
+            Err(FirestoreError::DeserializeError(_))
+        ));
+
+        let mut other_doc = docs[0].clone();
+        other_doc.name = "projects/other/databases/(default)/documents/parents/parent".to_string();
+        assert!(db.serialize_doc_to_ndjson(&other_doc).is_err());
+
+        let mut other_reference_doc = docs[0].clone();
+        other_reference_doc.fields.insert(
+            "other".to_string(),
+            test_value(value::ValueType::ReferenceValue(
+                "projects/other/databases/(default)/documents/parents/parent".to_string(),
+            )),
+        );
+        assert!(db.serialize_doc_to_ndjson(&other_reference_doc).is_err());
+    }
+
+    #[test]
+    fn import_skips_only_existing_documents_for_create_only() {
+        let summary = FirestoreBulkWriterSummary {
+            succeeded: 3,
+            failures: vec![
+                test_failure(gcloud_sdk::tonic::Code::AlreadyExists),
+                test_failure(gcloud_sdk::tonic::Code::AlreadyExists),
+                test_failure(gcloud_sdk::tonic::Code::PermissionDenied),
+            ],
+        };
+
+        let mut create_only = FirestoreImportResult::default();
+        create_only.add_bulk_writer_summary(FirestoreImportMode::CreateOnly, summary.clone());
+        assert_eq!(create_only.imported_documents, 3);
+        assert_eq!(create_only.skipped_documents, 2);
+        assert_eq!(
+            create_only.failures,
+            vec![test_failure(gcloud_sdk::tonic::Code::PermissionDenied)]
+        );
+
+        let mut upsert = FirestoreImportResult::default();
+        upsert.add_bulk_writer_summary(FirestoreImportMode::Upsert, summary);
+        assert_eq!(upsert.imported_documents, 3);
+        assert_eq!(upsert.skipped_documents, 0);
+        assert_eq!(upsert.failures.len(), 3);
+    }
+}
@@ -1,6 +1,8 @@
+use crate::FirestoreRetryPolicy;
 use rsb_derive::Builder;
+use std::time::Duration;
 
-#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+#[derive(Debug, PartialEq, Clone, Builder)]
 pub struct FirestoreDbOptions {
     pub google_project_id: String,
 
@@ -11,6 +13,12 @@ pub struct FirestoreDbOptions {
     pub max_retries: usize,
 
     pub firebase_api_url: Option<String>,
+
+    /// Retry policy for all calls. If not specified, the default policy with `max_retries` is used.
+    pub retry_policy: Option<FirestoreRetryPolicy>,
+
+    /// Default deadline for the requests. Not applied to the long-living listen and write streams.
+    pub timeout: Option<Duration>,
 }
 
 pub const FIREBASE_DEFAULT_DATABASE_ID: &str = "(default)";
@@ -1,10 +1,11 @@
+use crate::db::explain_models::explain_unsupported_error;
+use crate::db::query_ext_api::{run_query_ext, ExplainOptions, RunQueryExtRequest};
 use crate::*;
 use async_trait::async_trait;
 use chrono::prelude::*;
 use futures::future::BoxFuture;
 use futures::stream::BoxStream;
 use futures::FutureExt;
-use futures::TryFutureExt;
 use futures::TryStreamExt;
 use futures::{future, StreamExt};
 use gcloud_sdk::google::firestore::v1::*;
@@ -46,6 +47,27 @@ pub trait FirestoreQuerySupport {
         for<'de> T: Deserialize<'de>,
         T: Send + 'a;
 
+    /// Explaining the query plan and optionally running the query to collect the execution statistics.
+    /// Not supported by default.
+    async fn explain_query_doc(
+        &self,
+        _params: FirestoreQueryParams,
+        _options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        Err(explain_unsupported_error())
+    }
+
+    async fn explain_query_obj<T>(
+        &self,
+        _params: FirestoreQueryParams,
+        _options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        Err(explain_unsupported_error())
+    }
+
     fn stream_partition_cursors_with_errors(
         &self,
         params: FirestorePartitionQueryParams,
@@ -72,7 +94,7 @@ impl FirestoreDb {
         &self,
         params: FirestoreQueryParams,
     ) -> FirestoreResult<gcloud_sdk::tonic::Request<RunQueryRequest>> {
-        Ok(gcloud_sdk::tonic::Request::new(RunQueryRequest {
+        Ok(self.create_request(RunQueryRequest {
             parent: params
                 .parent
                 .as_ref()
@@ -88,30 +110,67 @@ impl FirestoreDb {
         }))
     }
 
-    fn stream_query_doc_with_retries(
+    fn create_query_ext_request(
         &self,
         params: FirestoreQueryParams,
-        retries: usize,
-        span: Span,
-    ) -> BoxFuture<FirestoreResult<BoxStream<FirestoreResult<Option<Document>>>>> {
-        async move {
-            let query_request = self.create_query_request(params.clone())?;
-            let begin_query_utc: DateTime<Utc> = Utc::now();
+        explain_options: Option<ExplainOptions>,
+    ) -> FirestoreResult<RunQueryExtRequest> {
+        Ok(RunQueryExtRequest {
+            parent: params
+                .parent
+                .as_ref()
+                .unwrap_or_else(|| self.get_documents_path())
+                .clone(),
+            consistency_selector: self
+                .session_params
+                .consistency_selector
+                .as_ref()
+                .map(|selector| selector.try_into())
+                .transpose()?,
+            structured_query: Some(params.into()),
+            explain_options,
+        })
+    }
 
-            match self
+    // Vector search queries aren't supported by the generated client, so they go through the extended one
+    async fn run_query_stream(
+        &self,
+        params: FirestoreQueryParams,
+    ) -> FirestoreResult<BoxStream<'static, FirestoreResult<Option<Document>>>> {
+        if params.find_nearest.is_some() {
+            let query_request = self.create_request(self.create_query_ext_request(params, None)?);
+            let mut client = self.query_ext_client().await?;
+            Ok(run_query_ext(&mut client, query_request)
+                .await?
+                .into_inner()
+                .map_ok(|r| r.document)
+                .map_err(|e| e.into())
+                .boxed())
+        } else {
+            let query_request = self.create_query_request(params)?;
+            Ok(self
                 .client()
                 .get()
                 .run_query(query_request)
+                .await?
+                .into_inner()
+                .map_ok(|r| r.document)
                 .map_err(|e| e.into())
-                .await
-            {
-                Ok(query_response) => {
-                    let query_stream = query_response
-                        .into_inner()
-                        .map_ok(|r| r.document)
-                        .map_err(|e| e.into())
-                        .boxed();
+                .boxed())
+        }
+    }
 
+    fn stream_query_doc_with_retries(
+        &self,
+        params: FirestoreQueryParams,
+        mut retry_state: FirestoreRetryState,
+        span: Span,
+    ) -> BoxFuture<FirestoreResult<BoxStream<FirestoreResult<Option<Document>>>>> {
+        async move {
+            let begin_query_utc: DateTime<Utc> = Utc::now();
+
+            match self.run_query_stream(params.clone()).await {
+                Ok(query_stream) => {
                     let end_query_utc: DateTime<Utc> = Utc::now();
                     let query_duration = end_query_utc.signed_duration_since(begin_query_utc);
 
@@ -129,22 +188,14 @@ impl FirestoreDb {
 
                     Ok(query_stream)
                 }
-                Err(err) => match err {
-                    FirestoreError::DatabaseError(ref db_err)
-                        if db_err.retry_possible && retries < self.inner.options.max_retries =>
-                    {
-                        warn!(
-                            "Failed with {}. Retrying: {}/{}",
-                            db_err,
-                            retries + 1,
-                            self.inner.options.max_retries
-                        );
-
-                        self.stream_query_doc_with_retries(params, retries + 1, span)
+                Err(err) => {
+                    if retry_state.wait_retry(&err, &span).await {
+                        self.stream_query_doc_with_retries(params, retry_state, span)
                             .await
+                    } else {
+                        Err(err)
                     }
-                    _ => Err(err),
-                },
+                }
             }
         }
         .boxed()
@@ -277,7 +328,10 @@ impl FirestoreQuerySupport for FirestoreDb {
             "/firestore/response_time" = field::Empty
         );
 
-        let doc_stream = self.stream_query_doc_with_retries(params, 0, span).await?;
+        self.record_query(&params);
+        let doc_stream = self
+            .stream_query_doc_with_retries(params, self.get_retry_policy().new_state(), span)
+            .await?;
 
         Ok(Box::pin(doc_stream.filter_map(|doc_res| {
             future::ready(match doc_res {
@@ -338,11 +392,94 @@ impl FirestoreQuerySupport for FirestoreDb {
         })))
     }
 
+    async fn explain_query_doc(
+        &self,
+        params: FirestoreQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        let collection_str = params.collection_id.to_string();
+
+        let span = span!(
+            Level::DEBUG,
+            "Firestore Explain Query",
+            "/firestore/collection_name" = collection_str.as_str(),
+            "/firestore/response_time" = field::Empty
+        );
+
+        self.record_query(&params);
+        let explain_request = self.create_query_ext_request(params, Some(options.into()))?;
+
+        let begin_query_utc: DateTime<Utc> = Utc::now();
+
+        let explain_result = self
+            .with_retries(&span, || {
+                let request = self.create_request(explain_request.clone());
+                async move {
+                    let mut client = self.query_ext_client().await?;
+                    let mut response_stream =
+                        run_query_ext(&mut client, request).await?.into_inner();
+
+                    let mut results = Vec::new();
+                    let mut metrics = None;
+                    while let Some(response) = response_stream.message().await? {
+                        if let Some(doc) = response.document {
+                            results.push(doc);
+                        }
+                        if let Some(explain_metrics) = response.explain_metrics {
+                            metrics = Some(explain_metrics.into());
+                        }
+                    }
+                    Ok(FirestoreExplainResult::new(results).opt_metrics(metrics))
+                }
+            })
+            .await?;
+
+        let end_query_utc: DateTime<Utc> = Utc::now();
+        let query_duration = end_query_utc.signed_duration_since(begin_query_utc);
+
+        span.record(
+            "/firestore/response_time",
+            query_duration.num_milliseconds(),
+        );
+        span.in_scope(|| {
+            debug!(
+                "Explaining query in {} took {}ms",
+                collection_str,
+                query_duration.num_milliseconds()
+            );
+        });
+
+        Ok(explain_result)
+    }
+
+    async fn explain_query_obj<T>(
+        &self,
+        params: FirestoreQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let explain_result = self.explain_query_doc(params, options).await?;
+        Ok(FirestoreExplainResult::new(
+            explain_result
+                .results
+                .iter()
+                .map(|doc| Self::deserialize_doc_to(doc))
+                .collect::<FirestoreResult<Vec<T>>>()?,
+        )
+        .opt_metrics(explain_result.metrics))
+    }
+
     fn stream_partition_cursors_with_errors(
         &self,
         params: FirestorePartitionQueryParams,
     ) -> BoxFuture<FirestoreResult<PeekableBoxStream<FirestoreResult<FirestoreQueryCursor>>>> {
         Box::pin(async move {
+            params
+                .query_params
+                .ensure_without_find_nearest("partition queries")?;
+            self.record_query(&params.query_params);
             let consistency_selector: Option<
                 gcloud_sdk::google::firestore::v1::partition_query_request::ConsistencySelector,
             > = self
@@ -357,7 +494,7 @@ impl FirestoreQuerySupport for FirestoreDb {
                     Some((params, consistency_selector)),
                     move |maybe_params| async move {
                         if let Some((params, maybe_consistency_selector)) = maybe_params {
-                            let request = gcloud_sdk::tonic::Request::new(PartitionQueryRequest {
+                            let partition_request = PartitionQueryRequest {
                                 page_size: params.page_size as i32,
                                 partition_count: params.partition_count as i64,
                                 parent: params
@@ -373,9 +510,22 @@ impl FirestoreQuerySupport for FirestoreDb {
                                     ),
                                 ),
                                 page_token: params.page_token.clone().unwrap_or_default(),
-                            });
-
-                            match self.client().get().partition_query(request).await {
+                            };
+
+                            let span = span!(Level::DEBUG, "Firestore Partition Query");
+                            match self
+                                .with_retries(&span, || {
+                                    let request = self.create_request(partition_request.clone());
+                                    async move {
+                                        self.client()
+                                            .get()
+                                            .partition_query(request)
+                                            .await
+                                            .map_err(FirestoreError::from)
+                                    }
+                                })
+                                .await
+                            {
                                 Ok(response) => {
                                     let partition_response = response.into_inner();
                                     let firestore_cursors: Vec<FirestoreQueryCursor> =
@@ -399,7 +549,7 @@ impl FirestoreQuerySupport for FirestoreDb {
                                         Some((Ok(firestore_cursors), None))
                                     }
                                 }
-                                Err(err) => Some((Err(FirestoreError::from(err)), None)),
+                                Err(err) => Some((Err(err), None)),
                             }
                         } else {
                             None
@@ -0,0 +1,289 @@
+// The generated Firestore v1 protos in gcloud-sdk don't have the query explain and
+// find_nearest fields yet, so the query requests and responses are extended here and
+// sent with a plain gRPC client over the same authenticated channel type.
+use gcloud_sdk::google::firestore::v1::{
+    run_aggregation_query_request, run_query_request, structured_query, AggregationResult,
+    Document, StructuredQuery, Value,
+};
+use gcloud_sdk::tonic::client::Grpc;
+use gcloud_sdk::tonic::codec::{ProstCodec, Streaming};
+use gcloud_sdk::tonic::codegen::http::uri::PathAndQuery;
+use gcloud_sdk::tonic::{Code, GrpcMethod, Request, Response, Status};
+use gcloud_sdk::GoogleAuthMiddleware;
+use prost::bytes::{Buf, BufMut};
+use prost::encoding::{DecodeContext, WireType};
+use prost::DecodeError;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ExplainOptions {
+    #[prost(bool, tag = "1")]
+    pub analyze: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ExplainMetrics {
+    #[prost(message, optional, tag = "1")]
+    pub plan_summary: Option<PlanSummary>,
+    #[prost(message, optional, tag = "2")]
+    pub execution_stats: Option<ExecutionStats>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct PlanSummary {
+    #[prost(message, repeated, tag = "1")]
+    pub indexes_used: Vec<gcloud_sdk::prost_types::Struct>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ExecutionStats {
+    #[prost(int64, tag = "1")]
+    pub results_returned: i64,
+    #[prost(message, optional, tag = "3")]
+    pub execution_duration: Option<gcloud_sdk::prost_types::Duration>,
+    #[prost(int64, tag = "4")]
+    pub read_operations: i64,
+    #[prost(message, optional, tag = "5")]
+    pub debug_stats: Option<gcloud_sdk::prost_types::Struct>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct FindNearest {
+    #[prost(message, optional, tag = "1")]
+    pub vector_field: Option<structured_query::FieldReference>,
+    #[prost(message, optional, tag = "2")]
+    pub query_vector: Option<Value>,
+    #[prost(int32, tag = "3")]
+    pub distance_measure: i32,
+    #[prost(message, optional, tag = "4")]
+    pub limit: Option<i32>,
+}
+
+pub(crate) const FIND_NEAREST_TAG: u32 = 9;
+
+/// `StructuredQuery` with the `find_nearest` field appended on the wire.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub(crate) struct StructuredQueryExt {
+    pub query: StructuredQuery,
+    pub find_nearest: Option<FindNearest>,
+}
+
+impl prost::Message for StructuredQueryExt {
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        self.query.encode_raw(buf);
+        if let Some(find_nearest) = &self.find_nearest {
+            prost::encoding::message::encode(FIND_NEAREST_TAG, find_nearest, buf);
+        }
+    }
+
+    fn merge_field<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        if tag == FIND_NEAREST_TAG {
+            prost::encoding::message::merge(
+                wire_type,
+                self.find_nearest.get_or_insert_with(Default::default),
+                buf,
+                ctx,
+            )
+        } else {
+            self.query.merge_field(tag, wire_type, buf, ctx)
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.query.encoded_len()
+            + self.find_nearest.as_ref().map_or(0, |find_nearest| {
+                prost::encoding::message::encoded_len(FIND_NEAREST_TAG, find_nearest)
+            })
+    }
+
+    fn clear(&mut self) {
+        self.query.clear();
+        self.find_nearest = None;
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct RunQueryExtRequest {
+    #[prost(string, tag = "1")]
+    pub parent: String,
+    #[prost(message, optional, tag = "2")]
+    pub structured_query: Option<StructuredQueryExt>,
+    #[prost(oneof = "run_query_request::ConsistencySelector", tags = "5, 6, 7")]
+    pub consistency_selector: Option<run_query_request::ConsistencySelector>,
+    #[prost(message, optional, tag = "10")]
+    pub explain_options: Option<ExplainOptions>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct RunQueryExtResponse {
+    #[prost(message, optional, tag = "1")]
+    pub document: Option<Document>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub transaction: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub read_time: Option<gcloud_sdk::prost_types::Timestamp>,
+    #[prost(int32, tag = "4")]
+    pub skipped_results: i32,
+    #[prost(bool, optional, tag = "6")]
+    pub done: Option<bool>,
+    #[prost(message, optional, tag = "11")]
+    pub explain_metrics: Option<ExplainMetrics>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct RunAggregationQueryExtRequest {
+    #[prost(string, tag = "1")]
+    pub parent: String,
+    #[prost(oneof = "run_aggregation_query_request::QueryType", tags = "2")]
+    pub query_type: Option<run_aggregation_query_request::QueryType>,
+    #[prost(
+        oneof = "run_aggregation_query_request::ConsistencySelector",
+        tags = "4, 5, 6"
+    )]
+    pub consistency_selector: Option<run_aggregation_query_request::ConsistencySelector>,
+    #[prost(message, optional, tag = "8")]
+    pub explain_options: Option<ExplainOptions>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct RunAggregationQueryExtResponse {
+    #[prost(message, optional, tag = "1")]
+    pub result: Option<AggregationResult>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub transaction: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub read_time: Option<gcloud_sdk::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "10")]
+    pub explain_metrics: Option<ExplainMetrics>,
+}
+
+pub(crate) type FirestoreQueryExtClient = Grpc<GoogleAuthMiddleware>;
+
+pub(crate) async fn run_query_ext(
+    client: &mut FirestoreQueryExtClient,
+    request: Request<RunQueryExtRequest>,
+) -> Result<Response<Streaming<RunQueryExtResponse>>, Status> {
+    server_streaming(client, request, "RunQuery").await
+}
+
+pub(crate) async fn run_aggregation_query_ext(
+    client: &mut FirestoreQueryExtClient,
+    request: Request<RunAggregationQueryExtRequest>,
+) -> Result<Response<Streaming<RunAggregationQueryExtResponse>>, Status> {
+    server_streaming(client, request, "RunAggregationQuery").await
+}
+
+async fn server_streaming<M1, M2>(
+    client: &mut FirestoreQueryExtClient,
+    mut request: Request<M1>,
+    method: &'static str,
+) -> Result<Response<Streaming<M2>>, Status>
+where
+    M1: prost::Message + Send + Sync + 'static,
+    M2: prost::Message + Default + Send + Sync + 'static,
+{
+    client
+        .ready()
+        .await
+        .map_err(|e| Status::new(Code::Unknown, format!("Service was not ready: {}", e)))?;
+    let path = PathAndQuery::try_from(format!("/google.firestore.v1.Firestore/{method}"))
+        .map_err(|e| Status::new(Code::Internal, format!("Invalid method path: {e}")))?;
+    request
+        .extensions_mut()
+        .insert(GrpcMethod::new("google.firestore.v1.Firestore", method));
+    client
+        .server_streaming(request, path, ProstCodec::default())
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gcloud_sdk::google::firestore::v1::{
+        run_query_response, value, RunAggregationQueryResponse, RunQueryRequest, RunQueryResponse,
+    };
+    use prost::Message;
+
+    #[test]
+    fn query_ext_messages_are_wire_compatible() {
+        let request = RunQueryExtRequest {
+            parent: "test-parent".to_string(),
+            structured_query: Some(StructuredQueryExt {
+                query: StructuredQuery {
+                    limit: Some(5),
+                    ..Default::default()
+                },
+                find_nearest: Some(FindNearest {
+                    vector_field: Some(structured_query::FieldReference {
+                        field_path: "embedding".to_string(),
+                    }),
+                    query_vector: Some(Value {
+                        value_type: Some(value::ValueType::DoubleValue(1.0)),
+                    }),
+                    distance_measure: 2,
+                    limit: Some(3),
+                }),
+            }),
+            consistency_selector: None,
+            explain_options: Some(ExplainOptions { analyze: true }),
+        };
+        let encoded = request.encode_to_vec();
+        let decoded = RunQueryRequest::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.parent, "test-parent");
+        assert!(matches!(
+            decoded.query_type,
+            Some(run_query_request::QueryType::StructuredQuery(ref query)) if query.limit == Some(5)
+        ));
+        assert_eq!(
+            RunQueryExtRequest::decode(encoded.as_slice()).unwrap(),
+            request
+        );
+
+        let read_time = gcloud_sdk::prost_types::Timestamp {
+            seconds: 1700000000,
+            nanos: 5,
+        };
+        let response = RunQueryResponse {
+            document: Some(Document {
+                name: "test-doc".to_string(),
+                ..Default::default()
+            }),
+            transaction: b"test-tx".to_vec(),
+            read_time: Some(read_time.clone()),
+            skipped_results: 3,
+            continuation_selector: Some(run_query_response::ContinuationSelector::Done(true)),
+        };
+        let decoded = RunQueryExtResponse::decode(response.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(
+            decoded.document.map(|doc| doc.name),
+            Some("test-doc".into())
+        );
+        assert_eq!(decoded.transaction, b"test-tx".to_vec());
+        assert_eq!(decoded.read_time, Some(read_time.clone()));
+        assert_eq!(decoded.skipped_results, 3);
+        assert_eq!(decoded.done, Some(true));
+        assert!(decoded.explain_metrics.is_none());
+
+        let response = RunAggregationQueryResponse {
+            result: Some(AggregationResult::default()),
+            transaction: b"test-tx".to_vec(),
+            read_time: Some(read_time.clone()),
+        };
+        let decoded =
+            RunAggregationQueryExtResponse::decode(response.encode_to_vec().as_slice()).unwrap();
+        assert!(decoded.result.is_some());
+        assert_eq!(decoded.transaction, b"test-tx".to_vec());
+        assert_eq!(decoded.read_time, Some(read_time));
+    }
+}
@@ -0,0 +1,466 @@
+use crate::{
+    FirestoreQueryCollection, FirestoreQueryDirection, FirestoreQueryFilter,
+    FirestoreQueryFilterCompare, FirestoreQueryFilterCompositeOperator, FirestoreQueryFilterUnary,
+    FirestoreQueryParams, FIRESTORE_DOCUMENT_NAME_FIELD,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FirestoreIndexQueryScope {
+    Collection,
+    CollectionGroup,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FirestoreIndexFieldOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FirestoreIndexArrayConfig {
+    Contains,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Default, Serialize, Deserialize)]
+pub struct FirestoreIndexVectorFlat {}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
+pub struct FirestoreIndexVectorConfig {
+    pub dimension: usize,
+    pub flat: FirestoreIndexVectorFlat,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirestoreIndexField {
+    pub field_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<FirestoreIndexFieldOrder>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub array_config: Option<FirestoreIndexArrayConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector_config: Option<FirestoreIndexVectorConfig>,
+}
+
+impl FirestoreIndexField {
+    fn ordered(field_path: String, order: FirestoreIndexFieldOrder) -> Self {
+        Self {
+            field_path,
+            order: Some(order),
+            array_config: None,
+            vector_config: None,
+        }
+    }
+}
+
+/// Composite index in the format of the `indexes` entries of `firestore.indexes.json`.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirestoreCompositeIndex {
+    pub collection_group: String,
+    pub query_scope: FirestoreIndexQueryScope,
+    pub fields: Vec<FirestoreIndexField>,
+}
+
+/// Index definitions file that can be deployed with `firebase deploy --only firestore:indexes`.
+#[derive(Debug, Eq, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct FirestoreIndexDefinitions {
+    pub indexes: Vec<FirestoreCompositeIndex>,
+}
+
+impl FirestoreIndexDefinitions {
+    /// Unique indexes required by the queries, sorted to keep the file stable.
+    pub fn from_queries<'a, I>(queries: I) -> Self
+    where
+        I: IntoIterator<Item = &'a FirestoreQueryParams>,
+    {
+        let indexes: BTreeSet<FirestoreCompositeIndex> = queries
+            .into_iter()
+            .flat_map(|query| query.required_composite_indexes())
+            .collect();
+        Self {
+            indexes: indexes.into_iter().collect(),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json_string(&self) -> crate::FirestoreResult<String> {
+        serde_json::to_string_pretty(self).map_err(|err| {
+            crate::errors::FirestoreError::SerializeError(
+                crate::errors::FirestoreSerializationError::from_message(err.to_string()),
+            )
+        })
+    }
+}
+
+#[derive(Clone)]
+enum FirestoreIndexFilterKind {
+    Equality,
+    ArrayContains,
+    Inequality,
+}
+
+impl FirestoreQueryParams {
+    /// Composite indexes the query needs, one for each disjunction of OR filters.
+    /// Queries served by the automatic single-field indexes return nothing.
+    pub fn required_composite_indexes(&self) -> Vec<FirestoreCompositeIndex> {
+        let (query_scope, collection_ids) = match &self.collection_id {
+            FirestoreQueryCollection::Single(collection_id)
+                if !self.all_descendants.unwrap_or(false) =>
+            {
+                (
+                    FirestoreIndexQueryScope::Collection,
+                    vec![collection_id.clone()],
+                )
+            }
+            FirestoreQueryCollection::Single(collection_id) => (
+                FirestoreIndexQueryScope::CollectionGroup,
+                vec![collection_id.clone()],
+            ),
+            FirestoreQueryCollection::Group(collection_ids) => (
+                FirestoreIndexQueryScope::CollectionGroup,
+                collection_ids.clone(),
+            ),
+        };
+
+        let conjunctions = self
+            .filter
+            .as_ref()
+            .map(filter_conjunctions)
+            .unwrap_or_else(|| vec![vec![]]);
+
+        let mut index_fields: BTreeSet<Vec<FirestoreIndexField>> = BTreeSet::new();
+        for conjunction in conjunctions {
+            if let Some(fields) = self.conjunction_index_fields(conjunction) {
+                index_fields.insert(fields);
+            }
+        }
+
+        collection_ids
+            .iter()
+            .flat_map(|collection_id| {
+                index_fields
+                    .iter()
+                    .map(move |fields| FirestoreCompositeIndex {
+                        collection_group: collection_id.clone(),
+                        query_scope,
+                        fields: fields.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    fn conjunction_index_fields(
+        &self,
+        conjunction: Vec<(&str, FirestoreIndexFilterKind)>,
+    ) -> Option<Vec<FirestoreIndexField>> {
+        let mut ordered: Vec<FirestoreIndexField> = self
+            .order_by
+            .iter()
+            .flatten()
+            .filter(|order| order.field_name != FIRESTORE_DOCUMENT_NAME_FIELD)
+            .map(|order| {
+                FirestoreIndexField::ordered(
+                    order.field_name.clone(),
+                    match order.direction {
+                        FirestoreQueryDirection::Ascending => FirestoreIndexFieldOrder::Ascending,
+                        FirestoreQueryDirection::Descending => FirestoreIndexFieldOrder::Descending,
+                    },
+                )
+            })
+            .collect();
+
+        // Inequality fields are ordered implicitly after the explicit ordering
+        let implicit_order = ordered
+            .last()
+            .and_then(|field| field.order)
+            .unwrap_or(FirestoreIndexFieldOrder::Ascending);
+        let inequality_fields: BTreeSet<&str> = conjunction
+            .iter()
+            .filter(|(_, kind)| matches!(kind, FirestoreIndexFilterKind::Inequality))
+            .map(|(field_name, _)| *field_name)
+            .collect();
+        for field_name in inequality_fields {
+            if !ordered.iter().any(|field| field.field_path == field_name) {
+                ordered.push(FirestoreIndexField::ordered(
+                    field_name.to_string(),
+                    implicit_order,
+                ));
+            }
+        }
+
+        let mut equality_fields: BTreeSet<&str> = BTreeSet::new();
+        let mut array_fields: BTreeSet<&str> = BTreeSet::new();
+        for (field_name, kind) in conjunction.iter() {
+            match kind {
+                FirestoreIndexFilterKind::Equality
+                    if !ordered.iter().any(|field| field.field_path == *field_name) =>
+                {
+                    equality_fields.insert(field_name);
+                }
+                FirestoreIndexFilterKind::ArrayContains => {
+                    array_fields.insert(field_name);
+                }
+                _ => {}
+            }
+        }
+
+        let prefix = equality_fields
+            .into_iter()
+            .map(|field_name| {
+                FirestoreIndexField::ordered(
+                    field_name.to_string(),
+                    FirestoreIndexFieldOrder::Ascending,
+                )
+            })
+            .chain(
+                array_fields
+                    .into_iter()
+                    .map(|field_name| FirestoreIndexField {
+                        field_path: field_name.to_string(),
+                        order: None,
+                        array_config: Some(FirestoreIndexArrayConfig::Contains),
+                        vector_config: None,
+                    }),
+            );
+
+        if let Some(find_nearest) = &self.find_nearest {
+            // Vector indexes are always composite and don't support the ordering
+            return Some(
+                prefix
+                    .chain(std::iter::once(FirestoreIndexField {
+                        field_path: find_nearest.field_name.clone(),
+                        order: None,
+                        array_config: None,
+                        vector_config: Some(FirestoreIndexVectorConfig {
+                            dimension: find_nearest.query_vector.0.len(),
+                            flat: FirestoreIndexVectorFlat::default(),
+                        }),
+                    }))
+                    .collect(),
+            );
+        }
+
+        // Equality filters alone are served by merging the single-field indexes
+        let fields: Vec<FirestoreIndexField> = prefix.collect();
+        if ordered.is_empty() || fields.len() + ordered.len() < 2 {
+            None
+        } else {
+            Some(fields.into_iter().chain(ordered).collect())
+        }
+    }
+}
+
+/// Filter as a disjunction of conjunctions of the filtered fields.
+fn filter_conjunctions(
+    filter: &FirestoreQueryFilter,
+) -> Vec<Vec<(&str, FirestoreIndexFilterKind)>> {
+    match filter {
+        FirestoreQueryFilter::Composite(composite) => {
+            match composite.operator {
+                FirestoreQueryFilterCompositeOperator::Or => composite
+                    .for_all_filters
+                    .iter()
+                    .flat_map(filter_conjunctions)
+                    .collect(),
+                FirestoreQueryFilterCompositeOperator::And => composite
+                    .for_all_filters
+                    .iter()
+                    .fold(vec![vec![]], |conjunctions, filter| {
+                        let filter_conjunctions = filter_conjunctions(filter);
+                        let mut result = Vec::new();
+                        for conjunction in conjunctions.iter() {
+                            for filter_conjunction in filter_conjunctions.iter() {
+                                result.push(
+                                    conjunction
+                                        .iter()
+                                        .chain(filter_conjunction.iter())
+                                        .map(|(field_name, kind)| (*field_name, kind.clone()))
+                                        .collect(),
+                                );
+                            }
+                        }
+                        result
+                    }),
+            }
+        }
+        FirestoreQueryFilter::Unary(unary) => vec![vec![match unary {
+            FirestoreQueryFilterUnary::IsNan(field_name)
+            | FirestoreQueryFilterUnary::IsNull(field_name) => {
+                (field_name.as_str(), FirestoreIndexFilterKind::Equality)
+            }
+            FirestoreQueryFilterUnary::IsNotNan(field_name)
+            | FirestoreQueryFilterUnary::IsNotNull(field_name) => {
+                (field_name.as_str(), FirestoreIndexFilterKind::Inequality)
+            }
+        }]],
+        FirestoreQueryFilter::Compare(Some(compare)) => vec![vec![match compare {
+            FirestoreQueryFilterCompare::Equal(field_name, _)
+            | FirestoreQueryFilterCompare::In(field_name, _) => {
+                (field_name.as_str(), FirestoreIndexFilterKind::Equality)
+            }
+            FirestoreQueryFilterCompare::ArrayContains(field_name, _)
+            | FirestoreQueryFilterCompare::ArrayContainsAny(field_name, _) => {
+                (field_name.as_str(), FirestoreIndexFilterKind::ArrayContains)
+            }
+            FirestoreQueryFilterCompare::LessThan(field_name, _)
+            | FirestoreQueryFilterCompare::LessThanOrEqual(field_name, _)
+            | FirestoreQueryFilterCompare::GreaterThan(field_name, _)
+            | FirestoreQueryFilterCompare::GreaterThanOrEqual(field_name, _)
+            | FirestoreQueryFilterCompare::NotEqual(field_name, _)
+            | FirestoreQueryFilterCompare::NotIn(field_name, _) => {
+                (field_name.as_str(), FirestoreIndexFilterKind::Inequality)
+            }
+        }]],
+        FirestoreQueryFilter::Compare(None) => vec![vec![]],
+    }
+}
+
+/// Collects the queries executed by a `FirestoreDb` instance, e.g. during a test run,
+/// to generate the index definitions for them.
+#[derive(Debug, Clone, Default)]
+pub struct FirestoreQueryRecorder {
+    queries: Arc<Mutex<Vec<FirestoreQueryParams>>>,
+}
+
+impl FirestoreQueryRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(&self, params: &FirestoreQueryParams) {
+        if let Ok(mut queries) = self.queries.lock() {
+            queries.push(params.clone());
+        }
+    }
+
+    pub fn queries(&self) -> Vec<FirestoreQueryParams> {
+        self.queries
+            .lock()
+            .map(|queries| queries.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut queries) = self.queries.lock() {
+            queries.clear();
+        }
+    }
+
+    pub fn index_definitions(&self) -> FirestoreIndexDefinitions {
+        FirestoreIndexDefinitions::from_queries(self.queries().iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FirestoreQueryFilterComposite, FirestoreQueryOrder};
+
+    fn compare(compare: FirestoreQueryFilterCompare) -> FirestoreQueryFilter {
+        FirestoreQueryFilter::Compare(Some(compare))
+    }
+
+    fn composite(
+        operator: FirestoreQueryFilterCompositeOperator,
+        filters: Vec<FirestoreQueryFilter>,
+    ) -> FirestoreQueryFilter {
+        FirestoreQueryFilter::Composite(FirestoreQueryFilterComposite::new(filters, operator))
+    }
+
+    fn ordered(field_path: &str, order: FirestoreIndexFieldOrder) -> FirestoreIndexField {
+        FirestoreIndexField::ordered(field_path.to_string(), order)
+    }
+
+    #[test]
+    fn required_composite_indexes() {
+        let equality_only = FirestoreQueryParams::new("test".into()).with_filter(composite(
+            FirestoreQueryFilterCompositeOperator::And,
+            vec![
+                compare(FirestoreQueryFilterCompare::Equal("a".into(), 1.into())),
+                compare(FirestoreQueryFilterCompare::Equal("b".into(), 1.into())),
+            ],
+        ));
+        assert_eq!(equality_only.required_composite_indexes(), vec![]);
+
+        let query = FirestoreQueryParams::new("test".into())
+            .with_all_descendants(true)
+            .with_filter(composite(
+                FirestoreQueryFilterCompositeOperator::And,
+                vec![
+                    compare(FirestoreQueryFilterCompare::Equal("a".into(), 1.into())),
+                    composite(
+                        FirestoreQueryFilterCompositeOperator::Or,
+                        vec![
+                            compare(FirestoreQueryFilterCompare::ArrayContains(
+                                "tags".into(),
+                                "x".into(),
+                            )),
+                            compare(FirestoreQueryFilterCompare::GreaterThan(
+                                "c".into(),
+                                1.into(),
+                            )),
+                        ],
+                    ),
+                ],
+            ))
+            .with_order_by(vec![FirestoreQueryOrder::new(
+                "b".into(),
+                FirestoreQueryDirection::Descending,
+            )]);
+
+        let definitions = FirestoreIndexDefinitions::from_queries([&query, &query]);
+        assert_eq!(
+            definitions.indexes,
+            vec![
+                FirestoreCompositeIndex {
+                    collection_group: "test".into(),
+                    query_scope: FirestoreIndexQueryScope::CollectionGroup,
+                    fields: vec![
+                        ordered("a", FirestoreIndexFieldOrder::Ascending),
+                        ordered("b", FirestoreIndexFieldOrder::Descending),
+                        ordered("c", FirestoreIndexFieldOrder::Descending),
+                    ],
+                },
+                FirestoreCompositeIndex {
+                    collection_group: "test".into(),
+                    query_scope: FirestoreIndexQueryScope::CollectionGroup,
+                    fields: vec![
+                        ordered("a", FirestoreIndexFieldOrder::Ascending),
+                        FirestoreIndexField {
+                            field_path: "tags".into(),
+                            order: None,
+                            array_config: Some(FirestoreIndexArrayConfig::Contains),
+                            vector_config: None,
+                        },
+                        ordered("b", FirestoreIndexFieldOrder::Descending),
+                    ],
+                },
+            ]
+        );
+
+        #[cfg(feature = "json")]
+        {
+            let json: serde_json::Value =
+                serde_json::from_str(&definitions.to_json_string().unwrap()).unwrap();
+            assert_eq!(
+                json["indexes"][1],
+                serde_json::json!({
+                    "collectionGroup": "test",
+                    "queryScope": "COLLECTION_GROUP",
+                    "fields": [
+                        { "fieldPath": "a", "order": "ASCENDING" },
+                        { "fieldPath": "tags", "arrayConfig": "CONTAINS" },
+                        { "fieldPath": "b", "order": "DESCENDING" }
+                    ]
+                })
+            );
+        }
+    }
+}
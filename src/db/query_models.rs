@@ -1,8 +1,20 @@
 #![allow(clippy::derive_partial_eq_without_eq)] // Since we may not be able to implement Eq for the changes coming from Firestore protos
 
-use crate::FirestoreValue;
+use crate::db::query_ext_api::{FindNearest, StructuredQueryExt};
+use crate::errors::{
+    FirestoreError, FirestoreInvalidParametersError, FirestoreInvalidParametersPublicDetails,
+};
+use crate::firestore_serde::firestore_vector_value;
+use crate::{
+    firestore_doc_get_field_by_path, FirestoreDocument, FirestoreResult, FirestoreValue,
+    FirestoreVector,
+};
 use gcloud_sdk::google::firestore::v1::*;
 use rsb_derive::Builder;
+use std::collections::BTreeSet;
+
+/// Field path of the document name used in ordering and cursors.
+pub const FIRESTORE_DOCUMENT_NAME_FIELD: &str = "__name__";
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum FirestoreQueryCollection {
@@ -37,8 +49,11 @@ pub struct FirestoreQueryParams {
     pub return_only_fields: Option<Vec<String>>,
     pub start_at: Option<FirestoreQueryCursor>,
     pub end_at: Option<FirestoreQueryCursor>,
+    pub find_nearest: Option<FirestoreFindNearestOptions>,
 }
 
+/// The generated `StructuredQuery` has no `find_nearest` field, so the vector search options
+/// aren't converted. Requests that can't carry them reject the queries with `find_nearest` instead.
 impl From<FirestoreQueryParams> for StructuredQuery {
     fn from(params: FirestoreQueryParams) -> Self {
         let query_filter = params.filter.map(|f| f.into());
@@ -82,6 +97,16 @@ impl From<FirestoreQueryParams> for StructuredQuery {
     }
 }
 
+impl From<FirestoreQueryParams> for StructuredQueryExt {
+    fn from(mut params: FirestoreQueryParams) -> Self {
+        let find_nearest = params.find_nearest.take().map(|options| options.into());
+        StructuredQueryExt {
+            query: params.into(),
+            find_nearest,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum FirestoreQueryFilter {
     Composite(FirestoreQueryFilterComposite),
@@ -363,6 +388,131 @@ pub enum FirestoreQueryCursor {
     AfterValue(Vec<FirestoreValue>),
 }
 
+impl FirestoreQueryCursor {
+    /// Cursor starting or ending right after the document (`start_after`/`end_at` in other SDKs).
+    /// The values are read from the ordered fields of the document, so the ordering should already
+    /// contain the implicit server ordering (see `FirestoreQueryParams::order_by_with_implicit_fields`).
+    /// Fails if an ordered field is missing in the document.
+    pub fn after_document(
+        doc: &FirestoreDocument,
+        order_by: &[FirestoreQueryOrder],
+    ) -> FirestoreResult<Self> {
+        Ok(FirestoreQueryCursor::AfterValue(
+            firestore_document_cursor_values(doc, order_by)?,
+        ))
+    }
+
+    /// Cursor starting or ending right before the document (`start_at`/`end_before` in other SDKs).
+    pub fn before_document(
+        doc: &FirestoreDocument,
+        order_by: &[FirestoreQueryOrder],
+    ) -> FirestoreResult<Self> {
+        Ok(FirestoreQueryCursor::BeforeValue(
+            firestore_document_cursor_values(doc, order_by)?,
+        ))
+    }
+}
+
+impl FirestoreQueryParams {
+    /// Ordering the server applies to the query: the explicit ordering, then the fields of the inequality
+    /// filters that aren't ordered explicitly and `__name__`, both in the direction of the last ordered field.
+    pub fn order_by_with_implicit_fields(&self) -> Vec<FirestoreQueryOrder> {
+        firestore_order_by_with_document_name(
+            self.order_by.as_deref().unwrap_or_default(),
+            self.filter.as_ref(),
+        )
+    }
+}
+
+pub(crate) fn firestore_order_by_with_document_name(
+    order_by: &[FirestoreQueryOrder],
+    filter: Option<&FirestoreQueryFilter>,
+) -> Vec<FirestoreQueryOrder> {
+    let mut order_by = order_by.to_vec();
+    if order_by
+        .iter()
+        .any(|order| order.field_name == FIRESTORE_DOCUMENT_NAME_FIELD)
+    {
+        return order_by;
+    }
+
+    let direction = order_by
+        .last()
+        .map(|order| order.direction.clone())
+        .unwrap_or(FirestoreQueryDirection::Ascending);
+
+    let mut inequality_fields = BTreeSet::new();
+    if let Some(filter) = filter {
+        collect_inequality_fields(filter, &mut inequality_fields);
+    }
+    for field_name in inequality_fields {
+        if !order_by.iter().any(|order| order.field_name == field_name) {
+            order_by.push(FirestoreQueryOrder::new(field_name, direction.clone()));
+        }
+    }
+
+    order_by.push(FirestoreQueryOrder::new(
+        FIRESTORE_DOCUMENT_NAME_FIELD.to_string(),
+        direction,
+    ));
+    order_by
+}
+
+fn collect_inequality_fields(filter: &FirestoreQueryFilter, fields: &mut BTreeSet<String>) {
+    match filter {
+        FirestoreQueryFilter::Composite(composite) => {
+            for filter in composite.for_all_filters.iter() {
+                collect_inequality_fields(filter, fields);
+            }
+        }
+        FirestoreQueryFilter::Unary(
+            FirestoreQueryFilterUnary::IsNotNan(field_name)
+            | FirestoreQueryFilterUnary::IsNotNull(field_name),
+        )
+        | FirestoreQueryFilter::Compare(Some(
+            FirestoreQueryFilterCompare::LessThan(field_name, _)
+            | FirestoreQueryFilterCompare::LessThanOrEqual(field_name, _)
+            | FirestoreQueryFilterCompare::GreaterThan(field_name, _)
+            | FirestoreQueryFilterCompare::GreaterThanOrEqual(field_name, _)
+            | FirestoreQueryFilterCompare::NotEqual(field_name, _)
+            | FirestoreQueryFilterCompare::NotIn(field_name, _),
+        )) => {
+            fields.insert(field_name.clone());
+        }
+        FirestoreQueryFilter::Unary(_) | FirestoreQueryFilter::Compare(_) => {}
+    }
+}
+
+fn firestore_document_cursor_values(
+    doc: &FirestoreDocument,
+    order_by: &[FirestoreQueryOrder],
+) -> FirestoreResult<Vec<FirestoreValue>> {
+    order_by
+        .iter()
+        .map(|order| {
+            let value_type = if order.field_name == FIRESTORE_DOCUMENT_NAME_FIELD {
+                value::ValueType::ReferenceValue(doc.name.clone())
+            } else {
+                firestore_doc_get_field_by_path(doc, &order.field_name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        FirestoreError::InvalidParametersError(
+                            FirestoreInvalidParametersError::new(
+                                FirestoreInvalidParametersPublicDetails::new(
+                                    order.field_name.clone(),
+                                    "Ordered field is missing in the document".to_string(),
+                                ),
+                            ),
+                        )
+                    })?
+            };
+            Ok(FirestoreValue::from(Value {
+                value_type: Some(value_type),
+            }))
+        })
+        .collect()
+}
+
 impl From<FirestoreQueryCursor> for gcloud_sdk::google::firestore::v1::Cursor {
     fn from(cursor: FirestoreQueryCursor) -> Self {
         match cursor {
@@ -408,3 +558,138 @@ pub struct FirestorePartition {
     pub start_at: Option<FirestoreQueryCursor>,
     pub end_at: Option<FirestoreQueryCursor>,
 }
+
+#[derive(Debug, PartialEq, Clone, Builder)]
+pub struct FirestoreFindNearestOptions {
+    pub field_name: String,
+    pub query_vector: FirestoreVector,
+    pub distance_measure: FirestoreFindNearestDistanceMeasure,
+    pub neighbors_limit: u32,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FirestoreFindNearestDistanceMeasure {
+    Euclidean,
+    Cosine,
+    DotProduct,
+}
+
+impl From<FirestoreFindNearestDistanceMeasure> for i32 {
+    fn from(measure: FirestoreFindNearestDistanceMeasure) -> Self {
+        match measure {
+            FirestoreFindNearestDistanceMeasure::Euclidean => 1,
+            FirestoreFindNearestDistanceMeasure::Cosine => 2,
+            FirestoreFindNearestDistanceMeasure::DotProduct => 3,
+        }
+    }
+}
+
+impl From<FirestoreFindNearestOptions> for FindNearest {
+    fn from(options: FirestoreFindNearestOptions) -> Self {
+        FindNearest {
+            vector_field: Some(structured_query::FieldReference {
+                field_path: options.field_name,
+            }),
+            query_vector: Some(firestore_vector_value(&options.query_vector.0).value),
+            distance_measure: options.distance_measure.into(),
+            limit: Some(options.neighbors_limit as i32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_by_with_implicit_fields() {
+        let params =
+            FirestoreQueryParams::new(FirestoreQueryCollection::Single("test".into()))
+                .with_order_by(vec![FirestoreQueryOrder::new(
+                    "b".to_string(),
+                    FirestoreQueryDirection::Descending,
+                )])
+                .with_filter(FirestoreQueryFilter::Composite(
+                    FirestoreQueryFilterComposite::new(
+                        vec![
+                            FirestoreQueryFilter::Compare(Some(
+                                FirestoreQueryFilterCompare::GreaterThan("c".to_string(), 1.into()),
+                            )),
+                            FirestoreQueryFilter::Compare(Some(
+                                FirestoreQueryFilterCompare::LessThan("b".to_string(), 1.into()),
+                            )),
+                            FirestoreQueryFilter::Unary(FirestoreQueryFilterUnary::IsNotNull(
+                                "a".to_string(),
+                            )),
+                            FirestoreQueryFilter::Compare(Some(
+                                FirestoreQueryFilterCompare::Equal("d".to_string(), 1.into()),
+                            )),
+                        ],
+                        FirestoreQueryFilterCompositeOperator::And,
+                    ),
+                ));
+
+        let field_names = |order_by: Vec<FirestoreQueryOrder>| {
+            order_by
+                .into_iter()
+                .map(|order| {
+                    assert_eq!(order.direction, FirestoreQueryDirection::Descending);
+                    order.field_name
+                })
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            field_names(params.order_by_with_implicit_fields()),
+            vec!["b", "a", "c", FIRESTORE_DOCUMENT_NAME_FIELD]
+        );
+
+        let explicit_name_order = vec![FirestoreQueryOrder::new(
+            FIRESTORE_DOCUMENT_NAME_FIELD.to_string(),
+            FirestoreQueryDirection::Descending,
+        )];
+        assert_eq!(
+            params
+                .clone()
+                .with_order_by(explicit_name_order.clone())
+                .order_by_with_implicit_fields(),
+            explicit_name_order
+        );
+    }
+
+    #[test]
+    fn document_cursor_requires_ordered_fields() {
+        let doc = FirestoreDocument {
+            name: "projects/p/databases/(default)/documents/test/doc".to_string(),
+            fields: [(
+                "a".to_string(),
+                Value {
+                    value_type: Some(value::ValueType::IntegerValue(1)),
+                },
+            )]
+            .into(),
+            create_time: None,
+            update_time: None,
+        };
+        let order_by = |field_name: &str| {
+            vec![
+                FirestoreQueryOrder::new(
+                    field_name.to_string(),
+                    FirestoreQueryDirection::Ascending,
+                ),
+                FirestoreQueryOrder::new(
+                    FIRESTORE_DOCUMENT_NAME_FIELD.to_string(),
+                    FirestoreQueryDirection::Ascending,
+                ),
+            ]
+        };
+
+        assert!(matches!(
+            FirestoreQueryCursor::after_document(&doc, &order_by("a")),
+            Ok(FirestoreQueryCursor::AfterValue(values)) if values.len() == 2
+        ));
+        assert!(matches!(
+            FirestoreQueryCursor::before_document(&doc, &order_by("b")),
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+    }
+}
@@ -0,0 +1,559 @@
+use crate::errors::{
+    FirestoreError, FirestoreInvalidParametersError, FirestoreInvalidParametersPublicDetails,
+};
+use crate::{
+    FirestoreAggregatedQueryParams, FirestoreAggregation, FirestoreAggregationOperator,
+    FirestoreAggregationOperatorCount, FirestoreQueryCursor, FirestoreQueryParams, FirestoreResult,
+    FirestoreValue, FIRESTORE_DOCUMENT_NAME_FIELD,
+};
+use gcloud_sdk::google::firestore::v1::{
+    structured_query, value, Document, StructuredQuery, Value,
+};
+use prost::Message;
+use rvstruct::ValueStruct;
+use serde::{Deserialize, Serialize};
+
+/// Opaque continuation token pointing after the last document of a page.
+/// It's tied to the query it was produced for and can be stored or passed to clients as is.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, ValueStruct, Serialize, Deserialize)]
+pub struct FirestorePageToken(String);
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FirestorePage<T> {
+    pub items: Vec<T>,
+    /// Token to request the next page, `None` for the last page.
+    pub next_page_token: Option<FirestorePageToken>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FirestorePageTokenContent {
+    #[prost(fixed64, tag = "1")]
+    fingerprint: u64,
+    #[prost(message, repeated, tag = "2")]
+    values: Vec<Value>,
+}
+
+/// Position after the last document of a page, decoded from a page token.
+pub(crate) struct FirestorePageCursor {
+    values: Vec<FirestoreValue>,
+}
+
+const RETURNED_COUNT_ALIAS: &str = "returned";
+
+/// Prepares the queries for the pages and the continuation tokens.
+/// The query is ordered by `__name__` in addition to the user ordering, so the cursors are unique.
+/// The query limit applies to all the pages together. Tokens carry only the cursor, so the number of
+/// documents already returned is counted on the server instead of being read from a token.
+pub(crate) struct FirestoreQueryPaginator {
+    params: FirestoreQueryParams,
+    page_size: u32,
+    fingerprint: u64,
+}
+
+impl FirestoreQueryPaginator {
+    pub fn new(params: FirestoreQueryParams, page_size: u32) -> FirestoreResult<Self> {
+        if page_size == 0 {
+            return Err(invalid_pagination_error(
+                "page_size",
+                "Page size must be greater than zero",
+            ));
+        }
+        if params.find_nearest.is_some() {
+            return Err(invalid_pagination_error(
+                "find_nearest",
+                "Vector search queries can't be paginated",
+            ));
+        }
+
+        let order_by = params.order_by_with_implicit_fields();
+
+        // Cursor values are read from the documents, so ordered fields have to be returned
+        let return_only_fields = params.return_only_fields.clone().map(|mut fields| {
+            for order in order_by.iter() {
+                if order.field_name != FIRESTORE_DOCUMENT_NAME_FIELD
+                    && !fields.contains(&order.field_name)
+                {
+                    fields.push(order.field_name.clone());
+                }
+            }
+            fields
+        });
+
+        let params = FirestoreQueryParams {
+            order_by: Some(order_by),
+            return_only_fields,
+            ..params
+        };
+        let fingerprint = query_fingerprint(&params);
+
+        Ok(Self {
+            params,
+            page_size,
+            fingerprint,
+        })
+    }
+
+    /// Decodes the token and checks it was created for the same query.
+    pub fn page_cursor(
+        &self,
+        page_token: &FirestorePageToken,
+    ) -> FirestoreResult<FirestorePageCursor> {
+        let content = hex::decode(page_token.value())
+            .ok()
+            .and_then(|bytes| FirestorePageTokenContent::decode(bytes.as_slice()).ok())
+            .ok_or_else(|| invalid_pagination_error("page_token", "Malformed page token"))?;
+
+        if content.fingerprint != self.fingerprint {
+            return Err(invalid_pagination_error(
+                "page_token",
+                "Page token was created for a different query",
+            ));
+        }
+
+        Ok(FirestorePageCursor {
+            values: content
+                .values
+                .into_iter()
+                .map(FirestoreValue::from)
+                .collect(),
+        })
+    }
+
+    /// Aggregation counting the documents of the query up to and including the cursor.
+    /// It's only needed for queries with a limit, to find out how much of the limit is left.
+    pub fn returned_count_params(
+        &self,
+        cursor: &FirestorePageCursor,
+    ) -> Option<FirestoreAggregatedQueryParams> {
+        self.params.limit.map(|_| {
+            FirestoreAggregatedQueryParams::new(
+                FirestoreQueryParams {
+                    end_at: Some(FirestoreQueryCursor::AfterValue(cursor.values.clone())),
+                    ..self.params.clone()
+                },
+                vec![
+                    FirestoreAggregation::new(RETURNED_COUNT_ALIAS.to_string()).with_operator(
+                        FirestoreAggregationOperator::Count(
+                            FirestoreAggregationOperatorCount::new(),
+                        ),
+                    ),
+                ],
+            )
+        })
+    }
+
+    /// Reads the result of the `returned_count_params` aggregation.
+    pub fn returned_count_from_docs(&self, docs: &[Document]) -> FirestoreResult<u32> {
+        docs.iter()
+            .find_map(|doc| doc.fields.get(RETURNED_COUNT_ALIAS))
+            .and_then(|value| match value.value_type {
+                Some(value::ValueType::IntegerValue(count)) => u32::try_from(count).ok(),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                invalid_pagination_error("page_token", "Unable to count the returned documents")
+            })
+    }
+
+    /// Query for the page after the cursor, or the first page without it.
+    /// `returned` is the number of documents up to the cursor, as counted by `returned_count_params`.
+    /// One more document than the page size is requested to find out if there is a next page,
+    /// unless the rest of the query limit fits into the page. `None` when nothing is left to the limit.
+    pub fn page_params(
+        &self,
+        cursor: Option<FirestorePageCursor>,
+        returned: u32,
+    ) -> Option<FirestoreQueryParams> {
+        let params = match cursor {
+            Some(cursor) => FirestoreQueryParams {
+                offset: None,
+                start_at: Some(FirestoreQueryCursor::AfterValue(cursor.values)),
+                ..self.params.clone()
+            },
+            None => self.params.clone(),
+        };
+        let remaining = self
+            .params
+            .limit
+            .map(|limit| limit.saturating_sub(returned));
+
+        match remaining {
+            Some(0) => None,
+            Some(remaining) if remaining <= self.page_size => Some(params.with_limit(remaining)),
+            _ => Some(params.with_limit(self.page_size + 1)),
+        }
+    }
+
+    /// Splits the fetched documents into the page and the token for the next one.
+    pub fn page_from_docs(
+        &self,
+        mut docs: Vec<Document>,
+    ) -> FirestoreResult<FirestorePage<Document>> {
+        let next_page_token = if docs.len() > self.page_size as usize {
+            docs.truncate(self.page_size as usize);
+            docs.last().map(|doc| self.encode_token(doc)).transpose()?
+        } else {
+            None
+        };
+
+        Ok(FirestorePage {
+            items: docs,
+            next_page_token,
+        })
+    }
+
+    fn encode_token(&self, last_doc: &Document) -> FirestoreResult<FirestorePageToken> {
+        let values = match FirestoreQueryCursor::after_document(
+            last_doc,
+            self.params.order_by.as_deref().unwrap_or_default(),
+        )? {
+            FirestoreQueryCursor::AfterValue(values)
+            | FirestoreQueryCursor::BeforeValue(values) => {
+                values.into_iter().map(|value| value.value).collect()
+            }
+        };
+
+        let content = FirestorePageTokenContent {
+            fingerprint: self.fingerprint,
+            values,
+        };
+        Ok(FirestorePageToken::new(hex::encode(
+            content.encode_to_vec(),
+        )))
+    }
+}
+
+fn invalid_pagination_error(field: &str, error: &str) -> FirestoreError {
+    FirestoreError::InvalidParametersError(FirestoreInvalidParametersError::new(
+        FirestoreInvalidParametersPublicDetails::new(field.to_string(), error.to_string()),
+    ))
+}
+
+/// Stable FNV-1a hash of the query without the parts that change between pages.
+/// Values are hashed with the map fields sorted, since protobuf encodes maps in the hash map order.
+fn query_fingerprint(params: &FirestoreQueryParams) -> u64 {
+    let mut hasher = FnvHasher::new();
+    hasher.write_str(params.parent.as_deref().unwrap_or_default());
+
+    let mut query: StructuredQuery = FirestoreQueryParams {
+        offset: None,
+        start_at: None,
+        ..params.clone()
+    }
+    .into();
+    let filter = query.r#where.take();
+    let end_at = query.end_at.take();
+    hasher.write(&query.encode_to_vec());
+    match filter {
+        Some(filter) => hash_filter(&mut hasher, &filter),
+        None => hasher.write(&[0]),
+    }
+    match end_at {
+        Some(end_at) => {
+            hasher.write(&[1, end_at.before as u8]);
+            for value in end_at.values.iter() {
+                hash_value(&mut hasher, value);
+            }
+        }
+        None => hasher.write(&[0]),
+    }
+    hasher.finish()
+}
+
+fn hash_filter(hasher: &mut FnvHasher, filter: &structured_query::Filter) {
+    match &filter.filter_type {
+        Some(structured_query::filter::FilterType::CompositeFilter(composite)) => {
+            hasher.write(&[1, composite.op as u8]);
+            for filter in composite.filters.iter() {
+                hash_filter(hasher, filter);
+            }
+            hasher.write(&[0]);
+        }
+        Some(structured_query::filter::FilterType::FieldFilter(field_filter)) => {
+            hasher.write(&[2, field_filter.op as u8]);
+            hasher.write_str(
+                field_filter
+                    .field
+                    .as_ref()
+                    .map(|field| field.field_path.as_str())
+                    .unwrap_or_default(),
+            );
+            if let Some(value) = &field_filter.value {
+                hash_value(hasher, value);
+            }
+        }
+        Some(structured_query::filter::FilterType::UnaryFilter(unary_filter)) => {
+            hasher.write(&[3, unary_filter.op as u8]);
+            hasher.write(&unary_filter.encode_to_vec());
+        }
+        None => hasher.write(&[4]),
+    }
+}
+
+fn hash_value(hasher: &mut FnvHasher, value: &Value) {
+    match &value.value_type {
+        Some(value::ValueType::MapValue(map_value)) => {
+            let mut fields: Vec<(&String, &Value)> = map_value.fields.iter().collect();
+            fields.sort_by_key(|(field_name, _)| *field_name);
+            hasher.write(&[1]);
+            for (field_name, field_value) in fields {
+                hasher.write_str(field_name);
+                hash_value(hasher, field_value);
+            }
+            hasher.write(&[0]);
+        }
+        Some(value::ValueType::ArrayValue(array_value)) => {
+            hasher.write(&[2]);
+            for value in array_value.values.iter() {
+                hash_value(hasher, value);
+            }
+            hasher.write(&[0]);
+        }
+        _ => {
+            hasher.write(&[3]);
+            hasher.write(&value.encode_to_vec());
+        }
+    }
+}
+
+struct FnvHasher(u64);
+
+impl FnvHasher {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FirestoreQueryCollection, FirestoreQueryDirection, FirestoreQueryFilter,
+        FirestoreQueryFilterCompare, FirestoreQueryOrder,
+    };
+
+    fn test_params() -> FirestoreQueryParams {
+        FirestoreQueryParams::new(FirestoreQueryCollection::Single("test".to_string()))
+            .with_order_by(vec![FirestoreQueryOrder::new(
+                "some_num".to_string(),
+                FirestoreQueryDirection::Descending,
+            )])
+            .with_filter(FirestoreQueryFilter::Compare(Some(
+                FirestoreQueryFilterCompare::Equal("some_string".to_string(), "test".into()),
+            )))
+    }
+
+    fn test_docs(count: usize) -> Vec<Document> {
+        (0..count)
+            .map(|idx| Document {
+                name: format!("projects/p/databases/(default)/documents/test/doc-{idx}"),
+                fields: [(
+                    "some_num".to_string(),
+                    Value {
+                        value_type: Some(value::ValueType::IntegerValue(idx as i64)),
+                    },
+                )]
+                .into(),
+                create_time: None,
+                update_time: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paginator_produces_and_validates_tokens() -> FirestoreResult<()> {
+        let paginator = FirestoreQueryPaginator::new(test_params(), 2)?;
+
+        let first_page = paginator.page_params(None, 0).expect("first page");
+        assert_eq!(first_page.limit, Some(3));
+        assert_eq!(
+            first_page.order_by.as_ref().and_then(|order| order.last()),
+            Some(&FirestoreQueryOrder::new(
+                FIRESTORE_DOCUMENT_NAME_FIELD.to_string(),
+                FirestoreQueryDirection::Descending
+            ))
+        );
+
+        let page = paginator.page_from_docs(test_docs(3))?;
+        assert_eq!(page.items.len(), 2);
+        let token = page.next_page_token.expect("next page token");
+
+        let cursor = paginator.page_cursor(&token)?;
+        assert!(paginator.returned_count_params(&cursor).is_none());
+        let next_page = paginator.page_params(Some(cursor), 0).expect("next page");
+        assert_eq!(
+            next_page.start_at,
+            Some(FirestoreQueryCursor::AfterValue(vec![
+                1.into(),
+                FirestoreValue::from(Value {
+                    value_type: Some(value::ValueType::ReferenceValue(
+                        "projects/p/databases/(default)/documents/test/doc-1".to_string()
+                    ))
+                })
+            ]))
+        );
+
+        let same_query = FirestoreQueryPaginator::new(test_params(), 2)?;
+        assert!(same_query.page_cursor(&token).is_ok());
+
+        for other_query in [
+            FirestoreQueryPaginator::new(test_params().with_order_by(vec![]), 2)?,
+            FirestoreQueryPaginator::new(test_params().with_limit(100), 2)?,
+        ] {
+            assert!(matches!(
+                other_query.page_cursor(&token),
+                Err(FirestoreError::InvalidParametersError(_))
+            ));
+        }
+        assert!(matches!(
+            paginator.page_cursor(&FirestorePageToken::new("garbage".to_string())),
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+
+        let last_page = paginator.page_from_docs(test_docs(2))?;
+        assert_eq!(last_page.next_page_token, None);
+
+        let mut missing_field_docs = test_docs(3);
+        missing_field_docs[1].fields.clear();
+        assert!(matches!(
+            paginator.page_from_docs(missing_field_docs),
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn paginator_keeps_query_limit() -> FirestoreResult<()> {
+        let paginator =
+            FirestoreQueryPaginator::new(test_params().with_limit(5).with_offset(1), 2)?;
+
+        let mut page_token = None;
+        let mut page_limits = Vec::new();
+        let mut items = 0;
+        loop {
+            let cursor = page_token
+                .as_ref()
+                .map(|page_token| paginator.page_cursor(page_token))
+                .transpose()?;
+            let returned = match &cursor {
+                Some(cursor) => {
+                    let count_params = paginator
+                        .returned_count_params(cursor)
+                        .expect("count params");
+                    assert_eq!(count_params.query_params.limit, Some(5));
+                    assert_eq!(count_params.query_params.offset, Some(1));
+                    assert!(matches!(
+                        count_params.query_params.end_at,
+                        Some(FirestoreQueryCursor::AfterValue(_))
+                    ));
+                    items as u32
+                }
+                None => 0,
+            };
+            let page_query = paginator.page_params(cursor, returned).expect("page query");
+            let limit = page_query.limit.unwrap_or_default();
+            page_limits.push(limit);
+
+            let page = paginator.page_from_docs(test_docs(limit as usize))?;
+            items += page.items.len();
+            page_token = page.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(page_limits, vec![3, 3, 1]);
+        assert_eq!(items, 5);
+
+        let exact_pages = FirestoreQueryPaginator::new(test_params().with_limit(2), 2)?;
+        let page_query = exact_pages.page_params(None, 0).expect("page query");
+        assert_eq!(page_query.limit, Some(2));
+        assert_eq!(
+            exact_pages.page_from_docs(test_docs(2))?.next_page_token,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn paginator_counts_returned_documents_on_server() -> FirestoreResult<()> {
+        let paginator = FirestoreQueryPaginator::new(test_params().with_limit(5), 2)?;
+        let token = paginator
+            .page_from_docs(test_docs(3))?
+            .next_page_token
+            .expect("next page token");
+        let cursor = paginator.page_cursor(&token)?;
+
+        let count_docs = vec![Document {
+            name: "".to_string(),
+            fields: [(
+                RETURNED_COUNT_ALIAS.to_string(),
+                Value {
+                    value_type: Some(value::ValueType::IntegerValue(5)),
+                },
+            )]
+            .into(),
+            create_time: None,
+            update_time: None,
+        }];
+        let returned = paginator.returned_count_from_docs(&count_docs)?;
+        assert_eq!(returned, 5);
+        assert!(paginator.page_params(Some(cursor), returned).is_none());
+
+        assert!(matches!(
+            paginator.returned_count_from_docs(&[]),
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn query_fingerprint_ignores_map_order() {
+        let map_value = |fields: Vec<(String, i64)>| -> FirestoreValue {
+            FirestoreValue::from(Value {
+                value_type: Some(value::ValueType::MapValue(
+                    gcloud_sdk::google::firestore::v1::MapValue {
+                        fields: fields
+                            .into_iter()
+                            .map(|(name, num)| {
+                                (
+                                    name,
+                                    Value {
+                                        value_type: Some(value::ValueType::IntegerValue(num)),
+                                    },
+                                )
+                            })
+                            .collect(),
+                    },
+                )),
+            })
+        };
+        let fields: Vec<(String, i64)> = (0..16).map(|idx| (format!("field_{idx}"), idx)).collect();
+        let mut reversed_fields = fields.clone();
+        reversed_fields.reverse();
+
+        let params_with_end = |value: FirestoreValue| {
+            test_params().with_end_at(FirestoreQueryCursor::BeforeValue(vec![value]))
+        };
+        assert_eq!(
+            query_fingerprint(&params_with_end(map_value(fields))),
+            query_fingerprint(&params_with_end(map_value(reversed_fields)))
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use crate::errors::{
+    FirestoreError, FirestoreInvalidParametersError, FirestoreInvalidParametersPublicDetails,
+};
+use crate::{
+    FirestoreQueryCursor, FirestoreQueryFilter, FirestoreQueryFilterCompare,
+    FirestoreQueryFilterCompositeOperator, FirestoreQueryFilterUnary, FirestoreQueryParams,
+    FirestoreResult, FirestoreValue,
+};
+use gcloud_sdk::google::firestore::v1::value;
+use std::collections::BTreeSet;
+
+// Limits from https://firebase.google.com/docs/firestore/query-data/queries#limits_on_or_queries
+const MAX_DISJUNCTIONS: usize = 30;
+const MAX_IN_VALUES: usize = 30;
+const MAX_NOT_IN_VALUES: usize = 10;
+const MAX_INEQUALITY_FIELDS: usize = 10;
+const MAX_FIND_NEAREST_LIMIT: u32 = 1000;
+
+#[derive(Default)]
+struct FirestoreQueryFilterStats {
+    inequality_fields: BTreeSet<String>,
+    not_equal_filters: usize,
+    not_in_filters: usize,
+    in_filters: usize,
+    or_filters: usize,
+}
+
+impl FirestoreQueryParams {
+    /// Checks the documented Firestore query restrictions on the client side,
+    /// so invalid queries fail before they are sent to the server.
+    pub fn validate(&self) -> FirestoreResult<()> {
+        let mut stats = FirestoreQueryFilterStats::default();
+        if let Some(filter) = &self.filter {
+            collect_filter_stats(filter, &mut stats)?;
+
+            let disjunctions = count_disjunctions(filter);
+            if disjunctions > MAX_DISJUNCTIONS {
+                return Err(invalid_query_error(
+                    "filter",
+                    format!(
+                        "The filter has {disjunctions} disjunctions in normal form, the maximum is {MAX_DISJUNCTIONS}"
+                    ),
+                ));
+            }
+        }
+
+        if stats.not_in_filters > 1 {
+            return Err(invalid_query_error(
+                "filter",
+                "Only one not-in filter is allowed in a query",
+            ));
+        }
+        if stats.not_in_filters > 0 && stats.not_equal_filters > 0 {
+            return Err(invalid_query_error(
+                "filter",
+                "not-in filters can't be combined with not-equal filters",
+            ));
+        }
+        if stats.not_in_filters > 0 && (stats.in_filters > 0 || stats.or_filters > 0) {
+            return Err(invalid_query_error(
+                "filter",
+                "not-in filters can't be combined with in, array-contains-any or OR filters",
+            ));
+        }
+
+        if stats.inequality_fields.len() > MAX_INEQUALITY_FIELDS {
+            return Err(invalid_query_error(
+                "filter",
+                format!("Inequality filters are allowed on at most {MAX_INEQUALITY_FIELDS} fields"),
+            ));
+        }
+
+        // The server orders by the inequality fields and `__name__` implicitly
+        let ordered_fields = self.order_by_with_implicit_fields().len();
+        for (cursor_field, cursor) in [("start_at", &self.start_at), ("end_at", &self.end_at)] {
+            if let Some(
+                FirestoreQueryCursor::BeforeValue(values)
+                | FirestoreQueryCursor::AfterValue(values),
+            ) = cursor
+            {
+                if values.len() > ordered_fields {
+                    return Err(invalid_query_error(
+                        cursor_field,
+                        format!(
+                            "The cursor has {} values, but the query is ordered by {} fields",
+                            values.len(),
+                            ordered_fields
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(find_nearest) = &self.find_nearest {
+            if find_nearest.neighbors_limit == 0
+                || find_nearest.neighbors_limit > MAX_FIND_NEAREST_LIMIT
+            {
+                return Err(invalid_query_error(
+                    "find_nearest",
+                    format!("The neighbors limit must be between 1 and {MAX_FIND_NEAREST_LIMIT}"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn collect_filter_stats(
+    filter: &FirestoreQueryFilter,
+    stats: &mut FirestoreQueryFilterStats,
+) -> FirestoreResult<()> {
+    match filter {
+        FirestoreQueryFilter::Composite(composite) => {
+            if composite.operator == FirestoreQueryFilterCompositeOperator::Or {
+                stats.or_filters += 1;
+            }
+            for filter in composite.for_all_filters.iter() {
+                collect_filter_stats(filter, stats)?;
+            }
+        }
+        FirestoreQueryFilter::Unary(unary) => match unary {
+            FirestoreQueryFilterUnary::IsNotNan(field_name)
+            | FirestoreQueryFilterUnary::IsNotNull(field_name) => {
+                stats.not_equal_filters += 1;
+                stats.inequality_fields.insert(field_name.clone());
+            }
+            FirestoreQueryFilterUnary::IsNan(_) | FirestoreQueryFilterUnary::IsNull(_) => {}
+        },
+        FirestoreQueryFilter::Compare(Some(compare)) => match compare {
+            FirestoreQueryFilterCompare::LessThan(field_name, _)
+            | FirestoreQueryFilterCompare::LessThanOrEqual(field_name, _)
+            | FirestoreQueryFilterCompare::GreaterThan(field_name, _)
+            | FirestoreQueryFilterCompare::GreaterThanOrEqual(field_name, _) => {
+                stats.inequality_fields.insert(field_name.clone());
+            }
+            FirestoreQueryFilterCompare::NotEqual(field_name, _) => {
+                stats.not_equal_filters += 1;
+                stats.inequality_fields.insert(field_name.clone());
+            }
+            FirestoreQueryFilterCompare::NotIn(field_name, value) => {
+                validate_array_filter_value(field_name, value, "not-in", MAX_NOT_IN_VALUES)?;
+                stats.not_in_filters += 1;
+                stats.inequality_fields.insert(field_name.clone());
+            }
+            FirestoreQueryFilterCompare::In(field_name, value) => {
+                validate_array_filter_value(field_name, value, "in", MAX_IN_VALUES)?;
+                stats.in_filters += 1;
+            }
+            FirestoreQueryFilterCompare::ArrayContainsAny(field_name, value) => {
+                validate_array_filter_value(
+                    field_name,
+                    value,
+                    "array-contains-any",
+                    MAX_IN_VALUES,
+                )?;
+                stats.in_filters += 1;
+            }
+            FirestoreQueryFilterCompare::Equal(_, _)
+            | FirestoreQueryFilterCompare::ArrayContains(_, _) => {}
+        },
+        FirestoreQueryFilter::Compare(None) => {}
+    }
+    Ok(())
+}
+
+fn array_filter_values_len(value: &FirestoreValue) -> Option<usize> {
+    match &value.value.value_type {
+        Some(value::ValueType::ArrayValue(array_value)) => Some(array_value.values.len()),
+        _ => None,
+    }
+}
+
+fn validate_array_filter_value(
+    field_name: &str,
+    value: &FirestoreValue,
+    filter_name: &str,
+    max_values: usize,
+) -> FirestoreResult<()> {
+    match array_filter_values_len(value) {
+        Some(len) if len > 0 && len <= max_values => Ok(()),
+        Some(len) => Err(invalid_query_error(
+            field_name,
+            format!(
+                "The {filter_name} filter has {len} values, it must have from 1 to {max_values}"
+            ),
+        )),
+        None => Err(invalid_query_error(
+            field_name,
+            format!("The {filter_name} filter requires an array value"),
+        )),
+    }
+}
+
+/// Number of the disjunctions of the filter in the disjunctive normal form.
+fn count_disjunctions(filter: &FirestoreQueryFilter) -> usize {
+    match filter {
+        FirestoreQueryFilter::Composite(composite) => match composite.operator {
+            FirestoreQueryFilterCompositeOperator::Or => composite
+                .for_all_filters
+                .iter()
+                .map(count_disjunctions)
+                .sum::<usize>()
+                .max(1),
+            FirestoreQueryFilterCompositeOperator::And => composite
+                .for_all_filters
+                .iter()
+                .map(count_disjunctions)
+                .fold(1, |acc, count| acc.saturating_mul(count)),
+        },
+        FirestoreQueryFilter::Compare(Some(
+            FirestoreQueryFilterCompare::In(_, value)
+            | FirestoreQueryFilterCompare::ArrayContainsAny(_, value),
+        )) => array_filter_values_len(value).unwrap_or(1).max(1),
+        _ => 1,
+    }
+}
+
+impl FirestoreQueryParams {
+    // Only the queries and explains use the extended messages with the find_nearest field,
+    // the other requests would silently run without the vector search.
+    pub(crate) fn ensure_without_find_nearest(&self, operation: &str) -> FirestoreResult<()> {
+        if self.find_nearest.is_some() {
+            Err(invalid_query_error(
+                "find_nearest",
+                format!("Vector search isn't supported for {operation}"),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn invalid_query_error<S: AsRef<str>>(field: &str, error: S) -> FirestoreError {
+    FirestoreError::InvalidParametersError(FirestoreInvalidParametersError::new(
+        FirestoreInvalidParametersPublicDetails::new(field.to_string(), error.as_ref().to_string()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FirestoreQueryCollection, FirestoreQueryDirection, FirestoreQueryFilterComposite,
+        FirestoreQueryOrder,
+    };
+
+    fn query_with_filters(filters: Vec<FirestoreQueryFilterCompare>) -> FirestoreQueryParams {
+        FirestoreQueryParams::new(FirestoreQueryCollection::Single("test".to_string())).with_filter(
+            FirestoreQueryFilter::Composite(FirestoreQueryFilterComposite::new(
+                filters
+                    .into_iter()
+                    .map(|filter| FirestoreQueryFilter::Compare(Some(filter)))
+                    .collect(),
+                FirestoreQueryFilterCompositeOperator::And,
+            )),
+        )
+    }
+
+    fn assert_invalid(params: &FirestoreQueryParams) {
+        assert!(
+            matches!(
+                params.validate(),
+                Err(FirestoreError::InvalidParametersError(_))
+            ),
+            "{params:?} should be invalid"
+        );
+    }
+
+    #[test]
+    fn validate_query_params() -> FirestoreResult<()> {
+        let ascending = |field_name: &str| {
+            FirestoreQueryOrder::new(field_name.to_string(), FirestoreQueryDirection::Ascending)
+        };
+
+        let inequalities = query_with_filters(vec![
+            FirestoreQueryFilterCompare::GreaterThan("a".to_string(), 1.into()),
+            FirestoreQueryFilterCompare::LessThan("b".to_string(), 1.into()),
+        ]);
+        inequalities.validate()?;
+        inequalities
+            .clone()
+            .with_order_by(vec![ascending("b"), ascending("a")])
+            .validate()?;
+        inequalities
+            .clone()
+            .with_start_at(FirestoreQueryCursor::AfterValue(vec![
+                1.into(),
+                2.into(),
+                "doc".into(),
+            ]))
+            .validate()?;
+        assert_invalid(
+            &inequalities.with_start_at(FirestoreQueryCursor::AfterValue(vec![
+                1.into(),
+                2.into(),
+                "doc".into(),
+                3.into(),
+            ])),
+        );
+
+        let values: Vec<i32> = (0..31).collect();
+        assert_invalid(&query_with_filters(vec![FirestoreQueryFilterCompare::In(
+            "a".to_string(),
+            values.into(),
+        )]));
+        assert_invalid(&query_with_filters(vec![FirestoreQueryFilterCompare::In(
+            "a".to_string(),
+            Vec::<i32>::new().into(),
+        )]));
+        query_with_filters(vec![
+            FirestoreQueryFilterCompare::In("a".to_string(), vec![1, 2, 3].into()),
+            FirestoreQueryFilterCompare::ArrayContainsAny("b".to_string(), vec![1, 2].into()),
+        ])
+        .validate()?;
+        assert_invalid(&query_with_filters(vec![
+            FirestoreQueryFilterCompare::In("a".to_string(), (0..6).collect::<Vec<i32>>().into()),
+            FirestoreQueryFilterCompare::ArrayContainsAny(
+                "b".to_string(),
+                (0..6).collect::<Vec<i32>>().into(),
+            ),
+        ]));
+
+        assert_invalid(&query_with_filters(vec![
+            FirestoreQueryFilterCompare::NotIn("a".to_string(), vec![1].into()),
+            FirestoreQueryFilterCompare::NotEqual("b".to_string(), 1.into()),
+        ]));
+
+        let ordered = query_with_filters(vec![]).with_order_by(vec![ascending("a")]);
+        ordered
+            .clone()
+            .with_start_at(FirestoreQueryCursor::AfterValue(vec![
+                1.into(),
+                "doc".into(),
+            ]))
+            .validate()?;
+        assert_invalid(
+            &ordered.with_start_at(FirestoreQueryCursor::AfterValue(vec![
+                1.into(),
+                "doc".into(),
+                2.into(),
+            ])),
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use crate::db::{safe_document_path, DeleteOperation};
+use crate::{
+    FirestoreBulkWriteFailure, FirestoreBulkWriterOptions, FirestoreDb, FirestoreDeleteSupport,
+    FirestoreListCollectionIdsParams, FirestoreListDocParams, FirestoreListingSupport,
+    FirestoreResult, FirestoreWritePrecondition,
+};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use gcloud_sdk::google::firestore::v1::{write, Write};
+use rsb_derive::*;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tracing::*;
+
+pub type FirestoreRecursiveDeleteProgressFn =
+    Arc<dyn Fn(&FirestoreRecursiveDeleteProgress) + Send + Sync>;
+
+#[derive(Clone, Builder)]
+pub struct FirestoreRecursiveDeleteOptions {
+    #[default = "10"]
+    pub max_in_flight_batches: usize,
+
+    #[default = "100"]
+    pub page_size: usize,
+
+    /// Called after every scanned collection.
+    pub on_progress: Option<FirestoreRecursiveDeleteProgressFn>,
+}
+
+impl Default for FirestoreRecursiveDeleteOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for FirestoreRecursiveDeleteOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FirestoreRecursiveDeleteOptions")
+            .field("max_in_flight_batches", &self.max_in_flight_batches)
+            .field("page_size", &self.page_size)
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct FirestoreRecursiveDeleteProgress {
+    pub scanned_collections: usize,
+    pub queued_documents: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FirestoreRecursiveDeleteResult {
+    pub deleted_documents: usize,
+    pub failures: Vec<FirestoreBulkWriteFailure>,
+}
+
+/// Deleting documents together with all their subcollections.
+#[async_trait]
+pub trait FirestoreRecursiveDeleteSupport {
+    async fn delete_recursive_by_id<S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>
+    where
+        S: AsRef<str> + Send;
+
+    async fn delete_recursive_by_id_at<S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>
+    where
+        S: AsRef<str> + Send;
+
+    async fn delete_collection(
+        &self,
+        collection_id: &str,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>;
+
+    async fn delete_collection_at(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>;
+}
+
+#[async_trait]
+impl FirestoreRecursiveDeleteSupport for FirestoreDb {
+    async fn delete_recursive_by_id<S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>
+    where
+        S: AsRef<str> + Send,
+    {
+        self.delete_recursive_by_id_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            precondition,
+            options,
+        )
+        .await
+    }
+
+    async fn delete_recursive_by_id_at<S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>
+    where
+        S: AsRef<str> + Send,
+    {
+        let document_path = safe_document_path(parent, collection_id, document_id.as_ref())?;
+
+        if precondition.is_some() {
+            // Nothing under the document is deleted unless its precondition holds
+            self.delete_by_id_at(parent, collection_id, document_id.as_ref(), precondition)
+                .await?;
+            let mut result = self
+                .delete_recursive(Some((document_path, None)), vec![], options)
+                .await?;
+            result.deleted_documents += 1;
+            Ok(result)
+        } else {
+            let delete_write: Write = DeleteOperation {
+                parent: parent.to_string(),
+                collection_id: collection_id.to_string(),
+                document_id,
+                precondition,
+            }
+            .try_into()?;
+
+            self.delete_recursive(Some((document_path, Some(delete_write))), vec![], options)
+                .await
+        }
+    }
+
+    async fn delete_collection(
+        &self,
+        collection_id: &str,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult> {
+        self.delete_collection_at(self.get_documents_path().as_str(), collection_id, options)
+            .await
+    }
+
+    async fn delete_collection_at(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult> {
+        self.delete_recursive(
+            None,
+            vec![(parent.to_string(), collection_id.to_string())],
+            options,
+        )
+        .await
+    }
+}
+
+impl FirestoreDb {
+    /// Deletes the documents and all their subcollections walking the tree breadth-first.
+    /// Documents that don't exist but have subcollections are deleted as well.
+    /// The root document is deleted with the optional write queued in the bulk writer together with the rest.
+    /// When the root document has a precondition, the caller deletes it before and passes no write,
+    /// so nothing under the document is deleted unless the precondition holds.
+    pub(crate) async fn delete_recursive(
+        &self,
+        root_document: Option<(String, Option<Write>)>,
+        root_collections: Vec<(String, String)>,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult> {
+        let span = span!(Level::DEBUG, "Firestore Recursive Delete");
+
+        let bulk_writer = self
+            .create_bulk_writer_with_options(
+                FirestoreBulkWriterOptions::new()
+                    .with_max_in_flight_batches(options.max_in_flight_batches),
+            )
+            .await?;
+
+        let mut progress = FirestoreRecursiveDeleteProgress::default();
+        let mut documents: VecDeque<String> = VecDeque::new();
+        let mut collections: VecDeque<(String, String)> = root_collections.into();
+
+        if let Some((document_path, delete_write)) = root_document {
+            if let Some(delete_write) = delete_write {
+                bulk_writer.add_write(delete_write).await?;
+                progress.queued_documents += 1;
+            }
+            documents.push_back(document_path);
+        }
+
+        loop {
+            if let Some((parent, collection_id)) = collections.pop_front() {
+                let mut docs = self
+                    .stream_list_doc_with_errors(
+                        FirestoreListDocParams::new(collection_id)
+                            .with_parent(parent)
+                            .with_page_size(options.page_size)
+                            .with_return_only_fields(vec![])
+                            .with_show_missing(true),
+                    )
+                    .await?;
+
+                while let Some(doc) = docs.try_next().await? {
+                    bulk_writer
+                        .add_write(Write {
+                            operation: Some(write::Operation::Delete(doc.name.clone())),
+                            ..Default::default()
+                        })
+                        .await?;
+                    progress.queued_documents += 1;
+                    documents.push_back(doc.name);
+                }
+
+                progress.scanned_collections += 1;
+                if let Some(on_progress) = &options.on_progress {
+                    on_progress(&progress);
+                }
+            } else if let Some(document_path) = documents.pop_front() {
+                let mut collection_ids = self
+                    .stream_list_collection_ids_with_errors(
+                        FirestoreListCollectionIdsParams::new()
+                            .with_parent(document_path.clone())
+                            .with_page_size(options.page_size),
+                    )
+                    .await?;
+                while let Some(collection_id) = collection_ids.try_next().await? {
+                    collections.push_back((document_path.clone(), collection_id));
+                }
+            } else {
+                break;
+            }
+        }
+
+        let summary = bulk_writer.finish().await?;
+
+        span.in_scope(|| {
+            debug!(
+                "Recursively deleted {} documents in {} collections, {} failed",
+                summary.succeeded,
+                progress.scanned_collections,
+                summary.failures.len()
+            );
+        });
+
+        Ok(FirestoreRecursiveDeleteResult {
+            deleted_documents: summary.succeeded,
+            failures: summary.failures,
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use crate::db::with_request_retry_policy;
+use crate::{FirestoreResult, FirestoreRetryPolicy};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+tokio::task_local! {
+    static FIRESTORE_REQUEST_DEADLINE: Instant;
+}
+
+// The remaining time before the deadline, so each retry attempt gets only what is left.
+pub(crate) fn current_request_timeout() -> Option<Duration> {
+    FIRESTORE_REQUEST_DEADLINE
+        .try_with(|deadline| deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
+// Requests created inside the future carry the remaining time as a gRPC deadline,
+// and the future itself, including all the retries, is cancelled when it expires.
+pub(crate) async fn with_request_timeout<T, F>(
+    timeout: Option<Duration>,
+    future: F,
+) -> FirestoreResult<T>
+where
+    F: Future<Output = FirestoreResult<T>>,
+{
+    match timeout {
+        Some(timeout) => {
+            FIRESTORE_REQUEST_DEADLINE
+                .scope(
+                    Instant::now() + timeout,
+                    tokio::time::timeout(timeout, future),
+                )
+                .await?
+        }
+        None => future.await,
+    }
+}
+
+// Request options of the fluent API builders. The retry policy and the timeout are applied
+// to the requests created inside the future, so for the stream_* methods
+// they only cover creating the stream.
+pub(crate) async fn with_request_options<T, F>(
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+    future: F,
+) -> FirestoreResult<T>
+where
+    F: Future<Output = FirestoreResult<T>>,
+{
+    with_request_retry_policy(retry_policy, with_request_timeout(timeout, future)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::FirestoreError;
+
+    #[tokio::test]
+    async fn request_timeout_test() {
+        let result = with_request_timeout(Some(Duration::from_millis(10)), async {
+            assert!(current_request_timeout() <= Some(Duration::from_millis(10)));
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(FirestoreError::DeadlineExceededError(_))
+        ));
+
+        let result = with_request_timeout(None, async { Ok(current_request_timeout()) }).await;
+        assert_eq!(result.ok(), Some(None));
+    }
+
+    #[tokio::test]
+    async fn request_timeout_remaining_budget_test() {
+        let result = with_request_timeout(Some(Duration::from_secs(10)), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(current_request_timeout())
+        })
+        .await;
+        let remaining = result.ok().flatten().unwrap_or_default();
+        assert!(remaining > Duration::from_secs(5));
+        assert!(remaining <= Duration::from_millis(9950));
+    }
+}
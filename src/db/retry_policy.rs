@@ -0,0 +1,273 @@
+use crate::errors::*;
+use crate::FirestoreResult;
+use backoff::backoff::Backoff;
+use backoff::{ExponentialBackoff, ExponentialBackoffBuilder};
+use rsb_derive::*;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::*;
+
+/// Retry policy applied to the Firestore calls: exponential backoff with jitter,
+/// limited by the number of retries and optionally by the total elapsed time.
+#[derive(Debug, Clone, PartialEq, Builder)]
+pub struct FirestoreRetryPolicy {
+    #[default = "3"]
+    pub max_retries: usize,
+
+    #[default = "Duration::from_millis(backoff::default::INITIAL_INTERVAL_MILLIS)"]
+    pub initial_interval: Duration,
+
+    #[default = "Duration::from_millis(backoff::default::MAX_INTERVAL_MILLIS)"]
+    pub max_interval: Duration,
+
+    #[default = "backoff::default::MULTIPLIER"]
+    pub multiplier: f64,
+
+    #[default = "backoff::default::RANDOMIZATION_FACTOR"]
+    pub randomization_factor: f64,
+
+    pub max_elapsed_time: Option<Duration>,
+
+    #[default = "FirestoreRetryClassifier::default()"]
+    pub classifier: FirestoreRetryClassifier,
+}
+
+impl Default for FirestoreRetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FirestoreRetryPolicy {
+    pub fn no_retries() -> Self {
+        Self::new().with_max_retries(0)
+    }
+
+    pub(crate) fn new_state(&self) -> FirestoreRetryState {
+        FirestoreRetryState::new(self.clone())
+    }
+
+    fn new_backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoffBuilder::new()
+            .with_initial_interval(self.initial_interval)
+            .with_max_interval(self.max_interval)
+            .with_multiplier(self.multiplier)
+            .with_randomization_factor(self.randomization_factor)
+            .with_max_elapsed_time(self.max_elapsed_time)
+            .build()
+    }
+}
+
+tokio::task_local! {
+    static FIRESTORE_REQUEST_RETRY_POLICY: FirestoreRetryPolicy;
+}
+
+pub(crate) fn current_request_retry_policy() -> Option<FirestoreRetryPolicy> {
+    FIRESTORE_REQUEST_RETRY_POLICY
+        .try_with(|retry_policy| retry_policy.clone())
+        .ok()
+}
+
+// The requests inside the future use the retry policy instead of the database one.
+pub(crate) async fn with_request_retry_policy<F>(
+    retry_policy: Option<FirestoreRetryPolicy>,
+    future: F,
+) -> F::Output
+where
+    F: Future,
+{
+    match retry_policy {
+        Some(retry_policy) => {
+            FIRESTORE_REQUEST_RETRY_POLICY
+                .scope(retry_policy, future)
+                .await
+        }
+        None => future.await,
+    }
+}
+
+pub type FirestoreRetryClassifierFn = Arc<dyn Fn(&FirestoreError) -> bool + Send + Sync>;
+
+/// Decides which errors are worth retrying.
+/// By default only database errors marked as `retry_possible` are retried.
+#[derive(Clone, Default)]
+pub struct FirestoreRetryClassifier {
+    custom: Option<FirestoreRetryClassifierFn>,
+}
+
+impl FirestoreRetryClassifier {
+    pub fn new<F>(classifier: F) -> Self
+    where
+        F: Fn(&FirestoreError) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            custom: Some(Arc::new(classifier)),
+        }
+    }
+
+    pub fn is_retryable(&self, err: &FirestoreError) -> bool {
+        match &self.custom {
+            Some(classifier) => classifier(err),
+            None => {
+                matches!(err, FirestoreError::DatabaseError(ref db_err) if db_err.retry_possible)
+            }
+        }
+    }
+}
+
+impl PartialEq for FirestoreRetryClassifier {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.custom, &other.custom) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for FirestoreRetryClassifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FirestoreRetryClassifier")
+            .field("custom", &self.custom.is_some())
+            .finish()
+    }
+}
+
+pub(crate) struct FirestoreRetryState {
+    policy: FirestoreRetryPolicy,
+    backoff: ExponentialBackoff,
+    retries: usize,
+}
+
+impl FirestoreRetryState {
+    fn new(policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            backoff: policy.new_backoff(),
+            policy,
+            retries: 0,
+        }
+    }
+
+    pub fn next_retry_delay(&mut self, err: &FirestoreError) -> Option<Duration> {
+        if self.retries < self.policy.max_retries && self.policy.classifier.is_retryable(err) {
+            self.backoff.next_backoff().map(|delay| {
+                self.retries += 1;
+                delay
+            })
+        } else {
+            None
+        }
+    }
+
+    pub async fn wait_retry(&mut self, err: &FirestoreError, span: &Span) -> bool {
+        match self.next_retry_delay(err) {
+            Some(delay) => {
+                span.in_scope(|| {
+                    warn!(
+                        "Failed with {}. Retrying in {:?}: {}/{}",
+                        err, delay, self.retries, self.policy.max_retries
+                    );
+                });
+                tokio::time::sleep(delay).await;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub(crate) async fn retry_with_policy<T, FN, F>(
+    policy: &FirestoreRetryPolicy,
+    span: &Span,
+    mut op: FN,
+) -> FirestoreResult<T>
+where
+    FN: FnMut() -> F,
+    F: Future<Output = FirestoreResult<T>>,
+{
+    let mut retry_state = policy.new_state();
+    loop {
+        match op().await {
+            Ok(result) => return Ok(result),
+            Err(err) => {
+                if !retry_state.wait_retry(&err, span).await {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retryable_error() -> FirestoreError {
+        gcloud_sdk::tonic::Status::unavailable("test").into()
+    }
+
+    #[tokio::test]
+    async fn retry_with_policy_test() {
+        let policy = FirestoreRetryPolicy::new()
+            .with_max_retries(2)
+            .with_initial_interval(Duration::from_millis(1));
+
+        let mut attempts = 0;
+        let result: FirestoreResult<()> = retry_with_policy(&policy, &Span::none(), || {
+            attempts += 1;
+            async { Err(retryable_error()) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result: FirestoreResult<()> = retry_with_policy(&policy, &Span::none(), || {
+            attempts += 1;
+            async { Err(gcloud_sdk::tonic::Status::not_found("test").into()) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn retry_with_custom_classifier_test() {
+        let policy = FirestoreRetryPolicy::new()
+            .with_initial_interval(Duration::from_millis(1))
+            .with_classifier(FirestoreRetryClassifier::new(|err| {
+                matches!(err, FirestoreError::DataNotFoundError(_))
+            }));
+
+        let mut attempts = 0;
+        let result = retry_with_policy(&policy, &Span::none(), || {
+            attempts += 1;
+            let current_attempt = attempts;
+            async move {
+                if current_attempt < 3 {
+                    Err(gcloud_sdk::tonic::Status::not_found("test").into())
+                } else {
+                    Ok(current_attempt)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.ok(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn request_retry_policy_test() {
+        let policy = FirestoreRetryPolicy::no_retries();
+
+        assert_eq!(current_request_retry_policy(), None);
+        with_request_retry_policy(Some(policy.clone()), async {
+            assert_eq!(current_request_retry_policy(), Some(policy.clone()));
+        })
+        .await;
+        with_request_retry_policy(None, async {
+            assert_eq!(current_request_retry_policy(), None);
+        })
+        .await;
+    }
+}
@@ -1,4 +1,4 @@
-use crate::FirestoreConsistencySelector;
+use crate::{FirestoreConsistencySelector, FirestoreQueryRecorder, FirestoreRetryPolicy};
 use rsb_derive::*;
 
 #[derive(Clone, Builder)]
@@ -7,6 +7,11 @@ pub struct FirestoreDbSessionParams {
 
     #[default = "FirestoreDbSessionCacheMode::None"]
     pub cache_mode: FirestoreDbSessionCacheMode,
+
+    pub retry_policy: Option<FirestoreRetryPolicy>,
+
+    /// Records the executed queries, e.g. to generate the required indexes.
+    pub query_recorder: Option<FirestoreQueryRecorder>,
 }
 
 #[derive(Clone)]
@@ -0,0 +1,191 @@
+use crate::errors::{
+    FirestoreError, FirestoreInvalidParametersError, FirestoreInvalidParametersPublicDetails,
+};
+use crate::{
+    FirestoreConsistencySelector, FirestoreDb, FirestoreExprBuilder, FirestoreResult,
+    FirestoreTransaction, FirestoreTransactionId, FirestoreTransactionMode,
+    FirestoreTransactionOptions,
+};
+use chrono::prelude::*;
+use chrono::Duration;
+use rsb_derive::Builder;
+
+/// Firestore keeps the versions of the documents for one hour
+/// or up to 7 days with the point-in-time recovery (PITR) enabled.
+const FIRESTORE_VERSION_RETENTION_HOURS: i64 = 1;
+const FIRESTORE_PITR_RETENTION_DAYS: i64 = 7;
+
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreSnapshotOptions {
+    /// Allows the read times up to 7 days old for the databases with PITR enabled,
+    /// otherwise the read time must be within the version retention of one hour.
+    #[default = "false"]
+    pub pitr_enabled: bool,
+}
+
+impl Default for FirestoreSnapshotOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read-only transaction to read the documents at the same point in time.
+/// All the reads using `fluent()` or `db()` share the consistency selector of the transaction.
+pub struct FirestoreSnapshot<'a> {
+    transaction: FirestoreTransaction<'a>,
+    db: FirestoreDb,
+    read_time: Option<DateTime<Utc>>,
+}
+
+impl<'a> FirestoreSnapshot<'a> {
+    pub fn fluent(&self) -> FirestoreExprBuilder<'_, FirestoreDb> {
+        self.db.fluent()
+    }
+
+    pub fn db(&self) -> &FirestoreDb {
+        &self.db
+    }
+
+    pub fn transaction_id(&self) -> &FirestoreTransactionId {
+        &self.transaction.transaction_id
+    }
+
+    /// The read time of the snapshot, `None` when it was chosen by Firestore with `snapshot_now`.
+    pub fn read_time(&self) -> Option<DateTime<Utc>> {
+        self.read_time
+    }
+
+    pub async fn finish(self) -> FirestoreResult<()> {
+        self.transaction.commit().await?;
+        Ok(())
+    }
+}
+
+impl FirestoreDb {
+    /// Snapshot at the specified time within the version retention of one hour.
+    pub async fn snapshot_at(
+        &self,
+        read_time: DateTime<Utc>,
+    ) -> FirestoreResult<FirestoreSnapshot<'_>> {
+        self.snapshot_at_with_options(read_time, FirestoreSnapshotOptions::new())
+            .await
+    }
+
+    /// Snapshot at the specified time within the version retention or the PITR window when enabled in the options.
+    pub async fn snapshot_at_with_options(
+        &self,
+        read_time: DateTime<Utc>,
+        options: FirestoreSnapshotOptions,
+    ) -> FirestoreResult<FirestoreSnapshot<'_>> {
+        ensure_read_time_in_retention(read_time, Utc::now(), &options)?;
+        self.begin_snapshot(
+            FirestoreTransactionMode::ReadOnlyWithConsistency(
+                FirestoreConsistencySelector::ReadTime(read_time),
+            ),
+            Some(read_time),
+        )
+        .await
+    }
+
+    pub async fn snapshot_now(&self) -> FirestoreResult<FirestoreSnapshot<'_>> {
+        self.begin_snapshot(FirestoreTransactionMode::ReadOnly, None)
+            .await
+    }
+
+    async fn begin_snapshot(
+        &self,
+        mode: FirestoreTransactionMode,
+        read_time: Option<DateTime<Utc>>,
+    ) -> FirestoreResult<FirestoreSnapshot<'_>> {
+        let transaction = self
+            .begin_transaction_with_options(FirestoreTransactionOptions::new().with_mode(mode))
+            .await?;
+        let db = self.clone_with_consistency_selector(FirestoreConsistencySelector::Transaction(
+            transaction.transaction_id.clone(),
+        ));
+
+        Ok(FirestoreSnapshot {
+            transaction,
+            db,
+            read_time,
+        })
+    }
+}
+
+pub(crate) fn ensure_read_time_in_retention(
+    read_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+    options: &FirestoreSnapshotOptions,
+) -> FirestoreResult<()> {
+    let age = now.signed_duration_since(read_time);
+
+    if age < Duration::zero() {
+        return Err(invalid_read_time_error("Read time can't be in the future"));
+    }
+    if age <= Duration::hours(FIRESTORE_VERSION_RETENTION_HOURS) {
+        return Ok(());
+    }
+    if !options.pitr_enabled {
+        return Err(invalid_read_time_error(&format!(
+            "Read time is outside the version retention of {FIRESTORE_VERSION_RETENTION_HOURS} hour without PITR enabled in the options"
+        )));
+    }
+    if age > Duration::days(FIRESTORE_PITR_RETENTION_DAYS) {
+        return Err(invalid_read_time_error(&format!(
+            "Read time is outside the PITR window of {FIRESTORE_PITR_RETENTION_DAYS} days"
+        )));
+    }
+    if read_time.second() != 0 || read_time.nanosecond() != 0 {
+        return Err(invalid_read_time_error(
+            "Read time older than one hour must be a whole minute",
+        ));
+    }
+
+    Ok(())
+}
+
+fn invalid_read_time_error(error: &str) -> FirestoreError {
+    FirestoreError::InvalidParametersError(FirestoreInvalidParametersError::new(
+        FirestoreInvalidParametersPublicDetails::new("read_time".to_string(), error.to_string()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_time_retention_test() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 12, 30, 15).unwrap();
+        let default_options = FirestoreSnapshotOptions::new();
+        let pitr_options = FirestoreSnapshotOptions::new().with_pitr_enabled(true);
+
+        for options in [&default_options, &pitr_options] {
+            assert!(ensure_read_time_in_retention(now, now, options).is_ok());
+            assert!(
+                ensure_read_time_in_retention(now - Duration::minutes(59), now, options).is_ok()
+            );
+            assert!(matches!(
+                ensure_read_time_in_retention(now + Duration::seconds(1), now, options),
+                Err(FirestoreError::InvalidParametersError(_))
+            ));
+        }
+
+        let pitr_read_time = Utc.with_ymd_and_hms(2024, 3, 5, 8, 15, 0).unwrap();
+        assert!(ensure_read_time_in_retention(pitr_read_time, now, &pitr_options).is_ok());
+        assert!(matches!(
+            ensure_read_time_in_retention(pitr_read_time, now, &default_options),
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+
+        for read_time in [
+            now - Duration::days(8),
+            Utc.with_ymd_and_hms(2024, 3, 5, 8, 15, 30).unwrap(),
+        ] {
+            assert!(matches!(
+                ensure_read_time_in_retention(read_time, now, &pitr_options),
+                Err(FirestoreError::InvalidParametersError(_))
+            ));
+        }
+    }
+}
@@ -1,14 +1,23 @@
+use crate::db::safe_document_path;
 use crate::errors::*;
+use crate::firestore_serde::firestore_document_to_serializable;
 use crate::timestamp_utils::from_timestamp;
 use crate::{
-    FirestoreConsistencySelector, FirestoreDb, FirestoreError, FirestoreResult,
-    FirestoreTransactionId, FirestoreTransactionMode, FirestoreTransactionOptions,
-    FirestoreTransactionResponse, FirestoreWriteResult,
+    FirestoreConsistencySelector, FirestoreDb, FirestoreError, FirestoreGetByIdSupport,
+    FirestoreQueryParams, FirestoreQuerySupport, FirestoreResult, FirestoreTransactionId,
+    FirestoreTransactionMode, FirestoreTransactionOptions, FirestoreTransactionResponse,
+    FirestoreWriteResult,
 };
 use backoff::future::retry;
 use backoff::ExponentialBackoffBuilder;
 use futures::future::BoxFuture;
-use gcloud_sdk::google::firestore::v1::{BeginTransactionRequest, CommitRequest, RollbackRequest};
+use gcloud_sdk::google::firestore::v1::{
+    write, BeginTransactionRequest, CommitRequest, Document, RollbackRequest,
+};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
 use tracing::*;
 
@@ -17,6 +26,7 @@ pub struct FirestoreTransaction<'a> {
     pub transaction_id: FirestoreTransactionId,
     pub transaction_span: Span,
     writes: Vec<gcloud_sdk::google::firestore::v1::Write>,
+    read_documents: BTreeSet<String>,
     finished: bool,
 }
 
@@ -32,7 +42,7 @@ impl<'a> FirestoreTransaction<'a> {
             "/firestore/commit_time" = field::Empty
         );
 
-        let request = gcloud_sdk::tonic::Request::new(BeginTransactionRequest {
+        let request = db.create_request(BeginTransactionRequest {
             database: db.get_database_path().clone(),
             options: Some(options.clone().try_into()?),
         });
@@ -58,6 +68,7 @@ impl<'a> FirestoreTransaction<'a> {
             transaction_id: response.transaction,
             transaction_span,
             writes: Vec::new(),
+            read_documents: BTreeSet::new(),
             finished: false,
         })
     }
@@ -76,24 +87,180 @@ impl<'a> FirestoreTransaction<'a> {
         Ok(self)
     }
 
+    /// Reads a document in the transaction and records it in the read set.
+    /// Missing documents are recorded as well, since their absence is a part of the transaction.
+    pub async fn get_doc<S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+    ) -> FirestoreResult<Option<Document>>
+    where
+        S: AsRef<str> + Send,
+    {
+        let parent = self.db.get_documents_path().clone();
+        self.get_doc_at(parent.as_str(), collection_id, document_id)
+            .await
+    }
+
+    pub async fn get_doc_at<S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+    ) -> FirestoreResult<Option<Document>>
+    where
+        S: AsRef<str> + Send,
+    {
+        self.ensure_reads_allowed()?;
+        let document_path = safe_document_path(parent, collection_id, document_id.as_ref())?;
+
+        let doc = match self
+            .transaction_db()
+            .get_doc_at(parent, collection_id, document_id, None)
+            .await
+        {
+            Ok(doc) => Some(doc),
+            Err(FirestoreError::DataNotFoundError(_)) => None,
+            Err(err) => return Err(err),
+        };
+        self.read_documents.insert(document_path);
+        Ok(doc)
+    }
+
+    pub async fn get_obj<T, S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+    ) -> FirestoreResult<Option<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        let parent = self.db.get_documents_path().clone();
+        self.get_obj_at(parent.as_str(), collection_id, document_id)
+            .await
+    }
+
+    pub async fn get_obj_at<T, S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+    ) -> FirestoreResult<Option<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        self.get_doc_at(parent, collection_id, document_id)
+            .await?
+            .map(|doc| firestore_document_to_serializable(&doc))
+            .transpose()
+    }
+
+    /// Runs the query in the transaction and records the returned documents in the read set.
+    pub async fn query_doc(
+        &mut self,
+        params: FirestoreQueryParams,
+    ) -> FirestoreResult<Vec<Document>> {
+        self.ensure_reads_allowed()?;
+        let docs = self.transaction_db().query_doc(params).await?;
+        self.read_documents
+            .extend(docs.iter().map(|doc| doc.name.clone()));
+        Ok(docs)
+    }
+
+    pub async fn query_obj<T>(&mut self, params: FirestoreQueryParams) -> FirestoreResult<Vec<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        self.query_doc(params)
+            .await?
+            .iter()
+            .map(firestore_document_to_serializable)
+            .collect()
+    }
+
+    /// Documents read with the transaction methods so far.
+    pub fn read_documents(&self) -> &BTreeSet<String> {
+        &self.read_documents
+    }
+
+    /// Documents changed by the writes added so far.
+    pub fn written_documents(&self) -> BTreeSet<String> {
+        self.writes
+            .iter()
+            .filter_map(|write| match &write.operation {
+                Some(write::Operation::Update(doc)) => Some(doc.name.clone()),
+                Some(write::Operation::Delete(document_path)) => Some(document_path.clone()),
+                Some(write::Operation::Transform(transform)) => Some(transform.document.clone()),
+                None => None,
+            })
+            .collect()
+    }
+
+    // Firestore requires all the reads to be done before the writes in a transaction
+    fn ensure_reads_allowed(&self) -> FirestoreResult<()> {
+        if self.writes.is_empty() {
+            Ok(())
+        } else {
+            Err(FirestoreError::InvalidParametersError(
+                FirestoreInvalidParametersError::new(FirestoreInvalidParametersPublicDetails::new(
+                    "transaction".to_string(),
+                    "Reads aren't allowed after writes have been added to the transaction"
+                        .to_string(),
+                )),
+            ))
+        }
+    }
+
+    fn transaction_db(&self) -> FirestoreDb {
+        self.db
+            .clone_with_consistency_selector(FirestoreConsistencySelector::Transaction(
+                self.transaction_id.clone(),
+            ))
+    }
+
+    /// Commits the writes. A failed commit returns the original error and reports the documents
+    /// read and written in a warning event, they are also available with `read_documents()` and
+    /// `written_documents()` before committing.
     pub async fn commit(mut self) -> FirestoreResult<FirestoreTransactionResponse> {
         self.finished = true;
 
+        let read_documents: Vec<String> = std::mem::take(&mut self.read_documents)
+            .into_iter()
+            .collect();
+        let written_documents: Vec<String> = self.written_documents().into_iter().collect();
+
         if self.writes.is_empty() {
             self.transaction_span.in_scope(|| {
                 debug!("Transaction has been committed without any writes");
             });
 
-            return Ok(FirestoreTransactionResponse::new(Vec::new()));
+            return Ok(
+                FirestoreTransactionResponse::new(Vec::new()).with_read_documents(read_documents)
+            );
         }
 
-        let request = gcloud_sdk::tonic::Request::new(CommitRequest {
+        let request = self.db.create_request(CommitRequest {
             database: self.db.get_database_path().clone(),
             writes: self.writes.drain(..).collect(),
             transaction: self.transaction_id.clone(),
         });
 
-        let response = self.db.client().get().commit(request).await?.into_inner();
+        let response = match self.db.client().get().commit(request).await {
+            Ok(response) => response.into_inner(),
+            Err(status) => {
+                self.transaction_span.in_scope(|| {
+                    warn!(
+                        read_documents = ?read_documents,
+                        written_documents = ?written_documents,
+                        "Transaction commit failed: {}",
+                        status
+                    );
+                });
+                return Err(status.into());
+            }
+        };
 
         let result = FirestoreTransactionResponse::new(
             response
@@ -102,7 +269,9 @@ impl<'a> FirestoreTransaction<'a> {
                 .map(|s| s.try_into())
                 .collect::<FirestoreResult<Vec<FirestoreWriteResult>>>()?,
         )
-        .opt_commit_time(response.commit_time.map(from_timestamp).transpose()?);
+        .opt_commit_time(response.commit_time.map(from_timestamp).transpose()?)
+        .with_read_documents(read_documents)
+        .with_written_documents(written_documents);
 
         if let Some(ref commit_time) = result.commit_time {
             self.transaction_span
@@ -118,7 +287,7 @@ impl<'a> FirestoreTransaction<'a> {
 
     pub async fn rollback(mut self) -> FirestoreResult<()> {
         self.finished = true;
-        let request = gcloud_sdk::tonic::Request::new(RollbackRequest {
+        let request = self.db.create_request(RollbackRequest {
             database: self.db.get_database_path().clone(),
             transaction: self.transaction_id.clone(),
         });
@@ -146,6 +315,27 @@ impl<'a> Drop for FirestoreTransaction<'a> {
     }
 }
 
+/// The function running in a transaction with the returned future borrowing the transaction,
+/// implemented for the functions like `async fn(FirestoreDb, &mut FirestoreTransaction<'_>) -> Result<T, E>`.
+/// The last parameter only keeps the transaction outliving its borrow and shouldn't be specified.
+pub trait FirestoreTransactionFn<'b, 't, T, E, ImpliedBounds = &'b &'t ()> {
+    type Future: Future<Output = std::result::Result<T, E>> + Send;
+
+    fn call(&self, db: FirestoreDb, transaction: &'b mut FirestoreTransaction<'t>) -> Self::Future;
+}
+
+impl<'b, 't, T, E, FN, F> FirestoreTransactionFn<'b, 't, T, E> for FN
+where
+    FN: Fn(FirestoreDb, &'b mut FirestoreTransaction<'t>) -> F,
+    F: Future<Output = std::result::Result<T, E>> + Send,
+{
+    type Future = F;
+
+    fn call(&self, db: FirestoreDb, transaction: &'b mut FirestoreTransaction<'t>) -> Self::Future {
+        self(db, transaction)
+    }
+}
+
 impl FirestoreDb {
     pub async fn begin_transaction(&self) -> FirestoreResult<FirestoreTransaction> {
         Self::begin_transaction_with_options(self, FirestoreTransactionOptions::new()).await
@@ -170,6 +360,39 @@ impl FirestoreDb {
             .await
     }
 
+    /// Runs a transaction with the function returning any error instead of `BackoffError`.
+    /// The function is retried when its error is caused by a database error allowing retries,
+    /// the same way as the commit itself. The function may fail with any error convertible to `Box<dyn Error + Send + Sync>`
+    /// (such as `FirestoreError`, `anyhow::Error` or a boxed error) and is usually an `async fn`:
+    /// ```ignore
+    /// async fn increment(db: FirestoreDb, transaction: &mut FirestoreTransaction<'_>) -> FirestoreResult<u64> { ... }
+    ///
+    /// let value = db.run_typed_transaction(increment).await?;
+    /// ```
+    pub async fn run_typed_transaction<T, FN, E>(&self, func: FN) -> FirestoreResult<T>
+    where
+        for<'b, 't> FN: FirestoreTransactionFn<'b, 't, T, E>,
+        T: 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        self.run_typed_transaction_with_options(func, FirestoreTransactionOptions::new())
+            .await
+    }
+
+    pub async fn run_typed_transaction_with_options<T, FN, E>(
+        &self,
+        func: FN,
+        options: FirestoreTransactionOptions,
+    ) -> FirestoreResult<T>
+    where
+        for<'b, 't> FN: FirestoreTransactionFn<'b, 't, T, E>,
+        T: 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        self.run_transaction_fn(func, |err| boxed_err_to_backoff(err.into()), options)
+            .await
+    }
+
     pub async fn run_transaction_with_options<T, FN, E>(
         &self,
         func: FN,
@@ -181,9 +404,34 @@ impl FirestoreDb {
             &'b mut FirestoreTransaction,
         ) -> BoxFuture<'b, std::result::Result<T, BackoffError<E>>>,
         E: std::error::Error + Send + Sync + 'static,
+    {
+        self.run_transaction_fn(
+            func,
+            |backoff_err: BackoffError<E>| match backoff_err {
+                BackoffError::Permanent(err) => BackoffError::Permanent(err.into()),
+                BackoffError::Transient { err, retry_after } => BackoffError::Transient {
+                    err: err.into(),
+                    retry_after,
+                },
+            },
+            options,
+        )
+        .await
+    }
+
+    async fn run_transaction_fn<T, FN, E, CE>(
+        &self,
+        func: FN,
+        classify_err: CE,
+        options: FirestoreTransactionOptions,
+    ) -> FirestoreResult<T>
+    where
+        for<'b, 't> FN: FirestoreTransactionFn<'b, 't, T, E>,
+        CE: Fn(E) -> BackoffError<Box<dyn std::error::Error + Send + Sync>>,
     {
         // Perform our initial attempt. If this fails and the backend tells us we can retry,
         // we'll try again with exponential backoff using the first attempt's transaction ID.
+        let retries_allowed = options.max_attempts.map_or(true, |max| max > 1);
         let (transaction_id, transaction_span, initial_backoff_duration) = {
             let mut transaction = self.begin_transaction_with_options(options.clone()).await?;
             let transaction_id = transaction.transaction_id().clone();
@@ -194,12 +442,17 @@ impl FirestoreDb {
                 FirestoreConsistencySelector::Transaction(transaction_id.clone()),
             );
 
-            match func(cdb, &mut transaction).await {
+            // Boxing the future keeps the borrowing future type out of the returned future type
+            let func_future: BoxFuture<std::result::Result<T, E>> =
+                Box::pin(func.call(cdb, &mut transaction));
+            match func_future.await {
                 Ok(ret_val) => {
                     match transaction.commit().await {
                         Ok(_) => return Ok(ret_val),
                         Err(err) => match err {
-                            FirestoreError::DatabaseError(ref db_err) if db_err.retry_possible => {
+                            FirestoreError::DatabaseError(ref db_err)
+                                if db_err.retry_possible && retries_allowed =>
+                            {
                                 transaction_span.in_scope(|| {
                                     warn!(
                                         "Transient error occurred in committing transaction: {}",
@@ -212,16 +465,16 @@ impl FirestoreDb {
                         },
                     }
                 }
-                Err(err) => match err {
-                    BackoffError::Transient { err, retry_after } => {
+                Err(err) => match classify_err(err) {
+                    BackoffError::Transient { err, retry_after } if retries_allowed => {
                         transaction_span.in_scope(|| {
                             warn!("Transient error occurred in transaction function: {}. Retrying after: {:?}", &err, retry_after)
                         });
                         initial_backoff_duration = retry_after;
                     }
-                    BackoffError::Permanent(err) => {
+                    BackoffError::Permanent(err) | BackoffError::Transient { err, .. } => {
                         return Err(FirestoreError::ErrorInTransaction(
-                            FirestoreErrorInTransaction::new(transaction_id.clone(), Box::new(err)),
+                            FirestoreErrorInTransaction::new(transaction_id.clone(), err),
                         ))
                     }
                 },
@@ -244,7 +497,17 @@ impl FirestoreDb {
             )))
             .build();
 
+        let attempts = AtomicU32::new(1);
         let retry_result = retry(backoff, || async {
+            let attempt = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+            let last_attempt = options.max_attempts.map_or(false, |max| attempt >= max);
+            let attempt_err_to_backoff = |err| match firestore_err_to_backoff(err) {
+                BackoffError::Transient { err, .. } if last_attempt => {
+                    backoff::Error::permanent(err)
+                }
+                backoff_err => backoff_err,
+            };
+
             let options = FirestoreTransactionOptions {
                 mode: FirestoreTransactionMode::ReadWriteRetry(transaction_id.clone()),
                 ..options
@@ -252,15 +515,17 @@ impl FirestoreDb {
             let mut transaction = self
                 .begin_transaction_with_options(options)
                 .await
-                .map_err(firestore_err_to_backoff)?;
+                .map_err(attempt_err_to_backoff)?;
             let transaction_id = transaction.transaction_id().clone();
 
             let cdb = self.clone_with_consistency_selector(
                 FirestoreConsistencySelector::Transaction(transaction_id.clone()),
             );
 
-            let ret_val = func(cdb, &mut transaction).await.map_err(|backoff_err| {
-                match backoff_err {
+            let func_future: BoxFuture<std::result::Result<T, E>> =
+                Box::pin(func.call(cdb, &mut transaction));
+            let ret_val = func_future.await.map_err(|err| {
+                match classify_err(err) {
                     BackoffError::Transient { err, retry_after } => {
                         transaction_span.in_scope(|| {
                             warn!("Transient error occurred in transaction function: {}. Retrying after: {:?}", &err, &retry_after)
@@ -269,11 +534,13 @@ impl FirestoreDb {
                         let firestore_err = FirestoreError::ErrorInTransaction(
                             FirestoreErrorInTransaction::new(
                                 transaction_id.clone(),
-                                Box::new(err)
+                                err
                             ),
                         );
 
-                        if let Some(retry_after_duration) = retry_after {
+                        if last_attempt {
+                            backoff::Error::permanent(firestore_err)
+                        } else if let Some(retry_after_duration) = retry_after {
                             backoff::Error::retry_after(
                                 firestore_err,
                                 retry_after_duration
@@ -287,7 +554,7 @@ impl FirestoreDb {
                             FirestoreError::ErrorInTransaction(
                                 FirestoreErrorInTransaction::new(
                                     transaction_id.clone(),
-                                    Box::new(err)
+                                    err
                                 ),
                             )
                         )
@@ -298,7 +565,7 @@ impl FirestoreDb {
             transaction
                 .commit()
                 .await
-                .map_err(firestore_err_to_backoff)?;
+                .map_err(attempt_err_to_backoff)?;
 
             Ok(ret_val)
         })
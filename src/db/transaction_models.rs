@@ -9,6 +9,8 @@ pub struct FirestoreTransactionOptions {
     #[default = "FirestoreTransactionMode::ReadWrite"]
     pub mode: FirestoreTransactionMode,
     pub max_elapsed_time: Option<Duration>,
+    /// Maximum number of attempts including the first one, unlimited within `max_elapsed_time` by default.
+    pub max_attempts: Option<u32>,
 }
 
 impl Default for FirestoreTransactionOptions {
@@ -16,6 +18,7 @@ impl Default for FirestoreTransactionOptions {
         Self {
             mode: FirestoreTransactionMode::ReadWrite,
             max_elapsed_time: None,
+            max_attempts: None,
         }
     }
 }
@@ -89,4 +92,9 @@ pub type FirestoreTransactionId = Vec<u8>;
 pub struct FirestoreTransactionResponse {
     pub write_results: Vec<FirestoreWriteResult>,
     pub commit_time: Option<DateTime<Utc>>,
+    /// Documents read with the transaction methods, useful for debugging the contention.
+    #[default = "Vec::new()"]
+    pub read_documents: Vec<String>,
+    #[default = "Vec::new()"]
+    pub written_documents: Vec<String>,
 }
@@ -3,8 +3,10 @@ use crate::{
     FirestoreDb, FirestoreError, FirestoreFieldTransform, FirestoreResult, FirestoreTransaction,
     FirestoreWritePrecondition,
 };
-use gcloud_sdk::google::firestore::v1::Write;
+use gcloud_sdk::google::firestore::v1::{value, Document, Value, Write};
 use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct UpdateObjectOperation<'a, T, S>
@@ -57,6 +59,98 @@ where
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct CreateDocumentOperation<S>
+where
+    S: AsRef<str>,
+{
+    pub parent: String,
+    pub collection_id: String,
+    pub document_id: S,
+    pub document: Document,
+}
+
+impl<S> TryInto<Write> for CreateDocumentOperation<S>
+where
+    S: AsRef<str>,
+{
+    type Error = FirestoreError;
+
+    fn try_into(self) -> Result<Write, Self::Error> {
+        Ok(Write {
+            update_mask: None,
+            update_transforms: vec![],
+            current_document: Some(FirestoreWritePrecondition::Exists(false).try_into()?),
+            operation: Some(gcloud_sdk::google::firestore::v1::write::Operation::Update(
+                Document {
+                    name: safe_document_path(
+                        &self.parent,
+                        self.collection_id.as_str(),
+                        self.document_id.as_ref(),
+                    )?,
+                    ..self.document
+                },
+            )),
+        })
+    }
+}
+
+pub(crate) fn object_merge_field_paths<T>(obj: &T) -> FirestoreResult<Vec<String>>
+where
+    T: Serialize,
+{
+    Ok(document_merge_field_paths(&FirestoreDb::serialize_to_doc(
+        "", obj,
+    )?))
+}
+
+/// Field paths to all the leaf values of the document, the nested maps are merged as well.
+fn document_merge_field_paths(document: &Document) -> Vec<String> {
+    let mut field_paths = Vec::new();
+    collect_merge_field_paths(None, &document.fields, &mut field_paths);
+    field_paths.sort();
+    field_paths
+}
+
+fn collect_merge_field_paths(
+    prefix: Option<&str>,
+    fields: &HashMap<String, Value>,
+    field_paths: &mut Vec<String>,
+) {
+    for (field_name, field_value) in fields {
+        let field_path = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, escape_field_path_segment(field_name)),
+            None => escape_field_path_segment(field_name).into_owned(),
+        };
+        match &field_value.value_type {
+            Some(value::ValueType::MapValue(map_value)) if !map_value.fields.is_empty() => {
+                collect_merge_field_paths(Some(&field_path), &map_value.fields, field_paths)
+            }
+            _ => field_paths.push(field_path),
+        }
+    }
+}
+
+/// Quotes the field names that aren't simple identifiers with backticks.
+fn escape_field_path_segment(field_name: &str) -> Cow<'_, str> {
+    let is_simple = field_name
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && field_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if is_simple {
+        Cow::Borrowed(field_name)
+    } else {
+        Cow::Owned(format!(
+            "`{}`",
+            field_name.replace('\\', "\\\\").replace('`', "\\`")
+        ))
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub(crate) struct DeleteOperation<S>
 where
@@ -180,6 +274,180 @@ impl<'a> FirestoreTransaction<'a> {
         })
     }
 
+    /// Creates the document, failing if it already exists.
+    pub fn create_doc<S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        document: Document,
+    ) -> FirestoreResult<&mut Self>
+    where
+        S: AsRef<str>,
+    {
+        self.create_doc_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            document,
+        )
+    }
+
+    pub fn create_doc_at<S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        document: Document,
+    ) -> FirestoreResult<&mut Self>
+    where
+        S: AsRef<str>,
+    {
+        self.add(CreateDocumentOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            document,
+        })
+    }
+
+    /// Same as `create_doc` for a serializable object.
+    pub fn create_object<T, S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.create_object_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            obj,
+            update_transforms,
+        )
+    }
+
+    pub fn create_object_at<T, S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.add(UpdateObjectOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            obj,
+            update_only: None,
+            precondition: Some(FirestoreWritePrecondition::Exists(false)),
+            update_transforms,
+        })
+    }
+
+    /// Creates or replaces the whole document.
+    pub fn set_object<T, S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.set_object_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            obj,
+            precondition,
+            update_transforms,
+        )
+    }
+
+    pub fn set_object_at<T, S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.update_object_at(
+            parent,
+            collection_id,
+            document_id,
+            obj,
+            None,
+            precondition,
+            update_transforms,
+        )
+    }
+
+    /// Creates or updates the document with only the fields present in the object.
+    pub fn set_object_merge<T, S>(
+        &mut self,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.set_object_merge_at(
+            self.db.get_documents_path(),
+            collection_id,
+            document_id,
+            obj,
+            precondition,
+            update_transforms,
+        )
+    }
+
+    pub fn set_object_merge_at<T, S>(
+        &mut self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &T,
+        precondition: Option<FirestoreWritePrecondition>,
+        update_transforms: Vec<FirestoreFieldTransform>,
+    ) -> FirestoreResult<&mut Self>
+    where
+        T: Serialize + Sync + Send,
+        S: AsRef<str>,
+    {
+        self.add(UpdateObjectOperation {
+            parent: parent.to_string(),
+            collection_id: collection_id.to_string(),
+            document_id,
+            obj,
+            update_only: Some(object_merge_field_paths(obj)?),
+            precondition,
+            update_transforms,
+        })
+    }
+
     pub fn delete_by_id<S>(
         &mut self,
         collection_id: &str,
@@ -254,3 +522,60 @@ impl<'a> FirestoreTransaction<'a> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct MyNestedStructure {
+        nested_num: u64,
+        empty_map: BTreeMap<String, String>,
+    }
+
+    #[derive(Serialize)]
+    struct MyTestStructure {
+        some_string: String,
+        #[serde(rename = "some-num")]
+        some_num: u64,
+        nested: MyNestedStructure,
+    }
+
+    fn test_object() -> MyTestStructure {
+        MyTestStructure {
+            some_string: "test".to_string(),
+            some_num: 42,
+            nested: MyNestedStructure {
+                nested_num: 1,
+                empty_map: BTreeMap::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn object_merge_masks_leaf_fields() -> FirestoreResult<()> {
+        let obj = test_object();
+        let write: Write = UpdateObjectOperation {
+            parent: "projects/p/databases/(default)/documents".to_string(),
+            collection_id: "test".to_string(),
+            document_id: "doc-1",
+            obj: &obj,
+            update_only: Some(object_merge_field_paths(&obj)?),
+            precondition: None,
+            update_transforms: vec![],
+        }
+        .try_into()?;
+
+        assert_eq!(
+            write.update_mask.map(|mask| mask.field_paths),
+            Some(vec![
+                "`some-num`".to_string(),
+                "nested.empty_map".to_string(),
+                "nested.nested_num".to_string(),
+                "some_string".to_string(),
+            ])
+        );
+        Ok(())
+    }
+}
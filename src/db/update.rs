@@ -1,5 +1,7 @@
-use crate::db::safe_document_path;
-use crate::{FirestoreDb, FirestoreResult, FirestoreWritePrecondition};
+use crate::db::{current_request_retry_policy, safe_document_path};
+use crate::{
+    FirestoreDb, FirestoreError, FirestoreResult, FirestoreRetryPolicy, FirestoreWritePrecondition,
+};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use gcloud_sdk::google::firestore::v1::*;
@@ -45,6 +47,11 @@ pub trait FirestoreUpdateSupport {
         return_only_fields: Option<Vec<String>>,
         precondition: Option<FirestoreWritePrecondition>,
     ) -> FirestoreResult<Document>;
+
+    /// Retry policy for the updates retrying on their own, such as the fluent `modify`.
+    fn get_retry_policy(&self) -> FirestoreRetryPolicy {
+        current_request_retry_policy().unwrap_or_default()
+    }
 }
 
 #[async_trait]
@@ -126,7 +133,7 @@ impl FirestoreUpdateSupport for FirestoreDb {
             "/firestore/response_time" = field::Empty,
         );
 
-        let update_document_request = gcloud_sdk::tonic::Request::new(UpdateDocumentRequest {
+        let update_document_request = UpdateDocumentRequest {
             update_mask: update_only.map({
                 |vf| DocumentMask {
                     field_paths: vf.iter().map(|f| f.to_string()).collect(),
@@ -137,13 +144,20 @@ impl FirestoreUpdateSupport for FirestoreDb {
                 field_paths: masks.clone(),
             }),
             current_document: precondition.map(|cond| cond.try_into()).transpose()?,
-        });
+        };
 
         let begin_query_utc: DateTime<Utc> = Utc::now();
         let update_response = self
-            .client()
-            .get()
-            .update_document(update_document_request)
+            .with_retries(&span, || {
+                let request = self.create_request(update_document_request.clone());
+                async move {
+                    self.client()
+                        .get()
+                        .update_document(request)
+                        .await
+                        .map_err(FirestoreError::from)
+                }
+            })
             .await?;
         let end_query_utc: DateTime<Utc> = Utc::now();
         let query_duration = end_query_utc.signed_duration_since(begin_query_utc);
@@ -159,4 +173,8 @@ impl FirestoreUpdateSupport for FirestoreDb {
 
         Ok(update_response.into_inner())
     }
+
+    fn get_retry_policy(&self) -> FirestoreRetryPolicy {
+        FirestoreDb::get_retry_policy(self)
+    }
 }
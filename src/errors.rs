@@ -1,5 +1,5 @@
 use crate::{FirestoreTransaction, FirestoreTransactionId};
-use gcloud_sdk::google::firestore::v1::WriteRequest;
+use gcloud_sdk::google::firestore::v1::{Write, WriteRequest};
 use rsb_derive::Builder;
 use serde::*;
 use std::error::Error;
@@ -16,6 +16,7 @@ pub enum FirestoreError {
     SerializeError(FirestoreSerializationError),
     DeserializeError(FirestoreSerializationError),
     NetworkError(FirestoreNetworkError),
+    DeadlineExceededError(FirestoreDeadlineExceededError),
     ErrorInTransaction(FirestoreErrorInTransaction),
     CacheError(FirestoreCacheError),
 }
@@ -31,6 +32,7 @@ impl Display for FirestoreError {
             FirestoreError::SerializeError(ref err) => err.fmt(f),
             FirestoreError::DeserializeError(ref err) => err.fmt(f),
             FirestoreError::NetworkError(ref err) => err.fmt(f),
+            FirestoreError::DeadlineExceededError(ref err) => err.fmt(f),
             FirestoreError::ErrorInTransaction(ref err) => err.fmt(f),
             FirestoreError::CacheError(ref err) => err.fmt(f),
         }
@@ -48,6 +50,7 @@ impl Error for FirestoreError {
             FirestoreError::SerializeError(ref err) => Some(err),
             FirestoreError::DeserializeError(ref err) => Some(err),
             FirestoreError::NetworkError(ref err) => Some(err),
+            FirestoreError::DeadlineExceededError(ref err) => Some(err),
             FirestoreError::ErrorInTransaction(ref err) => Some(err),
             FirestoreError::CacheError(ref err) => Some(err),
         }
@@ -154,6 +157,29 @@ impl Display for FirestoreNetworkError {
 
 impl std::error::Error for FirestoreNetworkError {}
 
+#[derive(Debug, Eq, PartialEq, Clone, Builder)]
+pub struct FirestoreDeadlineExceededError {
+    pub public: FirestoreErrorPublicGenericDetails,
+    pub message: String,
+}
+
+impl Display for FirestoreDeadlineExceededError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Deadline exceeded: {}", self.message)
+    }
+}
+
+impl std::error::Error for FirestoreDeadlineExceededError {}
+
+impl From<tokio::time::error::Elapsed> for FirestoreError {
+    fn from(elapsed: tokio::time::error::Elapsed) -> Self {
+        FirestoreError::DeadlineExceededError(FirestoreDeadlineExceededError::new(
+            FirestoreErrorPublicGenericDetails::new("DeadlineExceeded".into()),
+            format!("Request timed out: {elapsed}"),
+        ))
+    }
+}
+
 impl From<gcloud_sdk::error::Error> for FirestoreError {
     fn from(e: gcloud_sdk::error::Error) -> Self {
         FirestoreError::SystemError(FirestoreSystemError::new(
@@ -178,6 +204,12 @@ impl From<gcloud_sdk::tonic::Status> for FirestoreError {
                     format!("{status}"),
                 ))
             }
+            gcloud_sdk::tonic::Code::DeadlineExceeded => {
+                FirestoreError::DeadlineExceededError(FirestoreDeadlineExceededError::new(
+                    FirestoreErrorPublicGenericDetails::new(format!("{:?}", status.code())),
+                    format!("{status}"),
+                ))
+            }
             gcloud_sdk::tonic::Code::Aborted
             | gcloud_sdk::tonic::Code::Cancelled
             | gcloud_sdk::tonic::Code::Unavailable
@@ -320,6 +352,17 @@ impl From<tokio::sync::mpsc::error::SendError<gcloud_sdk::google::firestore::v1:
     }
 }
 
+impl From<tokio::sync::mpsc::error::SendError<gcloud_sdk::google::firestore::v1::Write>>
+    for FirestoreError
+{
+    fn from(send_error: tokio::sync::mpsc::error::SendError<Write>) -> Self {
+        FirestoreError::NetworkError(FirestoreNetworkError::new(
+            FirestoreErrorPublicGenericDetails::new("SEND_STREAM_ERROR".into()),
+            format!("Send stream error: {send_error}"),
+        ))
+    }
+}
+
 #[derive(Debug, Builder)]
 pub struct FirestoreErrorInTransaction {
     pub transaction_id: FirestoreTransactionId,
@@ -394,6 +437,30 @@ pub(crate) fn firestore_err_to_backoff(err: FirestoreError) -> BackoffError<Fire
     }
 }
 
+/// Classifies any error by the same rules: retryable when it is caused by a database error allowing retries.
+pub(crate) fn boxed_err_to_backoff(
+    err: Box<dyn Error + Send + Sync>,
+) -> BackoffError<Box<dyn Error + Send + Sync>> {
+    if is_retry_possible(err.as_ref()) {
+        backoff::Error::transient(err)
+    } else {
+        backoff::Error::permanent(err)
+    }
+}
+
+fn is_retry_possible(err: &(dyn Error + 'static)) -> bool {
+    let mut current: Option<&(dyn Error + 'static)> = Some(err);
+    while let Some(cause) = current {
+        if let Some(db_err) = cause.downcast_ref::<FirestoreDatabaseError>() {
+            if db_err.retry_possible {
+                return true;
+            }
+        }
+        current = cause.source();
+    }
+    false
+}
+
 pub(crate) type AnyBoxedErrResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 impl From<std::io::Error> for FirestoreError {
@@ -424,3 +491,51 @@ impl From<gcloud_sdk::prost::DecodeError> for FirestoreError {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct MyAppError(FirestoreError);
+
+    impl Display for MyAppError {
+        fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+            write!(f, "App error: {}", self.0)
+        }
+    }
+
+    impl Error for MyAppError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    fn database_error(retry_possible: bool) -> FirestoreError {
+        FirestoreError::DatabaseError(FirestoreDatabaseError::new(
+            FirestoreErrorPublicGenericDetails::new("Aborted".into()),
+            "Transaction was aborted".into(),
+            retry_possible,
+        ))
+    }
+
+    #[test]
+    fn boxed_err_to_backoff_follows_error_sources() {
+        assert!(matches!(
+            boxed_err_to_backoff(Box::new(database_error(true))),
+            BackoffError::Transient { .. }
+        ));
+        assert!(matches!(
+            boxed_err_to_backoff(Box::new(MyAppError(database_error(true)))),
+            BackoffError::Transient { .. }
+        ));
+        assert!(matches!(
+            boxed_err_to_backoff(Box::new(MyAppError(database_error(false)))),
+            BackoffError::Permanent(_)
+        ));
+        assert!(matches!(
+            boxed_err_to_backoff(Box::new(std::io::Error::from(std::io::ErrorKind::Other))),
+            BackoffError::Permanent(_)
+        ));
+    }
+}
@@ -0,0 +1,450 @@
+use crate::errors::*;
+use crate::timestamp_utils::{from_timestamp, to_timestamp};
+use crate::{FirestoreDocument, FirestoreResult, FirestoreValue};
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use gcloud_sdk::google::firestore::v1::{value, ArrayValue, MapValue, Value};
+use std::collections::HashMap;
+
+// Tags of the typed JSON encoding for the values that don't have a JSON counterpart
+const JSON_TYPE_TIMESTAMP: &str = "$timestamp";
+const JSON_TYPE_BYTES: &str = "$bytes";
+const JSON_TYPE_REFERENCE: &str = "$reference";
+const JSON_TYPE_GEO_POINT: &str = "$geoPoint";
+const JSON_TYPE_DOUBLE: &str = "$double";
+const JSON_TYPE_MAP: &str = "$map";
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FirestoreJsonMode {
+    /// Regular JSON values: timestamps are RFC 3339 strings, references are strings,
+    /// bytes are base64 strings and geo points are objects with `latitude` and `longitude`.
+    /// The Firestore types aren't restored when converting back.
+    Plain,
+    /// Lossless encoding keeping the Firestore types as objects with a single `$`-prefixed key,
+    /// such as `{"$timestamp": "2023-01-02T03:04:05Z"}`. Integers and doubles are JSON numbers
+    /// (doubles always have a fractional part).
+    Typed,
+}
+
+impl FirestoreValue {
+    /// Fails for the values without a JSON counterpart in the mode, such as NaN doubles
+    /// in the plain mode or timestamps out of the supported range.
+    pub fn to_json(&self, mode: FirestoreJsonMode) -> FirestoreResult<serde_json::Value> {
+        firestore_value_to_json(&self.value, mode)
+    }
+
+    /// Decodes the JSON in the mode. `FirestoreValue::from(serde_json::Value)` is a different conversion:
+    /// it comes from the conversion of any serializable value and keeps the typed tags such as
+    /// `{"$timestamp": ...}` as maps.
+    pub fn from_json(json: &serde_json::Value, mode: FirestoreJsonMode) -> FirestoreResult<Self> {
+        Ok(FirestoreValue::from(firestore_value_from_json(json, mode)?))
+    }
+}
+
+/// A document with the fields in the typed JSON encoding.
+/// Converts to and from `FirestoreDocument` without losses using `TryFrom`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FirestoreJsonDocument {
+    pub name: String,
+    pub fields: serde_json::Value,
+}
+
+impl TryFrom<FirestoreDocument> for FirestoreJsonDocument {
+    type Error = FirestoreError;
+
+    fn try_from(doc: FirestoreDocument) -> Result<Self, Self::Error> {
+        Ok(Self {
+            fields: firestore_document_to_json(&doc, FirestoreJsonMode::Typed)?,
+            name: doc.name,
+        })
+    }
+}
+
+impl TryFrom<FirestoreJsonDocument> for FirestoreDocument {
+    type Error = FirestoreError;
+
+    fn try_from(doc: FirestoreJsonDocument) -> Result<Self, Self::Error> {
+        firestore_document_from_json(doc.name, &doc.fields, FirestoreJsonMode::Typed)
+    }
+}
+
+/// Converts the document fields to a JSON object.
+pub fn firestore_document_to_json(
+    doc: &FirestoreDocument,
+    mode: FirestoreJsonMode,
+) -> FirestoreResult<serde_json::Value> {
+    firestore_fields_to_json(&doc.fields, mode)
+}
+
+/// Creates a document with the fields from a JSON object.
+pub fn firestore_document_from_json<S>(
+    document_path: S,
+    json: &serde_json::Value,
+    mode: FirestoreJsonMode,
+) -> FirestoreResult<FirestoreDocument>
+where
+    S: AsRef<str>,
+{
+    Ok(FirestoreDocument {
+        name: document_path.as_ref().to_string(),
+        fields: firestore_fields_from_json(json, mode)?,
+        create_time: None,
+        update_time: None,
+    })
+}
+
+pub(crate) fn firestore_value_to_json(
+    value: &Value,
+    mode: FirestoreJsonMode,
+) -> FirestoreResult<serde_json::Value> {
+    let json = match &value.value_type {
+        None | Some(value::ValueType::NullValue(_)) => serde_json::Value::Null,
+        Some(value::ValueType::BooleanValue(v)) => serde_json::Value::Bool(*v),
+        Some(value::ValueType::IntegerValue(v)) => serde_json::Value::from(*v),
+        Some(value::ValueType::DoubleValue(v)) => match serde_json::Number::from_f64(*v) {
+            Some(number) => serde_json::Value::Number(number),
+            None if mode == FirestoreJsonMode::Typed => {
+                typed_json_value(JSON_TYPE_DOUBLE, serde_json::Value::from(v.to_string()))
+            }
+            None => {
+                return Err(json_serialize_error(format!(
+                    "Double {v} can't be represented in plain JSON"
+                )))
+            }
+        },
+        Some(value::ValueType::TimestampValue(ts)) => {
+            let dt = from_timestamp(ts.clone())
+                .map_err(|err| json_serialize_error(format!("Invalid timestamp {ts:?}: {err}")))?;
+            typed_json_value_for_mode(
+                JSON_TYPE_TIMESTAMP,
+                serde_json::Value::from(dt.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+                mode,
+            )
+        }
+        Some(value::ValueType::StringValue(v)) => serde_json::Value::from(v.as_str()),
+        Some(value::ValueType::BytesValue(v)) => typed_json_value_for_mode(
+            JSON_TYPE_BYTES,
+            serde_json::Value::from(base64::engine::general_purpose::STANDARD.encode(v)),
+            mode,
+        ),
+        Some(value::ValueType::ReferenceValue(v)) => typed_json_value_for_mode(
+            JSON_TYPE_REFERENCE,
+            serde_json::Value::from(v.as_str()),
+            mode,
+        ),
+        Some(value::ValueType::GeoPointValue(v)) => typed_json_value_for_mode(
+            JSON_TYPE_GEO_POINT,
+            serde_json::json!({ "latitude": v.latitude, "longitude": v.longitude }),
+            mode,
+        ),
+        Some(value::ValueType::ArrayValue(v)) => serde_json::Value::Array(
+            v.values
+                .iter()
+                .map(|value| firestore_value_to_json(value, mode))
+                .collect::<FirestoreResult<Vec<serde_json::Value>>>()?,
+        ),
+        Some(value::ValueType::MapValue(v)) => {
+            let fields = firestore_fields_to_json(&v.fields, mode)?;
+            // Maps with the keys looking like type tags are wrapped to be decoded unambiguously
+            if mode == FirestoreJsonMode::Typed && v.fields.keys().any(|key| key.starts_with('$')) {
+                typed_json_value(JSON_TYPE_MAP, fields)
+            } else {
+                fields
+            }
+        }
+    };
+    Ok(json)
+}
+
+pub(crate) fn firestore_fields_to_json<'a, I>(
+    fields: I,
+    mode: FirestoreJsonMode,
+) -> FirestoreResult<serde_json::Value>
+where
+    I: IntoIterator<Item = (&'a String, &'a Value)>,
+{
+    Ok(serde_json::Value::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| Ok((key.clone(), firestore_value_to_json(value, mode)?)))
+            .collect::<FirestoreResult<serde_json::Map<String, serde_json::Value>>>()?,
+    ))
+}
+
+pub(crate) fn firestore_value_from_json(
+    json: &serde_json::Value,
+    mode: FirestoreJsonMode,
+) -> FirestoreResult<Value> {
+    let value_type = match json {
+        serde_json::Value::Null => value::ValueType::NullValue(0),
+        serde_json::Value::Bool(v) => value::ValueType::BooleanValue(*v),
+        serde_json::Value::Number(v) => match v.as_i64() {
+            Some(v) => value::ValueType::IntegerValue(v),
+            None => value::ValueType::DoubleValue(
+                v.as_f64()
+                    .ok_or_else(|| typed_json_error(format!("Unsupported number value: {v}")))?,
+            ),
+        },
+        serde_json::Value::String(v) => value::ValueType::StringValue(v.clone()),
+        serde_json::Value::Array(values) => value::ValueType::ArrayValue(ArrayValue {
+            values: values
+                .iter()
+                .map(|value| firestore_value_from_json(value, mode))
+                .collect::<FirestoreResult<Vec<Value>>>()?,
+        }),
+        serde_json::Value::Object(fields) => match fields.iter().next() {
+            Some((tag, tagged_value))
+                if mode == FirestoreJsonMode::Typed
+                    && fields.len() == 1
+                    && tag.starts_with('$') =>
+            {
+                firestore_typed_json_value(tag, tagged_value)?
+            }
+            _ => value::ValueType::MapValue(MapValue {
+                fields: firestore_fields_from_json(json, mode)?,
+            }),
+        },
+    };
+    Ok(Value {
+        value_type: Some(value_type),
+    })
+}
+
+pub(crate) fn firestore_fields_from_json(
+    json: &serde_json::Value,
+    mode: FirestoreJsonMode,
+) -> FirestoreResult<HashMap<String, Value>> {
+    match json {
+        serde_json::Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), firestore_value_from_json(value, mode)?)))
+            .collect(),
+        other => Err(typed_json_error(format!(
+            "Expected JSON object, got: {other}"
+        ))),
+    }
+}
+
+fn firestore_typed_json_value(
+    tag: &str,
+    json: &serde_json::Value,
+) -> FirestoreResult<value::ValueType> {
+    match (tag, json) {
+        (JSON_TYPE_TIMESTAMP, serde_json::Value::String(v)) => {
+            let dt = DateTime::parse_from_rfc3339(v)
+                .map_err(|err| typed_json_error(format!("Invalid timestamp {v}: {err}")))?;
+            Ok(value::ValueType::TimestampValue(to_timestamp(
+                dt.with_timezone(&Utc),
+            )))
+        }
+        (JSON_TYPE_BYTES, serde_json::Value::String(v)) => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(v)
+                .map_err(|err| typed_json_error(format!("Invalid base64 bytes: {err}")))?;
+            Ok(value::ValueType::BytesValue(bytes))
+        }
+        (JSON_TYPE_REFERENCE, serde_json::Value::String(v)) => {
+            Ok(value::ValueType::ReferenceValue(v.clone()))
+        }
+        (JSON_TYPE_GEO_POINT, serde_json::Value::Object(fields)) => {
+            match (
+                fields.get("latitude").and_then(|v| v.as_f64()),
+                fields.get("longitude").and_then(|v| v.as_f64()),
+            ) {
+                (Some(latitude), Some(longitude)) => Ok(value::ValueType::GeoPointValue(
+                    gcloud_sdk::google::r#type::LatLng {
+                        latitude,
+                        longitude,
+                    },
+                )),
+                _ => Err(typed_json_error(format!("Invalid geo point: {json}"))),
+            }
+        }
+        (JSON_TYPE_DOUBLE, serde_json::Value::String(v)) => v
+            .parse::<f64>()
+            .map(value::ValueType::DoubleValue)
+            .map_err(|err| typed_json_error(format!("Invalid double {v}: {err}"))),
+        (JSON_TYPE_MAP, serde_json::Value::Object(_)) => Ok(value::ValueType::MapValue(MapValue {
+            fields: firestore_fields_from_json(json, FirestoreJsonMode::Typed)?,
+        })),
+        _ => Err(typed_json_error(format!(
+            "Unknown or invalid typed JSON value {tag}: {json}"
+        ))),
+    }
+}
+
+fn typed_json_value(tag: &str, value: serde_json::Value) -> serde_json::Value {
+    serde_json::Value::Object([(tag.to_string(), value)].into_iter().collect())
+}
+
+fn typed_json_value_for_mode(
+    tag: &str,
+    value: serde_json::Value,
+    mode: FirestoreJsonMode,
+) -> serde_json::Value {
+    match mode {
+        FirestoreJsonMode::Plain => value,
+        FirestoreJsonMode::Typed => typed_json_value(tag, value),
+    }
+}
+
+fn typed_json_error(message: String) -> FirestoreError {
+    FirestoreError::DeserializeError(FirestoreSerializationError::from_message(message))
+}
+
+fn json_serialize_error(message: String) -> FirestoreError {
+    FirestoreError::SerializeError(FirestoreSerializationError::from_message(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_json_round_trip() -> FirestoreResult<()> {
+        let json = serde_json::json!({
+            "int": 42,
+            "double": 42.0,
+            "nan": { "$double": "NaN" },
+            "string": "$reference",
+            "timestamp": { "$timestamp": "2023-01-02T03:04:05.123456789Z" },
+            "bytes": { "$bytes": "AQID" },
+            "reference": { "$reference": "projects/p/databases/(default)/documents/c/d" },
+            "geo": { "$geoPoint": { "latitude": 1.5, "longitude": -2.5 } },
+            "array": [1, "two", null, true],
+            "map": { "nested": { "$timestamp": "2023-01-02T03:04:05Z" } },
+            "tagged_keys": { "$map": { "$bytes": "not bytes" } }
+        });
+
+        let fields = firestore_fields_from_json(&json, FirestoreJsonMode::Typed)?;
+        assert_eq!(
+            fields.get("int").and_then(|v| v.value_type.clone()),
+            Some(value::ValueType::IntegerValue(42))
+        );
+        assert_eq!(
+            fields.get("double").and_then(|v| v.value_type.clone()),
+            Some(value::ValueType::DoubleValue(42.0))
+        );
+        assert_eq!(
+            fields.get("bytes").and_then(|v| v.value_type.clone()),
+            Some(value::ValueType::BytesValue(vec![1, 2, 3]))
+        );
+        assert!(matches!(
+            fields.get("nan").and_then(|v| v.value_type.clone()),
+            Some(value::ValueType::DoubleValue(v)) if v.is_nan()
+        ));
+
+        assert_eq!(
+            firestore_fields_to_json(&fields, FirestoreJsonMode::Typed)?,
+            json
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn plain_json_conversion() -> FirestoreResult<()> {
+        let value = FirestoreValue::from_map([
+            (
+                "timestamp",
+                FirestoreValue::from(Value {
+                    value_type: Some(value::ValueType::TimestampValue(
+                        gcloud_sdk::prost_types::Timestamp {
+                            seconds: 1672628645,
+                            nanos: 0,
+                        },
+                    )),
+                }),
+            ),
+            (
+                "reference",
+                FirestoreValue::from(Value {
+                    value_type: Some(value::ValueType::ReferenceValue(
+                        "projects/p/databases/(default)/documents/c/d".to_string(),
+                    )),
+                }),
+            ),
+        ]);
+
+        let plain = value.to_json(FirestoreJsonMode::Plain)?;
+        assert_eq!(
+            plain,
+            serde_json::json!({
+                "timestamp": "2023-01-02T03:04:05Z",
+                "reference": "projects/p/databases/(default)/documents/c/d"
+            })
+        );
+
+        let typed = value.to_json(FirestoreJsonMode::Typed)?;
+        assert_eq!(
+            FirestoreValue::from_json(&typed, FirestoreJsonMode::Typed)?,
+            value
+        );
+        // The serde conversion keeps the typed tags as maps
+        let serialized: FirestoreValue = typed.into();
+        assert_ne!(serialized, value);
+        Ok(())
+    }
+
+    #[test]
+    fn json_conversion_errors() {
+        let nan = FirestoreValue::from(Value {
+            value_type: Some(value::ValueType::DoubleValue(f64::NAN)),
+        });
+        let infinity = FirestoreValue::from(Value {
+            value_type: Some(value::ValueType::DoubleValue(f64::INFINITY)),
+        });
+        let out_of_range = FirestoreValue::from(Value {
+            value_type: Some(value::ValueType::TimestampValue(
+                gcloud_sdk::prost_types::Timestamp {
+                    seconds: i64::MAX,
+                    nanos: 0,
+                },
+            )),
+        });
+
+        for value in [&nan, &infinity, &out_of_range] {
+            assert!(matches!(
+                value.to_json(FirestoreJsonMode::Plain),
+                Err(FirestoreError::SerializeError(_))
+            ));
+        }
+        assert!(nan.to_json(FirestoreJsonMode::Typed).is_ok());
+        assert!(matches!(
+            FirestoreValue::from(Value {
+                value_type: Some(value::ValueType::ArrayValue(ArrayValue {
+                    values: vec![out_of_range.value],
+                })),
+            })
+            .to_json(FirestoreJsonMode::Typed),
+            Err(FirestoreError::SerializeError(_))
+        ));
+    }
+
+    #[test]
+    fn json_document_conversion() -> FirestoreResult<()> {
+        let doc = FirestoreDocument {
+            name: "projects/p/databases/(default)/documents/c/d".to_string(),
+            fields: firestore_fields_from_json(
+                &serde_json::json!({
+                    "timestamp": { "$timestamp": "2023-01-02T03:04:05Z" },
+                    "nested": { "bytes": { "$bytes": "AQID" } }
+                }),
+                FirestoreJsonMode::Typed,
+            )?,
+            create_time: None,
+            update_time: None,
+        };
+
+        let json_doc = FirestoreJsonDocument::try_from(doc.clone())?;
+        assert_eq!(json_doc.name, doc.name);
+        assert_eq!(
+            json_doc.fields,
+            serde_json::json!({
+                "timestamp": { "$timestamp": "2023-01-02T03:04:05Z" },
+                "nested": { "bytes": { "$bytes": "AQID" } }
+            })
+        );
+        assert_eq!(FirestoreDocument::try_from(json_doc)?, doc);
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{FirestoreValue, FirestoreWritePrecondition};
+use chrono::{DateTime, Utc};
+use gcloud_sdk::google::firestore::v1::value;
+use serde::{Deserialize, Deserializer};
+
+/// Deserialized object together with the document metadata.
+/// Can be used in place of `T` everywhere the objects are deserialized, e.g. `.obj::<FirestoreDocumentWithMetadata<T>>()`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FirestoreDocumentWithMetadata<T> {
+    /// Document ID, the last segment of the name.
+    pub id: String,
+    /// Full document path.
+    pub name: String,
+    /// Path of the parent document or the documents root.
+    pub parent: String,
+    pub collection_id: String,
+    pub create_time: Option<DateTime<Utc>>,
+    pub update_time: Option<DateTime<Utc>>,
+    pub obj: T,
+}
+
+impl<T> FirestoreDocumentWithMetadata<T> {
+    /// Precondition for optimistic concurrency that fails if the document has been changed since it was read.
+    pub fn update_time_precondition(&self) -> Option<FirestoreWritePrecondition> {
+        self.update_time.map(FirestoreWritePrecondition::UpdateTime)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.obj
+    }
+}
+
+impl<'de, T> Deserialize<'de> for FirestoreDocumentWithMetadata<T>
+where
+    for<'a> T: Deserialize<'a>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = FirestoreValue::deserialize(deserializer)?;
+
+        let fields = match &value.value.value_type {
+            Some(value::ValueType::MapValue(map_value)) => &map_value.fields,
+            _ => {
+                return Err(serde::de::Error::custom(
+                    "Document metadata is available only for documents",
+                ))
+            }
+        };
+
+        let string_field =
+            |field_name: &str| match fields.get(field_name).and_then(|v| v.value_type.as_ref()) {
+                Some(value::ValueType::StringValue(v)) => Some(v.clone()),
+                _ => None,
+            };
+
+        let time_field = |field_name: &str| -> Result<Option<DateTime<Utc>>, D::Error> {
+            string_field(field_name)
+                .map(|v| {
+                    DateTime::parse_from_rfc3339(&v)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .map_err(serde::de::Error::custom)
+                })
+                .transpose()
+        };
+
+        let name = string_field("_firestore_full_id").ok_or_else(|| {
+            serde::de::Error::custom("Document metadata is available only for documents")
+        })?;
+        let create_time = time_field("_firestore_created")?;
+        let update_time = time_field("_firestore_updated")?;
+
+        let mut segments = name.rsplitn(3, '/');
+        let id = segments.next().unwrap_or_default().to_string();
+        let collection_id = segments.next().unwrap_or_default().to_string();
+        let parent = segments.next().unwrap_or_default().to_string();
+
+        let obj = T::deserialize(value).map_err(serde::de::Error::custom)?;
+
+        Ok(Self {
+            id,
+            name,
+            parent,
+            collection_id,
+            create_time,
+            update_time,
+            obj,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firestore_serde::{
+        firestore_document_from_serializable, firestore_document_to_serializable,
+    };
+    use crate::timestamp_utils::to_timestamp;
+    use chrono::TimeZone;
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    struct TestStructure {
+        some_string: String,
+        #[serde(with = "crate::serialize_as_timestamp")]
+        created_at: DateTime<Utc>,
+    }
+
+    #[test]
+    fn deserialize_with_metadata() -> crate::FirestoreResult<()> {
+        let update_time = Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap();
+        let obj = TestStructure {
+            some_string: "test".to_string(),
+            created_at: Utc.with_ymd_and_hms(2022, 1, 2, 3, 4, 5).unwrap(),
+        };
+
+        let mut doc = firestore_document_from_serializable(
+            "projects/p/databases/(default)/documents/parents/p1/children/c1",
+            &obj,
+        )?;
+        doc.update_time = Some(to_timestamp(update_time));
+
+        let with_metadata: FirestoreDocumentWithMetadata<TestStructure> =
+            firestore_document_to_serializable(&doc)?;
+
+        assert_eq!(with_metadata.id, "c1");
+        assert_eq!(with_metadata.collection_id, "children");
+        assert_eq!(
+            with_metadata.parent,
+            "projects/p/databases/(default)/documents/parents/p1"
+        );
+        assert_eq!(with_metadata.create_time, None);
+        assert_eq!(
+            with_metadata.update_time_precondition(),
+            Some(FirestoreWritePrecondition::UpdateTime(update_time))
+        );
+        assert_eq!(with_metadata.obj, obj);
+        Ok(())
+    }
+}
@@ -13,6 +13,12 @@ pub use latlng_serializers::*;
 mod reference_serializers;
 pub use reference_serializers::*;
 
+mod vector_serializers;
+pub use vector_serializers::*;
+
+mod document_metadata;
+pub use document_metadata::*;
+
 use crate::FirestoreValue;
 use gcloud_sdk::google::firestore::v1::Value;
 
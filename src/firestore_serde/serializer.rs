@@ -232,6 +232,9 @@ impl serde::Serializer for FirestoreValueSerializer {
                     value, false,
                 )
             }
+            crate::firestore_serde::vector_serializers::FIRESTORE_VECTOR_TYPE_TAG_TYPE => {
+                crate::firestore_serde::vector_serializers::serialize_vector_for_firestore(value)
+            }
             _ => value.serialize(self),
         }
     }
@@ -0,0 +1,297 @@
+use gcloud_sdk::google::firestore::v1::value;
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+use crate::errors::*;
+use crate::firestore_serde::serializer::FirestoreValueSerializer;
+use crate::FirestoreValue;
+
+pub(crate) const FIRESTORE_VECTOR_TYPE_TAG_TYPE: &str = "FirestoreVector";
+
+// Firestore encodes vectors as a map with the type marker and an array of doubles
+pub(crate) const FIRESTORE_VECTOR_TYPE_FIELD: &str = "__type__";
+pub(crate) const FIRESTORE_VECTOR_TYPE_VALUE: &str = "__vector__";
+pub(crate) const FIRESTORE_VECTOR_VALUE_FIELD: &str = "value";
+
+/// Vector embedding stored as a Firestore vector value.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Default)]
+pub struct FirestoreVector(pub Vec<f64>);
+
+impl From<Vec<f64>> for FirestoreVector {
+    fn from(value: Vec<f64>) -> Self {
+        FirestoreVector(value)
+    }
+}
+
+impl From<Vec<f32>> for FirestoreVector {
+    fn from(value: Vec<f32>) -> Self {
+        FirestoreVector(value.into_iter().map(f64::from).collect())
+    }
+}
+
+impl<const N: usize> From<[f32; N]> for FirestoreVector {
+    fn from(value: [f32; N]) -> Self {
+        FirestoreVector(value.into_iter().map(f64::from).collect())
+    }
+}
+
+impl<const N: usize> From<[f64; N]> for FirestoreVector {
+    fn from(value: [f64; N]) -> Self {
+        FirestoreVector(value.to_vec())
+    }
+}
+
+impl From<FirestoreVector> for Vec<f64> {
+    fn from(value: FirestoreVector) -> Self {
+        value.0
+    }
+}
+
+/// Types that can be stored as Firestore vector values with `serialize_as_vector`.
+pub trait FirestoreVectorValues: Sized {
+    fn to_vector_values(&self) -> Vec<f64>;
+
+    fn from_vector_values(values: Vec<f64>) -> Result<Self, String>;
+}
+
+impl FirestoreVectorValues for FirestoreVector {
+    fn to_vector_values(&self) -> Vec<f64> {
+        self.0.clone()
+    }
+
+    fn from_vector_values(values: Vec<f64>) -> Result<Self, String> {
+        Ok(FirestoreVector(values))
+    }
+}
+
+impl FirestoreVectorValues for Vec<f64> {
+    fn to_vector_values(&self) -> Vec<f64> {
+        self.clone()
+    }
+
+    fn from_vector_values(values: Vec<f64>) -> Result<Self, String> {
+        Ok(values)
+    }
+}
+
+impl FirestoreVectorValues for Vec<f32> {
+    fn to_vector_values(&self) -> Vec<f64> {
+        self.iter().map(|v| f64::from(*v)).collect()
+    }
+
+    fn from_vector_values(values: Vec<f64>) -> Result<Self, String> {
+        Ok(values.into_iter().map(|v| v as f32).collect())
+    }
+}
+
+impl<const N: usize> FirestoreVectorValues for [f64; N] {
+    fn to_vector_values(&self) -> Vec<f64> {
+        self.to_vec()
+    }
+
+    fn from_vector_values(values: Vec<f64>) -> Result<Self, String> {
+        let len = values.len();
+        values
+            .try_into()
+            .map_err(|_| format!("Vector has {len} dimensions, expected {N}"))
+    }
+}
+
+impl<const N: usize> FirestoreVectorValues for [f32; N] {
+    fn to_vector_values(&self) -> Vec<f64> {
+        self.iter().map(|v| f64::from(*v)).collect()
+    }
+
+    fn from_vector_values(values: Vec<f64>) -> Result<Self, String> {
+        <[f64; N]>::from_vector_values(values).map(|values| values.map(|v| v as f32))
+    }
+}
+
+pub mod serialize_as_vector {
+    use crate::firestore_serde::vector_serializers::FirestoreVectorVisitor;
+    use crate::FirestoreVectorValues;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: FirestoreVectorValues,
+    {
+        serializer.serialize_newtype_struct(
+            crate::firestore_serde::FIRESTORE_VECTOR_TYPE_TAG_TYPE,
+            &value.to_vector_values(),
+        )
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FirestoreVectorValues,
+    {
+        let values = deserializer.deserialize_any(FirestoreVectorVisitor)?;
+        T::from_vector_values(values).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for FirestoreVector {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(FIRESTORE_VECTOR_TYPE_TAG_TYPE, &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for FirestoreVector {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_any(FirestoreVectorVisitor)
+            .map(FirestoreVector)
+    }
+}
+
+/// Accepts both the Firestore vector map encoding and a plain sequence of numbers.
+pub(crate) struct FirestoreVectorVisitor;
+
+impl<'de> Visitor<'de> for FirestoreVectorVisitor {
+    type Value = Vec<f64>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a Firestore vector value")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut result = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element::<f64>()? {
+            result.push(value);
+        }
+        Ok(result)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut vector_type: Option<String> = None;
+        let mut values: Option<Vec<f64>> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                FIRESTORE_VECTOR_TYPE_FIELD => vector_type = Some(map.next_value()?),
+                FIRESTORE_VECTOR_VALUE_FIELD => values = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+
+        match (vector_type.as_deref(), values) {
+            (Some(FIRESTORE_VECTOR_TYPE_VALUE), Some(values)) => Ok(values),
+            _ => Err(serde::de::Error::custom(
+                "Vector deserializer doesn't recognize the structure of the object",
+            )),
+        }
+    }
+}
+
+pub fn serialize_vector_for_firestore<T: ?Sized + Serialize>(
+    value: &T,
+) -> Result<FirestoreValue, FirestoreError> {
+    let serialized = value.serialize(FirestoreValueSerializer::new())?;
+    match serialized.value.value_type {
+        Some(value::ValueType::ArrayValue(array)) => {
+            let values = array
+                .values
+                .into_iter()
+                .map(|value| match value.value_type {
+                    Some(value::ValueType::DoubleValue(v)) => Ok(v),
+                    Some(value::ValueType::IntegerValue(v)) => Ok(v as f64),
+                    _ => Err(FirestoreError::SerializeError(
+                        FirestoreSerializationError::from_message(
+                            "Vector serializer supports only numbers",
+                        ),
+                    )),
+                })
+                .collect::<Result<Vec<f64>, FirestoreError>>()?;
+            Ok(firestore_vector_value(&values))
+        }
+        None => Ok(serialized),
+        _ => Err(FirestoreError::SerializeError(
+            FirestoreSerializationError::from_message(
+                "Vector serializer doesn't support this type",
+            ),
+        )),
+    }
+}
+
+pub(crate) fn firestore_vector_value(values: &[f64]) -> FirestoreValue {
+    let fields: HashMap<String, gcloud_sdk::google::firestore::v1::Value> = [
+        (
+            FIRESTORE_VECTOR_TYPE_FIELD.to_string(),
+            gcloud_sdk::google::firestore::v1::Value {
+                value_type: Some(value::ValueType::StringValue(
+                    FIRESTORE_VECTOR_TYPE_VALUE.to_string(),
+                )),
+            },
+        ),
+        (
+            FIRESTORE_VECTOR_VALUE_FIELD.to_string(),
+            gcloud_sdk::google::firestore::v1::Value {
+                value_type: Some(value::ValueType::ArrayValue(
+                    gcloud_sdk::google::firestore::v1::ArrayValue {
+                        values: values
+                            .iter()
+                            .map(|v| gcloud_sdk::google::firestore::v1::Value {
+                                value_type: Some(value::ValueType::DoubleValue(*v)),
+                            })
+                            .collect(),
+                    },
+                )),
+            },
+        ),
+    ]
+    .into();
+
+    FirestoreValue::from(gcloud_sdk::google::firestore::v1::Value {
+        value_type: Some(value::ValueType::MapValue(
+            gcloud_sdk::google::firestore::v1::MapValue { fields },
+        )),
+    })
+}
+
+/// Reads the vector components back from a Firestore vector value.
+pub(crate) fn firestore_vector_from_value(value: &value::ValueType) -> Option<Vec<f64>> {
+    match value {
+        value::ValueType::MapValue(map_value) => {
+            match (
+                map_value.fields.get(FIRESTORE_VECTOR_TYPE_FIELD),
+                map_value.fields.get(FIRESTORE_VECTOR_VALUE_FIELD),
+            ) {
+                (
+                    Some(gcloud_sdk::google::firestore::v1::Value {
+                        value_type: Some(value::ValueType::StringValue(vector_type)),
+                    }),
+                    Some(gcloud_sdk::google::firestore::v1::Value {
+                        value_type: Some(value::ValueType::ArrayValue(array)),
+                    }),
+                ) if vector_type == FIRESTORE_VECTOR_TYPE_VALUE => array
+                    .values
+                    .iter()
+                    .map(|v| match v.value_type {
+                        Some(value::ValueType::DoubleValue(v)) => Some(v),
+                        Some(value::ValueType::IntegerValue(v)) => Some(v as f64),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -1,7 +1,12 @@
+use crate::db::with_request_options;
 use crate::{
-    FirestoreBatch, FirestoreBatchWriter, FirestoreDeleteSupport, FirestoreResult,
-    FirestoreTransaction, FirestoreWritePrecondition,
+    FirestoreBatch, FirestoreBatchWriter, FirestoreDeleteSupport, FirestoreRecursiveDeleteOptions,
+    FirestoreRecursiveDeleteProgress, FirestoreRecursiveDeleteResult,
+    FirestoreRecursiveDeleteSupport, FirestoreResult, FirestoreRetryPolicy, FirestoreTransaction,
+    FirestoreWritePrecondition,
 };
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct FirestoreDeleteInitialBuilder<'a, D>
@@ -24,6 +29,15 @@ where
     pub fn from(self, collection_id: &str) -> FirestoreDeleteDocIdBuilder<'a, D> {
         FirestoreDeleteDocIdBuilder::new(self.db, collection_id.to_string())
     }
+
+    /// Deletes documents together with all their subcollections.
+    #[inline]
+    pub fn recursive(self) -> FirestoreDeleteRecursiveInitialBuilder<'a, D>
+    where
+        D: FirestoreRecursiveDeleteSupport,
+    {
+        FirestoreDeleteRecursiveInitialBuilder::new(self.db)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -95,6 +109,8 @@ where
     document_id: String,
     parent: Option<String>,
     precondition: Option<FirestoreWritePrecondition>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestoreDeleteExecuteBuilder<'a, D>
@@ -115,6 +131,8 @@ where
             document_id,
             parent,
             precondition,
+            timeout: None,
+            retry_policy: None,
         }
     }
 
@@ -137,27 +155,46 @@ where
         }
     }
 
-    pub async fn execute(self) -> FirestoreResult<()> {
-        if let Some(parent) = self.parent {
-            self.db
-                .delete_by_id_at(
-                    parent.as_str(),
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.precondition,
-                )
-                .await
-        } else {
-            self.db
-                .delete_by_id(
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.precondition,
-                )
-                .await
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
         }
     }
 
+    pub async fn execute(self) -> FirestoreResult<()> {
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .delete_by_id_at(
+                        parent.as_str(),
+                        self.collection_id.as_str(),
+                        self.document_id,
+                        self.precondition,
+                    )
+                    .await
+            } else {
+                self.db
+                    .delete_by_id(
+                        self.collection_id.as_str(),
+                        self.document_id,
+                        self.precondition,
+                    )
+                    .await
+            }
+        })
+        .await
+    }
+
     #[inline]
     pub fn add_to_transaction<'t>(
         self,
@@ -203,3 +240,223 @@ where
         }
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct FirestoreDeleteRecursiveInitialBuilder<'a, D>
+where
+    D: FirestoreRecursiveDeleteSupport,
+{
+    db: &'a D,
+}
+
+impl<'a, D> FirestoreDeleteRecursiveInitialBuilder<'a, D>
+where
+    D: FirestoreRecursiveDeleteSupport,
+{
+    #[inline]
+    pub(crate) fn new(db: &'a D) -> Self {
+        Self { db }
+    }
+
+    #[inline]
+    pub fn from(self, collection_id: &str) -> FirestoreDeleteRecursiveDocIdBuilder<'a, D> {
+        FirestoreDeleteRecursiveDocIdBuilder::new(self.db, collection_id.to_string())
+    }
+
+    /// Deletes all the documents in the collection.
+    #[inline]
+    pub fn collection(self, collection_id: &str) -> FirestoreDeleteRecursiveExecuteBuilder<'a, D> {
+        FirestoreDeleteRecursiveExecuteBuilder::new(
+            self.db,
+            collection_id.to_string(),
+            None,
+            None,
+            None,
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreDeleteRecursiveDocIdBuilder<'a, D>
+where
+    D: FirestoreRecursiveDeleteSupport,
+{
+    db: &'a D,
+    collection_id: String,
+    parent: Option<String>,
+    precondition: Option<FirestoreWritePrecondition>,
+}
+
+impl<'a, D> FirestoreDeleteRecursiveDocIdBuilder<'a, D>
+where
+    D: FirestoreRecursiveDeleteSupport,
+{
+    #[inline]
+    pub(crate) fn new(db: &'a D, collection_id: String) -> Self {
+        Self {
+            db,
+            collection_id,
+            parent: None,
+            precondition: None,
+        }
+    }
+
+    #[inline]
+    pub fn parent<S>(self, parent: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self {
+            parent: Some(parent.as_ref().to_string()),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn precondition(self, precondition: FirestoreWritePrecondition) -> Self {
+        Self {
+            precondition: Some(precondition),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn document_id<S>(self, document_id: S) -> FirestoreDeleteRecursiveExecuteBuilder<'a, D>
+    where
+        S: AsRef<str> + Send,
+    {
+        FirestoreDeleteRecursiveExecuteBuilder::new(
+            self.db,
+            self.collection_id,
+            Some(document_id.as_ref().to_string()),
+            self.parent,
+            self.precondition,
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreDeleteRecursiveExecuteBuilder<'a, D>
+where
+    D: FirestoreRecursiveDeleteSupport,
+{
+    db: &'a D,
+    collection_id: String,
+    document_id: Option<String>,
+    parent: Option<String>,
+    precondition: Option<FirestoreWritePrecondition>,
+    options: FirestoreRecursiveDeleteOptions,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+}
+
+impl<'a, D> FirestoreDeleteRecursiveExecuteBuilder<'a, D>
+where
+    D: FirestoreRecursiveDeleteSupport,
+{
+    #[inline]
+    pub(crate) fn new(
+        db: &'a D,
+        collection_id: String,
+        document_id: Option<String>,
+        parent: Option<String>,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> Self {
+        Self {
+            db,
+            collection_id,
+            document_id,
+            parent,
+            precondition,
+            options: FirestoreRecursiveDeleteOptions::new(),
+            timeout: None,
+            retry_policy: None,
+        }
+    }
+
+    #[inline]
+    pub fn parent<S>(self, parent: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self {
+            parent: Some(parent.as_ref().to_string()),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn options(self, options: FirestoreRecursiveDeleteOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    #[inline]
+    pub fn on_progress<F>(self, on_progress: F) -> Self
+    where
+        F: Fn(&FirestoreRecursiveDeleteProgress) + Send + Sync + 'static,
+    {
+        Self {
+            options: self.options.with_on_progress(Arc::new(on_progress)),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    pub async fn execute(self) -> FirestoreResult<FirestoreRecursiveDeleteResult> {
+        with_request_options(self.timeout, self.retry_policy, async move {
+            match (self.parent, self.document_id) {
+                (Some(parent), Some(document_id)) => {
+                    self.db
+                        .delete_recursive_by_id_at(
+                            parent.as_str(),
+                            self.collection_id.as_str(),
+                            document_id,
+                            self.precondition,
+                            self.options,
+                        )
+                        .await
+                }
+                (None, Some(document_id)) => {
+                    self.db
+                        .delete_recursive_by_id(
+                            self.collection_id.as_str(),
+                            document_id,
+                            self.precondition,
+                            self.options,
+                        )
+                        .await
+                }
+                (Some(parent), None) => {
+                    self.db
+                        .delete_collection_at(
+                            parent.as_str(),
+                            self.collection_id.as_str(),
+                            self.options,
+                        )
+                        .await
+                }
+                (None, None) => {
+                    self.db
+                        .delete_collection(self.collection_id.as_str(), self.options)
+                        .await
+                }
+            }
+        })
+        .await
+    }
+}
@@ -1,6 +1,11 @@
-use crate::{FirestoreCreateSupport, FirestoreResult};
+use crate::db::{generate_document_id, with_request_options};
+use crate::{
+    FirestoreBatch, FirestoreBatchWriter, FirestoreCreateSupport, FirestoreResult,
+    FirestoreRetryPolicy, FirestoreTransaction,
+};
 use gcloud_sdk::google::firestore::v1::Document;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct FirestoreInsertInitialBuilder<'a, D>
@@ -51,13 +56,15 @@ where
         FirestoreInsertDocObjBuilder::new(
             self.db,
             self.collection_id,
-            Some(document_id.as_ref().to_string()),
+            document_id.as_ref().to_string(),
         )
     }
 
+    /// Generates the document ID on the client side the same way as Firestore does,
+    /// available with `document_id()` on the next builders.
     #[inline]
     pub fn generate_document_id(self) -> FirestoreInsertDocObjBuilder<'a, D> {
-        FirestoreInsertDocObjBuilder::new(self.db, self.collection_id, None)
+        FirestoreInsertDocObjBuilder::new(self.db, self.collection_id, generate_document_id())
     }
 }
 
@@ -68,7 +75,7 @@ where
 {
     db: &'a D,
     collection_id: String,
-    document_id: Option<String>,
+    document_id: String,
     parent: Option<String>,
     return_only_fields: Option<Vec<String>>,
 }
@@ -78,7 +85,7 @@ where
     D: FirestoreCreateSupport,
 {
     #[inline]
-    pub(crate) fn new(db: &'a D, collection_id: String, document_id: Option<String>) -> Self {
+    pub(crate) fn new(db: &'a D, collection_id: String, document_id: String) -> Self {
         Self {
             db,
             collection_id,
@@ -99,6 +106,11 @@ where
         }
     }
 
+    #[inline]
+    pub fn document_id(&self) -> &str {
+        &self.document_id
+    }
+
     #[inline]
     pub fn return_only_fields<I>(self, return_only_fields: I) -> Self
     where
@@ -152,10 +164,12 @@ where
 {
     db: &'a D,
     collection_id: String,
-    document_id: Option<String>,
+    document_id: String,
     parent: Option<String>,
     document: Document,
     return_only_fields: Option<Vec<String>>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestoreInsertDocExecuteBuilder<'a, D>
@@ -166,7 +180,7 @@ where
     pub(crate) fn new(
         db: &'a D,
         collection_id: String,
-        document_id: Option<String>,
+        document_id: String,
         parent: Option<String>,
         document: Document,
         return_only_fields: Option<Vec<String>>,
@@ -178,29 +192,92 @@ where
             parent,
             document,
             return_only_fields,
+            timeout: None,
+            retry_policy: None,
+        }
+    }
+
+    #[inline]
+    pub fn document_id(&self) -> &str {
+        &self.document_id
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
         }
     }
 
     pub async fn execute(self) -> FirestoreResult<Document> {
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .create_doc_at(
+                        parent.as_str(),
+                        self.collection_id.as_str(),
+                        Some(self.document_id),
+                        self.document,
+                        self.return_only_fields,
+                    )
+                    .await
+            } else {
+                self.db
+                    .create_doc(
+                        self.collection_id.as_str(),
+                        Some(self.document_id),
+                        self.document,
+                        self.return_only_fields,
+                    )
+                    .await
+            }
+        })
+        .await
+    }
+
+    #[inline]
+    pub fn add_to_transaction<'t>(
+        self,
+        transaction: &'a mut FirestoreTransaction<'t>,
+    ) -> FirestoreResult<&'a mut FirestoreTransaction<'t>> {
         if let Some(parent) = self.parent {
-            self.db
-                .create_doc_at(
-                    parent.as_str(),
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.document,
-                    self.return_only_fields,
-                )
-                .await
+            transaction.create_doc_at(
+                parent.as_str(),
+                self.collection_id.as_str(),
+                self.document_id,
+                self.document,
+            )
         } else {
-            self.db
-                .create_doc(
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.document,
-                    self.return_only_fields,
-                )
-                .await
+            transaction.create_doc(self.collection_id.as_str(), self.document_id, self.document)
+        }
+    }
+
+    #[inline]
+    pub fn add_to_batch<'t, W>(
+        self,
+        batch: &'a mut FirestoreBatch<'t, W>,
+    ) -> FirestoreResult<&'a mut FirestoreBatch<'t, W>>
+    where
+        W: FirestoreBatchWriter,
+    {
+        if let Some(parent) = self.parent {
+            batch.create_doc_at(
+                parent.as_str(),
+                self.collection_id.as_str(),
+                self.document_id,
+                self.document,
+            )
+        } else {
+            batch.create_doc(self.collection_id.as_str(), self.document_id, self.document)
         }
     }
 }
@@ -214,9 +291,11 @@ where
     db: &'a D,
     collection_id: String,
     parent: Option<String>,
-    document_id: Option<String>,
+    document_id: String,
     object: &'a T,
     return_only_fields: Option<Vec<String>>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D, T> FirestoreInsertObjExecuteBuilder<'a, D, T>
@@ -229,7 +308,7 @@ where
         db: &'a D,
         collection_id: String,
         parent: Option<String>,
-        document_id: Option<String>,
+        document_id: String,
         object: &'a T,
         return_only_fields: Option<Vec<String>>,
     ) -> Self {
@@ -240,32 +319,132 @@ where
             document_id,
             object,
             return_only_fields,
+            timeout: None,
+            retry_policy: None,
+        }
+    }
+
+    #[inline]
+    pub fn document_id(&self) -> &str {
+        &self.document_id
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
         }
     }
 
     pub async fn execute<O>(self) -> FirestoreResult<O>
     where
         for<'de> O: Deserialize<'de>,
+    {
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .create_obj_at(
+                        parent.as_str(),
+                        self.collection_id.as_str(),
+                        Some(self.document_id),
+                        self.object,
+                        self.return_only_fields,
+                    )
+                    .await
+            } else {
+                self.db
+                    .create_obj(
+                        self.collection_id.as_str(),
+                        Some(self.document_id),
+                        self.object,
+                        self.return_only_fields,
+                    )
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// Adds the insert to the transaction, the write fails on commit if the document already exists.
+    #[inline]
+    pub fn add_to_transaction<'t>(
+        self,
+        transaction: &'a mut FirestoreTransaction<'t>,
+    ) -> FirestoreResult<&'a mut FirestoreTransaction<'t>> {
+        if let Some(parent) = self.parent {
+            transaction.create_object_at(
+                parent.as_str(),
+                self.collection_id.as_str(),
+                self.document_id,
+                self.object,
+                vec![],
+            )
+        } else {
+            transaction.create_object(
+                self.collection_id.as_str(),
+                self.document_id,
+                self.object,
+                vec![],
+            )
+        }
+    }
+
+    #[inline]
+    pub fn add_to_batch<'t, W>(
+        self,
+        batch: &'a mut FirestoreBatch<'t, W>,
+    ) -> FirestoreResult<&'a mut FirestoreBatch<'t, W>>
+    where
+        W: FirestoreBatchWriter,
     {
         if let Some(parent) = self.parent {
-            self.db
-                .create_obj_at(
-                    parent.as_str(),
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.object,
-                    self.return_only_fields,
-                )
-                .await
+            batch.create_object_at(
+                parent.as_str(),
+                self.collection_id.as_str(),
+                self.document_id,
+                self.object,
+                vec![],
+            )
         } else {
-            self.db
-                .create_obj(
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.object,
-                    self.return_only_fields,
-                )
-                .await
+            batch.create_object(
+                self.collection_id.as_str(),
+                self.document_id,
+                self.object,
+                vec![],
+            )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::fluent_api::tests::*;
+    use crate::fluent_api::FirestoreExprBuilder;
+    use gcloud_sdk::google::firestore::v1::Document;
+
+    #[test]
+    fn insert_builder_generates_document_id() {
+        let insert = FirestoreExprBuilder::new(&mockdb::MockDatabase {})
+            .insert()
+            .into("test")
+            .generate_document_id();
+
+        let generated_id = insert.document_id().to_string();
+        assert_eq!(generated_id.len(), 20);
+        assert!(generated_id.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        assert_eq!(
+            insert.document(Document::default()).document_id(),
+            generated_id
+        );
+    }
+}
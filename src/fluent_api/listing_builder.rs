@@ -1,11 +1,14 @@
+use crate::db::with_request_options;
 use crate::{
     FirestoreListCollectionIdsParams, FirestoreListCollectionIdsResult, FirestoreListDocParams,
     FirestoreListDocResult, FirestoreListingSupport, FirestoreQueryOrder, FirestoreResult,
+    FirestoreRetryPolicy,
 };
 use futures::stream::BoxStream;
 use gcloud_sdk::google::firestore::v1::Document;
 use serde::Deserialize;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct FirestoreListingInitialBuilder<'a, D>
@@ -65,6 +68,8 @@ where
 {
     db: &'a D,
     params: FirestoreListDocParams,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestoreListingDocBuilder<'a, D>
@@ -73,7 +78,12 @@ where
 {
     #[inline]
     pub(crate) fn new(db: &'a D, params: FirestoreListDocParams) -> Self {
-        Self { db, params }
+        Self {
+            db,
+            params,
+            timeout: None,
+            retry_policy: None,
+        }
     }
 
     #[inline]
@@ -82,7 +92,7 @@ where
         T: Send,
         for<'de> T: Deserialize<'de>,
     {
-        FirestoreListingObjBuilder::new(self.db, self.params)
+        FirestoreListingObjBuilder::new(self.db, self.params, self.timeout, self.retry_policy)
     }
 
     #[inline]
@@ -118,18 +128,49 @@ where
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     pub async fn get_page(self) -> FirestoreResult<FirestoreListDocResult> {
-        self.db.list_doc(self.params).await
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.list_doc(self.params),
+        )
+        .await
     }
 
     pub async fn stream_all(self) -> FirestoreResult<BoxStream<'a, Document>> {
-        self.db.stream_list_doc(self.params).await
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_list_doc(self.params),
+        )
+        .await
     }
 
     pub async fn stream_all_with_errors(
         self,
     ) -> FirestoreResult<BoxStream<'a, FirestoreResult<Document>>> {
-        self.db.stream_list_doc_with_errors(self.params).await
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_list_doc_with_errors(self.params),
+        )
+        .await
     }
 }
 
@@ -142,6 +183,8 @@ where
 {
     db: &'a D,
     params: FirestoreListDocParams,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
     _pd: PhantomData<T>,
 }
 
@@ -154,22 +197,52 @@ where
     pub(crate) fn new(
         db: &'a D,
         params: FirestoreListDocParams,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
     ) -> FirestoreListingObjBuilder<'a, D, T> {
         Self {
             db,
             params,
+            timeout,
+            retry_policy,
             _pd: PhantomData,
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     pub async fn stream_all(self) -> FirestoreResult<BoxStream<'a, T>> {
-        self.db.stream_list_obj(self.params).await
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_list_obj(self.params),
+        )
+        .await
     }
 
     pub async fn stream_all_with_errors(
         self,
     ) -> FirestoreResult<BoxStream<'a, FirestoreResult<T>>> {
-        self.db.stream_list_obj_with_errors(self.params).await
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_list_obj_with_errors(self.params),
+        )
+        .await
     }
 }
 
@@ -180,6 +253,8 @@ where
 {
     db: &'a D,
     params: FirestoreListCollectionIdsParams,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestoreListCollectionIdsBuilder<'a, D>
@@ -191,6 +266,8 @@ where
         Self {
             db,
             params: FirestoreListCollectionIdsParams::new(),
+            timeout: None,
+            retry_policy: None,
         }
     }
 
@@ -213,19 +290,48 @@ where
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     pub async fn get_page(self) -> FirestoreResult<FirestoreListCollectionIdsResult> {
-        self.db.list_collection_ids(self.params).await
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.list_collection_ids(self.params),
+        )
+        .await
     }
 
     pub async fn stream_all(self) -> FirestoreResult<BoxStream<'a, String>> {
-        self.db.stream_list_collection_ids(self.params).await
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_list_collection_ids(self.params),
+        )
+        .await
     }
 
     pub async fn stream_all_with_errors(
         self,
     ) -> FirestoreResult<BoxStream<'a, FirestoreResult<String>>> {
-        self.db
-            .stream_list_collection_ids_with_errors(self.params)
-            .await
+        with_request_options(self.timeout, self.retry_policy, async move {
+            self.db
+                .stream_list_collection_ids_with_errors(self.params)
+                .await
+        })
+        .await
     }
 }
@@ -16,7 +16,7 @@ use crate::listing_builder::FirestoreListingInitialBuilder;
 use crate::update_builder::FirestoreUpdateInitialBuilder;
 use crate::{
     FirestoreAggregatedQuerySupport, FirestoreCreateSupport, FirestoreDb, FirestoreDeleteSupport,
-    FirestoreGetByIdSupport, FirestoreListenSupport, FirestoreListingSupport,
+    FirestoreGetByIdSupport, FirestoreInMemoryDb, FirestoreListenSupport, FirestoreListingSupport,
     FirestoreQuerySupport, FirestoreUpdateSupport,
 };
 
@@ -77,6 +77,13 @@ impl FirestoreDb {
     }
 }
 
+impl FirestoreInMemoryDb {
+    #[inline]
+    pub fn fluent(&self) -> FirestoreExprBuilder<'_, FirestoreInMemoryDb> {
+        FirestoreExprBuilder::new(self)
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     pub mod mockdb;
@@ -1,20 +1,27 @@
-use crate::errors::FirestoreError;
+use crate::db::with_request_options;
+use crate::errors::{AnyBoxedErrResult, FirestoreError};
+use crate::firestore_serde::firestore_document_to_serializable;
 use crate::select_aggregation_builder::FirestoreAggregationBuilder;
 use crate::select_filter_builder::FirestoreQueryFilterBuilder;
 use crate::{
     FirestoreAggregatedQueryParams, FirestoreAggregatedQuerySupport, FirestoreAggregation,
-    FirestoreCollectionDocuments, FirestoreGetByIdSupport, FirestoreListenSupport,
-    FirestoreListener, FirestoreListenerParams, FirestoreListenerTarget,
-    FirestoreListenerTargetParams, FirestorePartition, FirestorePartitionQueryParams,
-    FirestoreQueryCollection, FirestoreQueryCursor, FirestoreQueryFilter, FirestoreQueryOrder,
-    FirestoreQueryParams, FirestoreQuerySupport, FirestoreResult, FirestoreResumeStateStorage,
-    FirestoreTargetType,
+    FirestoreCollectionDocuments, FirestoreDocument, FirestoreExplainOptions,
+    FirestoreExplainResult, FirestoreFindNearestDistanceMeasure, FirestoreFindNearestOptions,
+    FirestoreGetByIdSupport, FirestoreListenSupport, FirestoreListener, FirestoreListenerEvent,
+    FirestoreListenerParams, FirestoreListenerTarget, FirestoreListenerTargetParams,
+    FirestoreListenerTargetsSupport, FirestorePage, FirestorePageToken, FirestorePartition,
+    FirestorePartitionQueryParams, FirestoreQueryCollection, FirestoreQueryCursor,
+    FirestoreQueryFilter, FirestoreQueryOrder, FirestoreQueryPaginator, FirestoreQueryParams,
+    FirestoreQuerySupport, FirestoreResult, FirestoreResumeStateStorage, FirestoreRetryPolicy,
+    FirestoreTargetType, FirestoreVector,
 };
 use futures::stream::BoxStream;
 use gcloud_sdk::google::firestore::v1::Document;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::future::Future;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct FirestoreSelectInitialBuilder<'a, D>
@@ -94,6 +101,8 @@ where
 {
     db: &'a D,
     params: FirestoreQueryParams,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestoreSelectDocBuilder<'a, D>
@@ -108,7 +117,12 @@ where
 {
     #[inline]
     pub(crate) fn new(db: &'a D, params: FirestoreQueryParams) -> Self {
-        Self { db, params }
+        Self {
+            db,
+            params,
+            timeout: None,
+            retry_policy: None,
+        }
     }
 
     #[inline]
@@ -168,6 +182,57 @@ where
         }
     }
 
+    /// Starts the results right after the document using its values of the ordered fields.
+    /// The implicit ordering by the inequality filter fields and `__name__` is added to the query,
+    /// so `order_by` and `filter` should be specified before. Fails if an ordered field is missing in the document.
+    #[inline]
+    pub fn start_after_doc(self, doc: &FirestoreDocument) -> FirestoreResult<Self> {
+        let order_by = self.params.order_by_with_implicit_fields();
+        let cursor = FirestoreQueryCursor::after_document(doc, &order_by)?;
+        Ok(Self {
+            params: self.params.with_start_at(cursor).with_order_by(order_by),
+            ..self
+        })
+    }
+
+    /// Ends the results right before the document using its values of the ordered fields.
+    /// The implicit ordering by the inequality filter fields and `__name__` is added to the query,
+    /// so `order_by` and `filter` should be specified before. Fails if an ordered field is missing in the document.
+    #[inline]
+    pub fn end_before_doc(self, doc: &FirestoreDocument) -> FirestoreResult<Self> {
+        let order_by = self.params.order_by_with_implicit_fields();
+        let cursor = FirestoreQueryCursor::before_document(doc, &order_by)?;
+        Ok(Self {
+            params: self.params.with_end_at(cursor).with_order_by(order_by),
+            ..self
+        })
+    }
+
+    #[inline]
+    pub fn find_nearest<F, V>(
+        self,
+        field_name: F,
+        vector: V,
+        distance_measure: FirestoreFindNearestDistanceMeasure,
+        neighbors_limit: u32,
+    ) -> Self
+    where
+        F: AsRef<str>,
+        V: Into<FirestoreVector>,
+    {
+        Self {
+            params: self
+                .params
+                .with_find_nearest(FirestoreFindNearestOptions::new(
+                    field_name.as_ref().to_string(),
+                    vector.into(),
+                    distance_measure,
+                    neighbors_limit,
+                )),
+            ..self
+        }
+    }
+
     #[inline]
     pub fn all_descendants(self) -> Self {
         Self {
@@ -176,6 +241,22 @@ where
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     #[inline]
     pub fn filter<FN>(self, filter: FN) -> Self
     where
@@ -195,12 +276,29 @@ where
         T: Send,
         for<'de> T: Deserialize<'de>,
     {
-        FirestoreSelectObjBuilder::new(self.db, self.params)
+        FirestoreSelectObjBuilder::new(self.db, self.params, self.timeout, self.retry_policy)
     }
 
     #[inline]
     pub fn partition_query(self) -> FirestorePartitionQueryDocBuilder<'a, D> {
-        FirestorePartitionQueryDocBuilder::new(self.db, self.params.with_all_descendants(true))
+        FirestorePartitionQueryDocBuilder::new(
+            self.db,
+            self.params.with_all_descendants(true),
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
+    /// Queries the results page by page using continuation tokens.
+    #[inline]
+    pub fn paginate(self, page_size: u32) -> FirestorePaginatedQueryDocBuilder<'a, D> {
+        FirestorePaginatedQueryDocBuilder::new(
+            self.db,
+            self.params,
+            page_size,
+            self.timeout,
+            self.retry_policy,
+        )
     }
 
     #[inline]
@@ -222,21 +320,52 @@ where
                 self.params,
                 aggregation(FirestoreAggregationBuilder::new()),
             ),
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
+    #[inline]
+    pub fn explain(self, analyze: bool) -> FirestoreExplainQueryDocBuilder<'a, D> {
+        FirestoreExplainQueryDocBuilder::new(
+            self.db,
+            self.params,
+            FirestoreExplainOptions::new().with_analyze(analyze),
+            self.timeout,
+            self.retry_policy,
         )
     }
 
     pub async fn query(self) -> FirestoreResult<Vec<Document>> {
-        self.db.query_doc(self.params).await
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.query_doc(self.params),
+        )
+        .await
     }
 
     pub async fn stream_query(self) -> FirestoreResult<BoxStream<'a, Document>> {
-        self.db.stream_query_doc(self.params).await
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_query_doc(self.params),
+        )
+        .await
     }
 
     pub async fn stream_query_with_errors(
         self,
     ) -> FirestoreResult<BoxStream<'a, FirestoreResult<Document>>> {
-        self.db.stream_query_doc_with_errors(self.params).await
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_query_doc_with_errors(self.params),
+        )
+        .await
     }
 }
 
@@ -249,6 +378,8 @@ where
 {
     db: &'a D,
     params: FirestoreQueryParams,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
     _pd: PhantomData<T>,
 }
 
@@ -261,20 +392,66 @@ where
     pub(crate) fn new(
         db: &'a D,
         params: FirestoreQueryParams,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
     ) -> FirestoreSelectObjBuilder<'a, D, T> {
         Self {
             db,
             params,
+            timeout,
+            retry_policy,
             _pd: PhantomData,
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn explain(self, analyze: bool) -> FirestoreExplainQueryObjBuilder<'a, D, T>
+    where
+        D: Sync,
+    {
+        FirestoreExplainQueryObjBuilder::new(
+            self.db,
+            self.params,
+            FirestoreExplainOptions::new().with_analyze(analyze),
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
     pub async fn query(self) -> FirestoreResult<Vec<T>> {
-        self.db.query_obj(self.params).await
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.query_obj(self.params),
+        )
+        .await
     }
 
     pub async fn stream_query(self) -> FirestoreResult<BoxStream<'a, T>> {
-        self.db.stream_query_obj(self.params).await
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_query_obj(self.params),
+        )
+        .await
     }
 
     pub async fn stream_query_with_errors(
@@ -283,14 +460,39 @@ where
     where
         T: 'a,
     {
-        self.db.stream_query_obj_with_errors(self.params).await
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_query_obj_with_errors(self.params),
+        )
+        .await
     }
 
     pub fn partition_query(self) -> FirestorePartitionQueryObjBuilder<'a, D, T>
     where
         T: 'a,
     {
-        FirestorePartitionQueryObjBuilder::new(self.db, self.params.with_all_descendants(true))
+        FirestorePartitionQueryObjBuilder::new(
+            self.db,
+            self.params.with_all_descendants(true),
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
+    /// Queries the results page by page using continuation tokens.
+    pub fn paginate(self, page_size: u32) -> FirestorePaginatedQueryObjBuilder<'a, D, T>
+    where
+        D: FirestoreAggregatedQuerySupport,
+    {
+        FirestorePaginatedQueryObjBuilder::new(
+            self.db,
+            self.params,
+            page_size,
+            self.timeout,
+            self.retry_policy,
+        )
     }
 }
 
@@ -303,6 +505,8 @@ where
     collection: String,
     parent: Option<String>,
     return_only_fields: Option<Vec<String>>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestoreSelectByIdBuilder<'a, D>
@@ -319,6 +523,8 @@ where
             collection,
             parent: None,
             return_only_fields,
+            timeout: None,
+            retry_policy: None,
         }
     }
 
@@ -333,6 +539,22 @@ where
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     #[inline]
     pub fn obj<T>(self) -> FirestoreSelectObjByIdBuilder<'a, D, T>
     where
@@ -344,6 +566,8 @@ where
             self.collection,
             self.parent,
             self.return_only_fields,
+            self.timeout,
+            self.retry_policy,
         )
     }
 
@@ -351,40 +575,43 @@ where
     where
         S: AsRef<str> + Send,
     {
-        if let Some(parent) = self.parent {
-            match self
-                .db
-                .get_doc_at::<S>(
-                    parent.as_str(),
-                    self.collection.as_str(),
-                    document_id,
-                    self.return_only_fields,
-                )
-                .await
-            {
-                Ok(doc) => Ok(Some(doc)),
-                Err(err) => match err {
-                    FirestoreError::DataNotFoundError(_) => Ok(None),
-                    _ => Err(err),
-                },
-            }
-        } else {
-            match self
-                .db
-                .get_doc::<S>(
-                    self.collection.as_str(),
-                    document_id,
-                    self.return_only_fields,
-                )
-                .await
-            {
-                Ok(doc) => Ok(Some(doc)),
-                Err(err) => match err {
-                    FirestoreError::DataNotFoundError(_) => Ok(None),
-                    _ => Err(err),
-                },
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                match self
+                    .db
+                    .get_doc_at::<S>(
+                        parent.as_str(),
+                        self.collection.as_str(),
+                        document_id,
+                        self.return_only_fields,
+                    )
+                    .await
+                {
+                    Ok(doc) => Ok(Some(doc)),
+                    Err(err) => match err {
+                        FirestoreError::DataNotFoundError(_) => Ok(None),
+                        _ => Err(err),
+                    },
+                }
+            } else {
+                match self
+                    .db
+                    .get_doc::<S>(
+                        self.collection.as_str(),
+                        document_id,
+                        self.return_only_fields,
+                    )
+                    .await
+                {
+                    Ok(doc) => Ok(Some(doc)),
+                    Err(err) => match err {
+                        FirestoreError::DataNotFoundError(_) => Ok(None),
+                        _ => Err(err),
+                    },
+                }
             }
-        }
+        })
+        .await
     }
 
     pub async fn batch<S, I>(
@@ -395,24 +622,27 @@ where
         S: AsRef<str> + Send,
         I: IntoIterator<Item = S> + Send,
     {
-        if let Some(parent) = self.parent {
-            self.db
-                .batch_stream_get_docs_at::<S, I>(
-                    parent.as_str(),
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        } else {
-            self.db
-                .batch_stream_get_docs::<S, I>(
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        }
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .batch_stream_get_docs_at::<S, I>(
+                        parent.as_str(),
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            } else {
+                self.db
+                    .batch_stream_get_docs::<S, I>(
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn batch_with_errors<S, I>(
@@ -423,24 +653,27 @@ where
         S: AsRef<str> + Send,
         I: IntoIterator<Item = S> + Send,
     {
-        if let Some(parent) = self.parent {
-            self.db
-                .batch_stream_get_docs_at_with_errors::<S, I>(
-                    parent.as_str(),
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        } else {
-            self.db
-                .batch_stream_get_docs_with_errors::<S, I>(
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        }
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .batch_stream_get_docs_at_with_errors::<S, I>(
+                        parent.as_str(),
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            } else {
+                self.db
+                    .batch_stream_get_docs_with_errors::<S, I>(
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            }
+        })
+        .await
     }
 
     pub fn batch_listen<S, I>(
@@ -478,6 +711,8 @@ where
     collection: String,
     parent: Option<String>,
     return_only_fields: Option<Vec<String>>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
     _pd: PhantomData<T>,
 }
 
@@ -492,54 +727,77 @@ where
         collection: String,
         parent: Option<String>,
         return_only_fields: Option<Vec<String>>,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
     ) -> FirestoreSelectObjByIdBuilder<'a, D, T> {
         Self {
             db,
             collection,
             parent,
             return_only_fields,
+            timeout,
+            retry_policy,
             _pd: PhantomData,
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     pub async fn one<S>(self, document_id: S) -> FirestoreResult<Option<T>>
     where
         S: AsRef<str> + Send,
     {
-        if let Some(parent) = self.parent {
-            match self
-                .db
-                .get_obj_at_return_fields::<T, S>(
-                    parent.as_str(),
-                    self.collection.as_str(),
-                    document_id,
-                    self.return_only_fields,
-                )
-                .await
-            {
-                Ok(doc) => Ok(Some(doc)),
-                Err(err) => match err {
-                    FirestoreError::DataNotFoundError(_) => Ok(None),
-                    _ => Err(err),
-                },
-            }
-        } else {
-            match self
-                .db
-                .get_obj_return_fields::<T, S>(
-                    self.collection.as_str(),
-                    document_id,
-                    self.return_only_fields,
-                )
-                .await
-            {
-                Ok(doc) => Ok(Some(doc)),
-                Err(err) => match err {
-                    FirestoreError::DataNotFoundError(_) => Ok(None),
-                    _ => Err(err),
-                },
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                match self
+                    .db
+                    .get_obj_at_return_fields::<T, S>(
+                        parent.as_str(),
+                        self.collection.as_str(),
+                        document_id,
+                        self.return_only_fields,
+                    )
+                    .await
+                {
+                    Ok(doc) => Ok(Some(doc)),
+                    Err(err) => match err {
+                        FirestoreError::DataNotFoundError(_) => Ok(None),
+                        _ => Err(err),
+                    },
+                }
+            } else {
+                match self
+                    .db
+                    .get_obj_return_fields::<T, S>(
+                        self.collection.as_str(),
+                        document_id,
+                        self.return_only_fields,
+                    )
+                    .await
+                {
+                    Ok(doc) => Ok(Some(doc)),
+                    Err(err) => match err {
+                        FirestoreError::DataNotFoundError(_) => Ok(None),
+                        _ => Err(err),
+                    },
+                }
             }
-        }
+        })
+        .await
     }
 
     pub async fn batch<S, I>(
@@ -551,24 +809,27 @@ where
         I: IntoIterator<Item = S> + Send,
         T: Send + 'a,
     {
-        if let Some(parent) = self.parent {
-            self.db
-                .batch_stream_get_objects_at::<T, S, I>(
-                    parent.as_str(),
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        } else {
-            self.db
-                .batch_stream_get_objects::<T, S, I>(
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        }
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .batch_stream_get_objects_at::<T, S, I>(
+                        parent.as_str(),
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            } else {
+                self.db
+                    .batch_stream_get_objects::<T, S, I>(
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn batch_with_errors<S, I>(
@@ -580,24 +841,27 @@ where
         I: IntoIterator<Item = S> + Send,
         T: Send + 'a,
     {
-        if let Some(parent) = self.parent {
-            self.db
-                .batch_stream_get_objects_at_with_errors::<T, S, I>(
-                    parent.as_str(),
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        } else {
-            self.db
-                .batch_stream_get_objects_with_errors::<T, S, I>(
-                    self.collection.as_str(),
-                    document_ids,
-                    self.return_only_fields,
-                )
-                .await
-        }
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .batch_stream_get_objects_at_with_errors::<T, S, I>(
+                        parent.as_str(),
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            } else {
+                self.db
+                    .batch_stream_get_objects_with_errors::<T, S, I>(
+                        self.collection.as_str(),
+                        document_ids,
+                        self.return_only_fields,
+                    )
+                    .await
+            }
+        })
+        .await
     }
 }
 
@@ -611,6 +875,8 @@ where
     parallelism: usize,
     partition_count: u32,
     page_size: u32,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestorePartitionQueryDocBuilder<'a, D>
@@ -618,13 +884,20 @@ where
     D: FirestoreQuerySupport,
 {
     #[inline]
-    pub(crate) fn new(db: &'a D, params: FirestoreQueryParams) -> Self {
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreQueryParams,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
         Self {
             db,
             params,
             parallelism: 2,
             partition_count: 10,
             page_size: 1000,
+            timeout,
+            retry_policy,
         }
     }
 
@@ -652,19 +925,39 @@ where
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     pub async fn stream_partitions_with_errors(
         self,
     ) -> FirestoreResult<BoxStream<'a, FirestoreResult<(FirestorePartition, Document)>>> {
-        self.db
-            .stream_partition_query_doc_with_errors(
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_partition_query_doc_with_errors(
                 self.parallelism,
                 FirestorePartitionQueryParams::new(
                     self.params,
                     self.partition_count,
                     self.page_size,
                 ),
-            )
-            .await
+            ),
+        )
+        .await
     }
 }
 
@@ -680,6 +973,8 @@ where
     parallelism: usize,
     partition_count: u32,
     page_size: u32,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
     _ph: PhantomData<T>,
 }
 
@@ -690,13 +985,20 @@ where
     for<'de> T: Deserialize<'de>,
 {
     #[inline]
-    pub(crate) fn new(db: &'a D, params: FirestoreQueryParams) -> Self {
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreQueryParams,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
         Self {
             db,
             params,
             parallelism: 2,
             partition_count: 10,
             page_size: 1000,
+            timeout,
+            retry_policy,
             _ph: PhantomData,
         }
     }
@@ -725,120 +1027,498 @@ where
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
     pub async fn stream_partitions_with_errors(
         self,
     ) -> FirestoreResult<BoxStream<'a, FirestoreResult<(FirestorePartition, T)>>> {
-        self.db
-            .stream_partition_query_obj_with_errors(
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_partition_query_obj_with_errors(
                 self.parallelism,
                 FirestorePartitionQueryParams::new(
                     self.params,
                     self.partition_count,
                     self.page_size,
                 ),
-            )
-            .await
+            ),
+        )
+        .await
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct FirestoreDocChangesListenerInitBuilder<'a, D>
+pub struct FirestorePaginatedQueryDocBuilder<'a, D>
 where
-    D: FirestoreListenSupport + Clone,
+    D: FirestoreQuerySupport + FirestoreAggregatedQuerySupport,
 {
-    _db: &'a D,
-    listener_params: FirestoreListenerParams,
-    target_type: FirestoreTargetType,
-    labels: HashMap<String, String>,
+    db: &'a D,
+    params: FirestoreQueryParams,
+    page_size: u32,
+    page_token: Option<FirestorePageToken>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
-impl<'a, D> FirestoreDocChangesListenerInitBuilder<'a, D>
+impl<'a, D> FirestorePaginatedQueryDocBuilder<'a, D>
 where
-    D: FirestoreListenSupport + Clone + Send + Sync + 'static,
+    D: FirestoreQuerySupport + FirestoreAggregatedQuerySupport,
 {
     #[inline]
-    pub(crate) fn new(db: &'a D, target_type: FirestoreTargetType) -> Self {
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreQueryParams,
+        page_size: u32,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
         Self {
-            _db: db,
-            listener_params: FirestoreListenerParams::new(),
-            target_type,
-            labels: HashMap::new(),
+            db,
+            params,
+            page_size,
+            page_token: None,
+            timeout,
+            retry_policy,
         }
     }
 
+    /// Continues from the page the token was returned with.
     #[inline]
-    pub fn labels(self, labels: HashMap<String, String>) -> Self {
-        Self { labels, ..self }
+    pub fn page_token(self, page_token: FirestorePageToken) -> Self {
+        Self {
+            page_token: Some(page_token),
+            ..self
+        }
     }
 
     #[inline]
-    pub fn retry_delay(self, delay: std::time::Duration) -> Self {
+    pub fn opt_page_token(self, page_token: Option<FirestorePageToken>) -> Self {
+        Self { page_token, ..self }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
         Self {
-            listener_params: self.listener_params.with_retry_delay(delay),
+            timeout: Some(timeout),
             ..self
         }
     }
 
     #[inline]
-    pub fn add_target<S>(
-        self,
-        target: FirestoreListenerTarget,
-        listener: &mut FirestoreListener<D, S>,
-    ) -> FirestoreResult<()>
-    where
-        S: FirestoreResumeStateStorage + Send + Sync + Clone + 'static,
-    {
-        listener.add_target(FirestoreListenerTargetParams::new(
-            target,
-            self.target_type,
-            self.labels,
-        ))?;
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
 
-        Ok(())
+    pub async fn get_page(self) -> FirestoreResult<FirestorePage<Document>> {
+        let paginator = FirestoreQueryPaginator::new(self.params, self.page_size)?;
+        let cursor = self
+            .page_token
+            .as_ref()
+            .map(|page_token| paginator.page_cursor(page_token))
+            .transpose()?;
+
+        // The rest of the query limit is counted on the server, tokens aren't trusted with it
+        let returned = match cursor
+            .as_ref()
+            .and_then(|cursor| paginator.returned_count_params(cursor))
+        {
+            Some(count_params) => {
+                let count_docs = with_request_options(
+                    self.timeout,
+                    self.retry_policy.clone(),
+                    self.db.aggregated_query_doc(count_params),
+                )
+                .await?;
+                paginator.returned_count_from_docs(&count_docs)?
+            }
+            None => 0,
+        };
+
+        match paginator.page_params(cursor, returned) {
+            Some(page_params) => {
+                page_params.validate()?;
+                let docs = with_request_options(
+                    self.timeout,
+                    self.retry_policy,
+                    self.db.query_doc(page_params),
+                )
+                .await?;
+                paginator.page_from_docs(docs)
+            }
+            None => Ok(FirestorePage {
+                items: vec![],
+                next_page_token: None,
+            }),
+        }
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct FirestoreAggregatedQueryDocBuilder<'a, D>
+pub struct FirestorePaginatedQueryObjBuilder<'a, D, T>
 where
-    D: FirestoreAggregatedQuerySupport,
+    D: FirestoreQuerySupport + FirestoreAggregatedQuerySupport,
+    T: Send,
+    for<'de> T: Deserialize<'de>,
 {
     db: &'a D,
-    params: FirestoreAggregatedQueryParams,
+    params: FirestoreQueryParams,
+    page_size: u32,
+    page_token: Option<FirestorePageToken>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+    _pd: PhantomData<T>,
 }
 
-impl<'a, D> FirestoreAggregatedQueryDocBuilder<'a, D>
+impl<'a, D, T> FirestorePaginatedQueryObjBuilder<'a, D, T>
 where
-    D: FirestoreAggregatedQuerySupport,
+    D: FirestoreQuerySupport + FirestoreAggregatedQuerySupport,
+    T: Send,
+    for<'de> T: Deserialize<'de>,
 {
     #[inline]
-    pub(crate) fn new(db: &'a D, params: FirestoreAggregatedQueryParams) -> Self {
-        Self { db, params }
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreQueryParams,
+        page_size: u32,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
+        Self {
+            db,
+            params,
+            page_size,
+            page_token: None,
+            timeout,
+            retry_policy,
+            _pd: PhantomData,
+        }
     }
 
+    /// Continues from the page the token was returned with.
     #[inline]
-    pub fn obj<T>(self) -> FirestoreAggregatedQueryObjBuilder<'a, D, T>
-    where
-        T: Send,
-        for<'de> T: Deserialize<'de>,
-    {
-        FirestoreAggregatedQueryObjBuilder::new(self.db, self.params)
-    }
-
-    pub async fn query(self) -> FirestoreResult<Vec<Document>> {
-        self.db.aggregated_query_doc(self.params).await
+    pub fn page_token(self, page_token: FirestorePageToken) -> Self {
+        Self {
+            page_token: Some(page_token),
+            ..self
+        }
     }
 
-    pub async fn stream_query<'b>(self) -> FirestoreResult<BoxStream<'b, Document>> {
-        self.db.stream_aggregated_query_doc(self.params).await
+    #[inline]
+    pub fn opt_page_token(self, page_token: Option<FirestorePageToken>) -> Self {
+        Self { page_token, ..self }
     }
 
-    pub async fn stream_query_with_errors<'b>(
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    pub async fn get_page(self) -> FirestoreResult<FirestorePage<T>> {
+        let page = FirestorePaginatedQueryDocBuilder::new(
+            self.db,
+            self.params,
+            self.page_size,
+            self.timeout,
+            self.retry_policy,
+        )
+        .opt_page_token(self.page_token)
+        .get_page()
+        .await?;
+
+        Ok(FirestorePage {
+            items: page
+                .items
+                .iter()
+                .map(firestore_document_to_serializable)
+                .collect::<FirestoreResult<Vec<T>>>()?,
+            next_page_token: page.next_page_token,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreDocChangesListenerInitBuilder<'a, D>
+where
+    D: FirestoreListenSupport + Clone,
+{
+    db: &'a D,
+    listener_params: FirestoreListenerParams,
+    target_type: FirestoreTargetType,
+    labels: HashMap<String, String>,
+}
+
+impl<'a, D> FirestoreDocChangesListenerInitBuilder<'a, D>
+where
+    D: FirestoreListenSupport + Clone + Send + Sync + 'static,
+{
+    #[inline]
+    pub(crate) fn new(db: &'a D, target_type: FirestoreTargetType) -> Self {
+        Self {
+            db,
+            listener_params: FirestoreListenerParams::new(),
+            target_type,
+            labels: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn labels(self, labels: HashMap<String, String>) -> Self {
+        Self { labels, ..self }
+    }
+
+    #[inline]
+    pub fn retry_delay(self, delay: std::time::Duration) -> Self {
+        Self {
+            listener_params: self.listener_params.with_retry_delay(delay),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn obj<T>(self) -> FirestoreDocChangesListenerObjInitBuilder<'a, D, T>
+    where
+        T: Send + 'static,
+        for<'de> T: Deserialize<'de>,
+    {
+        FirestoreDocChangesListenerObjInitBuilder::new(self)
+    }
+
+    #[inline]
+    pub fn add_target<L>(
+        self,
+        target: FirestoreListenerTarget,
+        listener: &mut L,
+    ) -> FirestoreResult<()>
+    where
+        L: FirestoreListenerTargetsSupport,
+    {
+        listener.add_target(FirestoreListenerTargetParams::new(
+            target,
+            self.target_type,
+            self.labels,
+        ))?;
+
+        Ok(())
+    }
+
+    pub async fn start<S, FN, F>(
+        self,
+        target: FirestoreListenerTarget,
+        storage: S,
+        cb: FN,
+    ) -> FirestoreResult<FirestoreListener<D, S>>
+    where
+        S: FirestoreResumeStateStorage + Send + Sync + Clone + 'static,
+        FN: Fn(FirestoreListenerEvent<Document>) -> F + Send + Sync + 'static,
+        F: Future<Output = AnyBoxedErrResult<()>> + Send + 'static,
+    {
+        let mut listener =
+            FirestoreListener::new(self.db.clone(), storage, self.listener_params.clone()).await?;
+        self.add_target(target, &mut listener)?;
+        listener.start_events(cb).await?;
+        Ok(listener)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreDocChangesListenerObjInitBuilder<'a, D, T>
+where
+    D: FirestoreListenSupport + Clone,
+    T: Send + 'static,
+    for<'de> T: Deserialize<'de>,
+{
+    init_builder: FirestoreDocChangesListenerInitBuilder<'a, D>,
+    _pd: PhantomData<T>,
+}
+
+impl<'a, D, T> FirestoreDocChangesListenerObjInitBuilder<'a, D, T>
+where
+    D: FirestoreListenSupport + Clone + Send + Sync + 'static,
+    T: Send + 'static,
+    for<'de> T: Deserialize<'de>,
+{
+    #[inline]
+    pub(crate) fn new(init_builder: FirestoreDocChangesListenerInitBuilder<'a, D>) -> Self {
+        Self {
+            init_builder,
+            _pd: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn labels(self, labels: HashMap<String, String>) -> Self {
+        Self {
+            init_builder: self.init_builder.labels(labels),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_delay(self, delay: std::time::Duration) -> Self {
+        Self {
+            init_builder: self.init_builder.retry_delay(delay),
+            ..self
+        }
+    }
+
+    pub async fn start<S, FN, F>(
+        self,
+        target: FirestoreListenerTarget,
+        storage: S,
+        cb: FN,
+    ) -> FirestoreResult<FirestoreListener<D, S>>
+    where
+        S: FirestoreResumeStateStorage + Send + Sync + Clone + 'static,
+        FN: Fn(FirestoreListenerEvent<T>) -> F + Send + Sync + 'static,
+        F: Future<Output = AnyBoxedErrResult<()>> + Send + 'static,
+    {
+        let init_builder = self.init_builder;
+        let mut listener = FirestoreListener::new(
+            init_builder.db.clone(),
+            storage,
+            init_builder.listener_params.clone(),
+        )
+        .await?;
+        init_builder.add_target(target, &mut listener)?;
+        listener.start_obj(cb).await?;
+        Ok(listener)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreAggregatedQueryDocBuilder<'a, D>
+where
+    D: FirestoreAggregatedQuerySupport,
+{
+    db: &'a D,
+    params: FirestoreAggregatedQueryParams,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+}
+
+impl<'a, D> FirestoreAggregatedQueryDocBuilder<'a, D>
+where
+    D: FirestoreAggregatedQuerySupport,
+{
+    #[inline]
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreAggregatedQueryParams,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
+        Self {
+            db,
+            params,
+            timeout,
+            retry_policy,
+        }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn obj<T>(self) -> FirestoreAggregatedQueryObjBuilder<'a, D, T>
+    where
+        T: Send,
+        for<'de> T: Deserialize<'de>,
+    {
+        FirestoreAggregatedQueryObjBuilder::new(
+            self.db,
+            self.params,
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
+    #[inline]
+    pub fn explain(self, analyze: bool) -> FirestoreExplainAggregatedQueryDocBuilder<'a, D>
+    where
+        D: Sync,
+    {
+        FirestoreExplainAggregatedQueryDocBuilder::new(
+            self.db,
+            self.params,
+            FirestoreExplainOptions::new().with_analyze(analyze),
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
+    pub async fn query(self) -> FirestoreResult<Vec<Document>> {
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.aggregated_query_doc(self.params),
+        )
+        .await
+    }
+
+    pub async fn stream_query<'b>(self) -> FirestoreResult<BoxStream<'b, Document>> {
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_aggregated_query_doc(self.params),
+        )
+        .await
+    }
+
+    pub async fn stream_query_with_errors<'b>(
         self,
     ) -> FirestoreResult<BoxStream<'b, FirestoreResult<Document>>> {
-        self.db
-            .stream_aggregated_query_doc_with_errors(self.params)
-            .await
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_aggregated_query_doc_with_errors(self.params),
+        )
+        .await
     }
 }
 
@@ -851,6 +1531,8 @@ where
 {
     db: &'a D,
     params: FirestoreAggregatedQueryParams,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
     _ph: PhantomData<T>,
 }
 
@@ -861,20 +1543,69 @@ where
     for<'de> T: Deserialize<'de>,
 {
     #[inline]
-    pub(crate) fn new(db: &'a D, params: FirestoreAggregatedQueryParams) -> Self {
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreAggregatedQueryParams,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
         Self {
             db,
             params,
+            timeout,
+            retry_policy,
             _ph: PhantomData,
         }
     }
 
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn explain(self, analyze: bool) -> FirestoreExplainAggregatedQueryObjBuilder<'a, D, T>
+    where
+        D: Sync,
+    {
+        FirestoreExplainAggregatedQueryObjBuilder::new(
+            self.db,
+            self.params,
+            FirestoreExplainOptions::new().with_analyze(analyze),
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
     pub async fn query(self) -> FirestoreResult<Vec<T>> {
-        self.db.aggregated_query_obj(self.params).await
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.aggregated_query_obj(self.params),
+        )
+        .await
     }
 
     pub async fn stream_query<'b>(self) -> FirestoreResult<BoxStream<'b, T>> {
-        self.db.stream_aggregated_query_obj(self.params).await
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_aggregated_query_obj(self.params),
+        )
+        .await
     }
 
     pub async fn stream_query_with_errors<'b>(
@@ -883,14 +1614,303 @@ where
     where
         T: 'b,
     {
-        self.db
-            .stream_aggregated_query_obj_with_errors(self.params)
-            .await
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.stream_aggregated_query_obj_with_errors(self.params),
+        )
+        .await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreExplainQueryDocBuilder<'a, D>
+where
+    D: FirestoreQuerySupport + Sync,
+{
+    db: &'a D,
+    params: FirestoreQueryParams,
+    options: FirestoreExplainOptions,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+}
+
+impl<'a, D> FirestoreExplainQueryDocBuilder<'a, D>
+where
+    D: FirestoreQuerySupport + Sync,
+{
+    #[inline]
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreQueryParams,
+        options: FirestoreExplainOptions,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
+        Self {
+            db,
+            params,
+            options,
+            timeout,
+            retry_policy,
+        }
+    }
+
+    #[inline]
+    pub fn obj<T>(self) -> FirestoreExplainQueryObjBuilder<'a, D, T>
+    where
+        T: Send,
+        for<'de> T: Deserialize<'de>,
+    {
+        FirestoreExplainQueryObjBuilder::new(
+            self.db,
+            self.params,
+            self.options,
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    pub async fn query(self) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.explain_query_doc(self.params, self.options),
+        )
+        .await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreExplainQueryObjBuilder<'a, D, T>
+where
+    D: FirestoreQuerySupport + Sync,
+    T: Send,
+    for<'de> T: Deserialize<'de>,
+{
+    db: &'a D,
+    params: FirestoreQueryParams,
+    options: FirestoreExplainOptions,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+    _ph: PhantomData<T>,
+}
+
+impl<'a, D, T> FirestoreExplainQueryObjBuilder<'a, D, T>
+where
+    D: FirestoreQuerySupport + Sync,
+    T: Send,
+    for<'de> T: Deserialize<'de>,
+{
+    #[inline]
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreQueryParams,
+        options: FirestoreExplainOptions,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
+        Self {
+            db,
+            params,
+            options,
+            timeout,
+            retry_policy,
+            _ph: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    pub async fn query(self) -> FirestoreResult<FirestoreExplainResult<T>> {
+        self.params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db.explain_query_obj(self.params, self.options),
+        )
+        .await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreExplainAggregatedQueryDocBuilder<'a, D>
+where
+    D: FirestoreAggregatedQuerySupport + Sync,
+{
+    db: &'a D,
+    params: FirestoreAggregatedQueryParams,
+    options: FirestoreExplainOptions,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+}
+
+impl<'a, D> FirestoreExplainAggregatedQueryDocBuilder<'a, D>
+where
+    D: FirestoreAggregatedQuerySupport + Sync,
+{
+    #[inline]
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreAggregatedQueryParams,
+        options: FirestoreExplainOptions,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
+        Self {
+            db,
+            params,
+            options,
+            timeout,
+            retry_policy,
+        }
+    }
+
+    #[inline]
+    pub fn obj<T>(self) -> FirestoreExplainAggregatedQueryObjBuilder<'a, D, T>
+    where
+        T: Send,
+        for<'de> T: Deserialize<'de>,
+    {
+        FirestoreExplainAggregatedQueryObjBuilder::new(
+            self.db,
+            self.params,
+            self.options,
+            self.timeout,
+            self.retry_policy,
+        )
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    pub async fn query(self) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db
+                .explain_aggregated_query_doc(self.params, self.options),
+        )
+        .await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FirestoreExplainAggregatedQueryObjBuilder<'a, D, T>
+where
+    D: FirestoreAggregatedQuerySupport + Sync,
+    T: Send,
+    for<'de> T: Deserialize<'de>,
+{
+    db: &'a D,
+    params: FirestoreAggregatedQueryParams,
+    options: FirestoreExplainOptions,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+    _ph: PhantomData<T>,
+}
+
+impl<'a, D, T> FirestoreExplainAggregatedQueryObjBuilder<'a, D, T>
+where
+    D: FirestoreAggregatedQuerySupport + Sync,
+    T: Send,
+    for<'de> T: Deserialize<'de>,
+{
+    #[inline]
+    pub(crate) fn new(
+        db: &'a D,
+        params: FirestoreAggregatedQueryParams,
+        options: FirestoreExplainOptions,
+        timeout: Option<Duration>,
+        retry_policy: Option<FirestoreRetryPolicy>,
+    ) -> Self {
+        Self {
+            db,
+            params,
+            options,
+            timeout,
+            retry_policy,
+            _ph: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    pub async fn query(self) -> FirestoreResult<FirestoreExplainResult<T>> {
+        self.params.query_params.validate()?;
+        with_request_options(
+            self.timeout,
+            self.retry_policy,
+            self.db
+                .explain_aggregated_query_obj(self.params, self.options),
+        )
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::errors::FirestoreError;
     use crate::fluent_api::tests::*;
     use crate::fluent_api::FirestoreExprBuilder;
     use crate::{path, paths, FirestoreQueryCollection};
@@ -923,4 +1943,19 @@ mod tests {
             FirestoreQueryCollection::Single("test".to_string())
         )
     }
+
+    #[tokio::test]
+    async fn select_query_builder_explain_unsupported_by_default() {
+        let result = FirestoreExprBuilder::new(&mockdb::MockDatabase {})
+            .select()
+            .from("test")
+            .explain(false)
+            .query()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+    }
 }
@@ -1,10 +1,20 @@
+use crate::db::{
+    object_merge_field_paths, with_request_options, with_request_retry_policy, with_request_timeout,
+};
 use crate::document_transform_builder::FirestoreTransformBuilder;
+use crate::errors::{
+    FirestoreError, FirestoreInvalidParametersError, FirestoreInvalidParametersPublicDetails,
+};
 use crate::{
-    FirestoreBatch, FirestoreBatchWriter, FirestoreFieldTransform, FirestoreResult,
+    FirestoreBatch, FirestoreBatchWriter, FirestoreDocumentWithMetadata, FirestoreFieldTransform,
+    FirestoreGetByIdSupport, FirestoreResult, FirestoreRetryClassifier, FirestoreRetryPolicy,
     FirestoreTransaction, FirestoreUpdateSupport, FirestoreWritePrecondition,
 };
 use gcloud_sdk::google::firestore::v1::Document;
 use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use std::time::Duration;
+use tracing::*;
 
 #[derive(Clone, Debug)]
 pub struct FirestoreUpdateInitialBuilder<'a, D>
@@ -166,6 +176,8 @@ where
     document: Document,
     return_only_fields: Option<Vec<String>>,
     precondition: Option<FirestoreWritePrecondition>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
 }
 
 impl<'a, D> FirestoreUpdateDocExecuteBuilder<'a, D>
@@ -188,19 +200,40 @@ where
             document,
             return_only_fields,
             precondition,
+            timeout: None,
+            retry_policy: None,
+        }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
         }
     }
 
     pub async fn execute(self) -> FirestoreResult<Document> {
-        self.db
-            .update_doc(
-                self.collection_id.as_str(),
-                self.document,
-                self.update_only_fields,
-                self.return_only_fields,
-                self.precondition,
-            )
-            .await
+        with_request_options(self.timeout, self.retry_policy, async move {
+            self.db
+                .update_doc(
+                    self.collection_id.as_str(),
+                    self.document,
+                    self.update_only_fields,
+                    self.return_only_fields,
+                    self.precondition,
+                )
+                .await
+        })
+        .await
     }
 }
 
@@ -300,6 +333,197 @@ where
     }
 }
 
+impl<'a, D> FirestoreUpdateObjInitExecuteBuilder<'a, D>
+where
+    D: FirestoreUpdateSupport + FirestoreGetByIdSupport,
+{
+    /// Reads the document, applies the function and writes the result with an update time precondition.
+    /// The whole cycle is retried if the document was changed concurrently.
+    #[inline]
+    pub fn modify<T, FN>(self, modify_fn: FN) -> FirestoreUpdateModifyExecuteBuilder<'a, D, T, FN>
+    where
+        T: Serialize + Sync + Send,
+        for<'de> T: Deserialize<'de>,
+        FN: FnMut(T) -> T + Send,
+    {
+        FirestoreUpdateModifyExecuteBuilder::new(
+            self.db,
+            self.collection_id,
+            self.update_only_fields,
+            self.parent,
+            self.document_id,
+            self.return_only_fields,
+            modify_fn,
+        )
+    }
+}
+
+pub struct FirestoreUpdateModifyExecuteBuilder<'a, D, T, FN>
+where
+    D: FirestoreUpdateSupport + FirestoreGetByIdSupport,
+    FN: FnMut(T) -> T + Send,
+{
+    db: &'a D,
+    collection_id: String,
+    update_only_fields: Option<Vec<String>>,
+    parent: Option<String>,
+    document_id: String,
+    return_only_fields: Option<Vec<String>>,
+    modify_fn: FN,
+    retry_policy: Option<FirestoreRetryPolicy>,
+    timeout: Option<Duration>,
+    _obj: PhantomData<T>,
+}
+
+impl<'a, D, T, FN> FirestoreUpdateModifyExecuteBuilder<'a, D, T, FN>
+where
+    D: FirestoreUpdateSupport + FirestoreGetByIdSupport,
+    T: Serialize + Sync + Send,
+    for<'de> T: Deserialize<'de>,
+    FN: FnMut(T) -> T + Send,
+{
+    #[inline]
+    pub(crate) fn new(
+        db: &'a D,
+        collection_id: String,
+        update_only_fields: Option<Vec<String>>,
+        parent: Option<String>,
+        document_id: String,
+        return_only_fields: Option<Vec<String>>,
+        modify_fn: FN,
+    ) -> Self {
+        Self {
+            db,
+            collection_id,
+            update_only_fields,
+            parent,
+            document_id,
+            return_only_fields,
+            modify_fn,
+            retry_policy: None,
+            timeout: None,
+            _obj: PhantomData,
+        }
+    }
+
+    /// Policy for the conflicts and other errors. The conflicts are always retried
+    /// while the other errors are retried according to the policy classifier.
+    /// Only the whole cycle is retried, the reads and writes inside it don't retry on their own.
+    /// The database retry policy is used by default.
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub async fn execute(mut self) -> FirestoreResult<T> {
+        let span = span!(
+            Level::DEBUG,
+            "Firestore Modify",
+            "/firestore/collection_name" = self.collection_id.as_str(),
+            "/firestore/document_name" = self.document_id.as_str()
+        );
+
+        let retry_policy = self
+            .retry_policy
+            .take()
+            .unwrap_or_else(|| self.db.get_retry_policy());
+        let classifier = retry_policy.classifier.clone();
+        let retry_policy =
+            retry_policy.with_classifier(FirestoreRetryClassifier::new(move |err| {
+                is_update_conflict(err) || classifier.is_retryable(err)
+            }));
+
+        let timeout = self.timeout;
+        with_request_timeout(timeout, async move {
+            let mut retry_state = retry_policy.new_state();
+            loop {
+                // The whole cycle is retried here, so the reads and writes shouldn't retry on their own
+                match with_request_retry_policy(
+                    Some(FirestoreRetryPolicy::no_retries()),
+                    self.modify_once(),
+                )
+                .await
+                {
+                    Ok(result) => return Ok(result),
+                    Err(err) => {
+                        if !retry_state.wait_retry(&err, &span).await {
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+        })
+        .await
+    }
+
+    async fn modify_once(&mut self) -> FirestoreResult<T> {
+        let current: FirestoreDocumentWithMetadata<T> = match &self.parent {
+            Some(parent) => {
+                self.db
+                    .get_obj_at(
+                        parent.as_str(),
+                        self.collection_id.as_str(),
+                        self.document_id.as_str(),
+                    )
+                    .await?
+            }
+            None => {
+                self.db
+                    .get_obj(self.collection_id.as_str(), self.document_id.as_str())
+                    .await?
+            }
+        };
+
+        let precondition = current
+            .update_time_precondition()
+            .unwrap_or(FirestoreWritePrecondition::Exists(true));
+        let modified = (self.modify_fn)(current.obj);
+
+        match &self.parent {
+            Some(parent) => {
+                self.db
+                    .update_obj_at(
+                        parent.as_str(),
+                        self.collection_id.as_str(),
+                        self.document_id.as_str(),
+                        &modified,
+                        self.update_only_fields.clone(),
+                        self.return_only_fields.clone(),
+                        Some(precondition),
+                    )
+                    .await
+            }
+            None => {
+                self.db
+                    .update_obj(
+                        self.collection_id.as_str(),
+                        self.document_id.as_str(),
+                        &modified,
+                        self.update_only_fields.clone(),
+                        self.return_only_fields.clone(),
+                        Some(precondition),
+                    )
+                    .await
+            }
+        }
+    }
+}
+
+fn is_update_conflict(err: &FirestoreError) -> bool {
+    matches!(err, FirestoreError::DatabaseError(db_err) if db_err.public.code == "FailedPrecondition")
+}
+
 #[derive(Clone, Debug)]
 pub struct FirestoreUpdateObjExecuteBuilder<'a, D, T>
 where
@@ -315,6 +539,9 @@ where
     return_only_fields: Option<Vec<String>>,
     precondition: Option<FirestoreWritePrecondition>,
     transforms: Vec<FirestoreFieldTransform>,
+    timeout: Option<Duration>,
+    retry_policy: Option<FirestoreRetryPolicy>,
+    merge: bool,
 }
 
 impl<'a, D, T> FirestoreUpdateObjExecuteBuilder<'a, D, T>
@@ -344,6 +571,44 @@ where
             return_only_fields,
             precondition,
             transforms,
+            timeout: None,
+            retry_policy: None,
+            merge: false,
+        }
+    }
+
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn retry_policy(self, retry_policy: FirestoreRetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(retry_policy),
+            ..self
+        }
+    }
+
+    /// Updates only the fields present in the object, creating the document if it doesn't exist.
+    /// Can't be combined with `fields`, the writes fail with `FirestoreError::InvalidParametersError` then.
+    #[inline]
+    pub fn merge(self) -> Self {
+        Self {
+            merge: true,
+            ..self
+        }
+    }
+
+    /// Creates the document, failing the write if it already exists.
+    #[inline]
+    pub fn create_only(self) -> Self {
+        Self {
+            precondition: Some(FirestoreWritePrecondition::Exists(false)),
+            ..self
         }
     }
 
@@ -351,30 +616,40 @@ where
     where
         for<'de> O: Deserialize<'de>,
     {
-        if let Some(parent) = self.parent {
-            self.db
-                .update_obj_at(
-                    parent.as_str(),
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.object,
-                    self.update_only_fields,
-                    self.return_only_fields,
-                    self.precondition,
-                )
-                .await
+        self.validate_merge()?;
+        let update_only_fields = if self.merge {
+            Some(object_merge_field_paths(self.object)?)
         } else {
-            self.db
-                .update_obj(
-                    self.collection_id.as_str(),
-                    self.document_id,
-                    self.object,
-                    self.update_only_fields,
-                    self.return_only_fields,
-                    self.precondition,
-                )
-                .await
-        }
+            self.update_only_fields
+        };
+
+        with_request_options(self.timeout, self.retry_policy, async move {
+            if let Some(parent) = self.parent {
+                self.db
+                    .update_obj_at(
+                        parent.as_str(),
+                        self.collection_id.as_str(),
+                        self.document_id,
+                        self.object,
+                        update_only_fields,
+                        self.return_only_fields,
+                        self.precondition,
+                    )
+                    .await
+            } else {
+                self.db
+                    .update_obj(
+                        self.collection_id.as_str(),
+                        self.document_id,
+                        self.object,
+                        update_only_fields,
+                        self.return_only_fields,
+                        self.precondition,
+                    )
+                    .await
+            }
+        })
+        .await
     }
 
     #[inline]
@@ -393,6 +668,28 @@ where
         self,
         transaction: &'a mut FirestoreTransaction<'t>,
     ) -> FirestoreResult<&'a mut FirestoreTransaction<'t>> {
+        self.validate_merge()?;
+        if self.merge {
+            return if let Some(parent) = self.parent {
+                transaction.set_object_merge_at(
+                    parent.as_str(),
+                    self.collection_id.as_str(),
+                    self.document_id,
+                    self.object,
+                    self.precondition,
+                    self.transforms,
+                )
+            } else {
+                transaction.set_object_merge(
+                    self.collection_id.as_str(),
+                    self.document_id,
+                    self.object,
+                    self.precondition,
+                    self.transforms,
+                )
+            };
+        }
+
         if let Some(parent) = self.parent {
             transaction.update_object_at(
                 parent.as_str(),
@@ -423,6 +720,28 @@ where
     where
         W: FirestoreBatchWriter,
     {
+        self.validate_merge()?;
+        if self.merge {
+            return if let Some(parent) = self.parent {
+                batch.set_object_merge_at(
+                    parent.as_str(),
+                    self.collection_id.as_str(),
+                    self.document_id,
+                    self.object,
+                    self.precondition,
+                    self.transforms,
+                )
+            } else {
+                batch.set_object_merge(
+                    self.collection_id.as_str(),
+                    self.document_id,
+                    self.object,
+                    self.precondition,
+                    self.transforms,
+                )
+            };
+        }
+
         if let Some(parent) = self.parent {
             batch.update_object_at(
                 parent.as_str(),
@@ -444,6 +763,19 @@ where
             )
         }
     }
+
+    fn validate_merge(&self) -> FirestoreResult<()> {
+        if self.merge && self.update_only_fields.is_some() {
+            return Err(FirestoreError::InvalidParametersError(
+                FirestoreInvalidParametersError::new(FirestoreInvalidParametersPublicDetails::new(
+                    "update_only_fields".to_string(),
+                    "Merge updates the fields of the object and can't be combined with the update fields"
+                        .to_string(),
+                )),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug)]
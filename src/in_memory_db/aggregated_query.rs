@@ -0,0 +1,200 @@
+use crate::in_memory_db::explain_query_results;
+use crate::*;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::{future, StreamExt, TryStreamExt};
+use gcloud_sdk::google::firestore::v1::{value, Document, Value};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::*;
+
+#[async_trait]
+impl FirestoreAggregatedQuerySupport for FirestoreInMemoryDb {
+    async fn aggregated_query_doc(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+    ) -> FirestoreResult<Vec<Document>> {
+        let docs = self.query_docs(&params.query_params).await?;
+
+        let aggregate_fields: HashMap<String, Value> = params
+            .aggregations
+            .iter()
+            .filter_map(|aggregation| {
+                aggregation
+                    .operator
+                    .as_ref()
+                    .map(|operator| (aggregation.alias.clone(), aggregate_docs(operator, &docs)))
+            })
+            .collect();
+
+        Ok(vec![Document {
+            name: "".to_string(),
+            fields: aggregate_fields,
+            create_time: None,
+            update_time: None,
+        }])
+    }
+
+    async fn stream_aggregated_query_doc<'b>(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+    ) -> FirestoreResult<BoxStream<'b, Document>> {
+        let docs = self.aggregated_query_doc(params).await?;
+        Ok(futures::stream::iter(docs).boxed())
+    }
+
+    async fn stream_aggregated_query_doc_with_errors<'b>(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<Document>>> {
+        let docs = self.aggregated_query_doc(params).await?;
+        Ok(futures::stream::iter(docs.into_iter().map(Ok)).boxed())
+    }
+
+    async fn aggregated_query_obj<T>(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+    ) -> FirestoreResult<Vec<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let doc_vec = self.aggregated_query_doc(params).await?;
+        doc_vec
+            .iter()
+            .map(|doc| FirestoreDb::deserialize_doc_to(doc))
+            .collect()
+    }
+
+    async fn stream_aggregated_query_obj<'b, T>(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+    ) -> FirestoreResult<BoxStream<'b, T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let doc_stream = self.stream_aggregated_query_doc(params).await?;
+        Ok(Box::pin(doc_stream.filter_map(|doc| async move {
+            match FirestoreDb::deserialize_doc_to::<T>(&doc) {
+                Ok(obj) => Some(obj),
+                Err(err) => {
+                    error!(
+                        "Error occurred while consuming query document as a stream: {}",
+                        err
+                    );
+                    None
+                }
+            }
+        })))
+    }
+
+    async fn stream_aggregated_query_obj_with_errors<'b, T>(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<T>>>
+    where
+        for<'de> T: Deserialize<'de>,
+        T: Send + 'b,
+    {
+        let doc_stream = self.stream_aggregated_query_doc_with_errors(params).await?;
+        Ok(Box::pin(doc_stream.and_then(|doc| {
+            future::ready(FirestoreDb::deserialize_doc_to::<T>(&doc))
+        })))
+    }
+
+    async fn explain_aggregated_query_doc(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        explain_query_results(&options, self.aggregated_query_doc(params)).await
+    }
+
+    async fn explain_aggregated_query_obj<T>(
+        &self,
+        params: FirestoreAggregatedQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let explain_result = self.explain_aggregated_query_doc(params, options).await?;
+        Ok(FirestoreExplainResult::new(
+            explain_result
+                .results
+                .iter()
+                .map(|doc| FirestoreDb::deserialize_doc_to(doc))
+                .collect::<FirestoreResult<Vec<T>>>()?,
+        )
+        .opt_metrics(explain_result.metrics))
+    }
+}
+
+fn aggregate_docs(operator: &FirestoreAggregationOperator, docs: &[Document]) -> Value {
+    match operator {
+        FirestoreAggregationOperator::Count(count) => {
+            let total = count
+                .up_to
+                .map(|up_to| docs.len().min(up_to))
+                .unwrap_or(docs.len());
+            Value {
+                value_type: Some(value::ValueType::IntegerValue(total as i64)),
+            }
+        }
+        FirestoreAggregationOperator::Sum(sum) => {
+            let values = numeric_values(sum.field_name.as_str(), docs);
+            let all_integers = values
+                .iter()
+                .all(|value| matches!(value, value::ValueType::IntegerValue(_)));
+
+            let integer_sum = values.iter().try_fold(0_i64, |acc, value| match value {
+                value::ValueType::IntegerValue(v) => acc.checked_add(*v),
+                _ => None,
+            });
+
+            match integer_sum {
+                Some(total) if all_integers => Value {
+                    value_type: Some(value::ValueType::IntegerValue(total)),
+                },
+                _ => Value {
+                    value_type: Some(value::ValueType::DoubleValue(
+                        values.iter().map(numeric_as_f64).sum(),
+                    )),
+                },
+            }
+        }
+        FirestoreAggregationOperator::Avg(avg) => {
+            let values = numeric_values(avg.field_name.as_str(), docs);
+            if values.is_empty() {
+                Value {
+                    value_type: Some(value::ValueType::NullValue(0)),
+                }
+            } else {
+                let total: f64 = values.iter().map(numeric_as_f64).sum();
+                Value {
+                    value_type: Some(value::ValueType::DoubleValue(total / values.len() as f64)),
+                }
+            }
+        }
+    }
+}
+
+fn numeric_values(field_name: &str, docs: &[Document]) -> Vec<value::ValueType> {
+    docs.iter()
+        .filter_map(|doc| firestore_doc_get_field_by_path(doc, field_name))
+        .filter(|value| {
+            matches!(
+                value,
+                value::ValueType::IntegerValue(_) | value::ValueType::DoubleValue(_)
+            )
+        })
+        .cloned()
+        .collect()
+}
+
+fn numeric_as_f64(value: &value::ValueType) -> f64 {
+    match value {
+        value::ValueType::IntegerValue(v) => *v as f64,
+        value::ValueType::DoubleValue(v) => *v,
+        _ => 0.0,
+    }
+}
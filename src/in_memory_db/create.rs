@@ -0,0 +1,107 @@
+use crate::db::{generate_document_id, safe_document_path};
+use crate::in_memory_db::apply_return_only_fields;
+use crate::*;
+use async_trait::async_trait;
+use gcloud_sdk::google::firestore::v1::*;
+use serde::{Deserialize, Serialize};
+
+#[async_trait]
+impl FirestoreCreateSupport for FirestoreInMemoryDb {
+    async fn create_doc<S>(
+        &self,
+        collection_id: &str,
+        document_id: Option<S>,
+        input_doc: Document,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<Document>
+    where
+        S: AsRef<str> + Send,
+    {
+        self.create_doc_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            input_doc,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn create_doc_at<S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: Option<S>,
+        input_doc: Document,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<Document>
+    where
+        S: AsRef<str> + Send,
+    {
+        let document_id = document_id
+            .as_ref()
+            .map(|id| id.as_ref().to_string())
+            .unwrap_or_else(generate_document_id);
+        let document_path = safe_document_path(parent, collection_id, document_id.as_str())?;
+
+        let doc = self.write_doc(
+            document_path.as_str(),
+            Some(&FirestoreWritePrecondition::Exists(false)),
+            |_| Ok(Some(input_doc)),
+        )?;
+
+        Ok(doc
+            .map(|doc| apply_return_only_fields(doc, &return_only_fields))
+            .unwrap_or_default())
+    }
+
+    async fn create_obj<I, O, S>(
+        &self,
+        collection_id: &str,
+        document_id: Option<S>,
+        obj: &I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<O>
+    where
+        I: Serialize + Sync + Send,
+        for<'de> O: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        self.create_obj_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            obj,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn create_obj_at<I, O, S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: Option<S>,
+        obj: &I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<O>
+    where
+        I: Serialize + Sync + Send,
+        for<'de> O: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        let input_doc = FirestoreDb::serialize_to_doc("", obj)?;
+
+        let doc = self
+            .create_doc_at(
+                parent,
+                collection_id,
+                document_id,
+                input_doc,
+                return_only_fields,
+            )
+            .await?;
+
+        FirestoreDb::deserialize_doc_to(&doc)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::db::safe_document_path;
+use crate::*;
+use async_trait::async_trait;
+
+#[async_trait]
+impl FirestoreDeleteSupport for FirestoreInMemoryDb {
+    async fn delete_by_id<S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> FirestoreResult<()>
+    where
+        S: AsRef<str> + Send,
+    {
+        self.delete_by_id_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            precondition,
+        )
+        .await
+    }
+
+    async fn delete_by_id_at<S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> FirestoreResult<()>
+    where
+        S: AsRef<str> + Send,
+    {
+        let document_path = safe_document_path(parent, collection_id, document_id.as_ref())?;
+
+        self.write_doc(document_path.as_str(), precondition.as_ref(), |_| Ok(None))?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FirestoreRecursiveDeleteSupport for FirestoreInMemoryDb {
+    async fn delete_recursive_by_id<S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>
+    where
+        S: AsRef<str> + Send,
+    {
+        self.delete_recursive_by_id_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            precondition,
+            options,
+        )
+        .await
+    }
+
+    async fn delete_recursive_by_id_at<S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        precondition: Option<FirestoreWritePrecondition>,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult>
+    where
+        S: AsRef<str> + Send,
+    {
+        let document_path = safe_document_path(parent, collection_id, document_id.as_ref())?;
+
+        let mut root_existed = false;
+        self.write_doc(document_path.as_str(), precondition.as_ref(), |existing| {
+            root_existed = existing.is_some();
+            Ok(None)
+        })?;
+
+        let mut result = self.delete_docs_with_prefix(&format!("{document_path}/"), &options)?;
+        if root_existed {
+            result.deleted_documents += 1;
+        }
+        Ok(result)
+    }
+
+    async fn delete_collection(
+        &self,
+        collection_id: &str,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult> {
+        self.delete_collection_at(self.get_documents_path().as_str(), collection_id, options)
+            .await
+    }
+
+    async fn delete_collection_at(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        options: FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult> {
+        self.delete_docs_with_prefix(&format!("{parent}/{collection_id}/"), &options)
+    }
+}
+
+impl FirestoreInMemoryDb {
+    fn delete_docs_with_prefix(
+        &self,
+        prefix: &str,
+        options: &FirestoreRecursiveDeleteOptions,
+    ) -> FirestoreResult<FirestoreRecursiveDeleteResult> {
+        let docs = self.find_docs(|doc| doc.name.starts_with(prefix));
+
+        let mut deleted_documents = 0;
+        for doc in docs {
+            self.write_doc(doc.name.as_str(), None, |_| Ok(None))?;
+            deleted_documents += 1;
+        }
+
+        if let Some(on_progress) = &options.on_progress {
+            on_progress(&FirestoreRecursiveDeleteProgress {
+                scanned_collections: 1,
+                queued_documents: deleted_documents,
+            });
+        }
+
+        Ok(FirestoreRecursiveDeleteResult {
+            deleted_documents,
+            failures: vec![],
+        })
+    }
+}
@@ -0,0 +1,369 @@
+use crate::db::safe_document_path;
+use crate::in_memory_db::{apply_return_only_fields, split_doc_name};
+use crate::*;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::{future, StreamExt, TryStreamExt};
+use gcloud_sdk::google::firestore::v1::*;
+use serde::Deserialize;
+use tracing::*;
+
+#[async_trait]
+impl FirestoreGetByIdSupport for FirestoreInMemoryDb {
+    async fn get_doc<S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<Document>
+    where
+        S: AsRef<str> + Send,
+    {
+        self.get_doc_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn get_doc_at<S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<Document>
+    where
+        S: AsRef<str> + Send,
+    {
+        let document_path = safe_document_path(parent, collection_id, document_id.as_ref())?;
+        match self.read_doc(document_path.as_str()) {
+            Some(doc) => Ok(apply_return_only_fields(doc, &return_only_fields)),
+            None => Err(gcloud_sdk::tonic::Status::not_found(format!(
+                "Document not found: {document_path}"
+            ))
+            .into()),
+        }
+    }
+
+    async fn get_obj<T, S>(&self, collection_id: &str, document_id: S) -> FirestoreResult<T>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        self.get_obj_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+        )
+        .await
+    }
+
+    async fn get_obj_return_fields<T, S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<T>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        self.get_obj_at_return_fields(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn get_obj_at<T, S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+    ) -> FirestoreResult<T>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        self.get_obj_at_return_fields(parent, collection_id, document_id, None)
+            .await
+    }
+
+    async fn get_obj_at_return_fields<T, S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<T>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        let doc: Document = self
+            .get_doc_at(parent, collection_id, document_id, return_only_fields)
+            .await?;
+
+        FirestoreDb::deserialize_doc_to(&doc)
+    }
+
+    async fn get_obj_if_exists<T, S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<Option<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        self.get_obj_at_if_exists(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn get_obj_at_if_exists<T, S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<Option<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        match self
+            .get_obj_at_return_fields::<T, S>(
+                parent,
+                collection_id,
+                document_id,
+                return_only_fields,
+            )
+            .await
+        {
+            Ok(obj) => Ok(Some(obj)),
+            Err(err) => match err {
+                FirestoreError::DataNotFoundError(_) => Ok(None),
+                _ => Err(err),
+            },
+        }
+    }
+
+    async fn batch_stream_get_docs<S, I>(
+        &self,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<(String, Option<Document>)>>
+    where
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        self.batch_stream_get_docs_at(
+            self.get_documents_path(),
+            collection_id,
+            document_ids,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn batch_stream_get_docs_with_errors<S, I>(
+        &self,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<FirestoreResult<(String, Option<Document>)>>>
+    where
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        self.batch_stream_get_docs_at_with_errors(
+            self.get_documents_path(),
+            collection_id,
+            document_ids,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn batch_stream_get_docs_at<S, I>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<(String, Option<Document>)>>
+    where
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        let doc_stream = self
+            .batch_stream_get_docs_at_with_errors(
+                parent,
+                collection_id,
+                document_ids,
+                return_only_fields,
+            )
+            .await?;
+
+        Ok(Box::pin(doc_stream.filter_map(|doc_res| {
+            future::ready(match doc_res {
+                Ok(doc_pair) => Some(doc_pair),
+                Err(err) => {
+                    error!(
+                        "Error occurred while consuming batch get as a stream: {}",
+                        err
+                    );
+                    None
+                }
+            })
+        })))
+    }
+
+    async fn batch_stream_get_docs_at_with_errors<S, I>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<FirestoreResult<(String, Option<Document>)>>>
+    where
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        let full_doc_ids: Vec<String> = document_ids
+            .into_iter()
+            .map(|document_id| safe_document_path(parent, collection_id, document_id.as_ref()))
+            .collect::<FirestoreResult<Vec<String>>>()?;
+
+        let docs: Vec<FirestoreResult<(String, Option<Document>)>> = full_doc_ids
+            .into_iter()
+            .map(|full_doc_id| {
+                let doc_id = split_doc_name(full_doc_id.as_str()).1.to_string();
+                let maybe_doc = self
+                    .read_doc(full_doc_id.as_str())
+                    .map(|doc| apply_return_only_fields(doc, &return_only_fields));
+                Ok((doc_id, maybe_doc))
+            })
+            .collect();
+
+        Ok(futures::stream::iter(docs).boxed())
+    }
+
+    async fn batch_stream_get_objects<'a, T, S, I>(
+        &'a self,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<'a, (String, Option<T>)>>
+    where
+        for<'de> T: Deserialize<'de> + Send + 'a,
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        self.batch_stream_get_objects_at(
+            self.get_documents_path(),
+            collection_id,
+            document_ids,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn batch_stream_get_objects_with_errors<'a, T, S, I>(
+        &'a self,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<'a, FirestoreResult<(String, Option<T>)>>>
+    where
+        for<'de> T: Deserialize<'de> + Send + 'a,
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        self.batch_stream_get_objects_at_with_errors(
+            self.get_documents_path(),
+            collection_id,
+            document_ids,
+            return_only_fields,
+        )
+        .await
+    }
+
+    async fn batch_stream_get_objects_at<'a, T, S, I>(
+        &'a self,
+        parent: &str,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<'a, (String, Option<T>)>>
+    where
+        for<'de> T: Deserialize<'de> + Send + 'a,
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        let obj_stream = self
+            .batch_stream_get_objects_at_with_errors(
+                parent,
+                collection_id,
+                document_ids,
+                return_only_fields,
+            )
+            .await?;
+
+        Ok(Box::pin(obj_stream.filter_map(|obj_res| {
+            future::ready(match obj_res {
+                Ok(obj_pair) => Some(obj_pair),
+                Err(err) => {
+                    error!(
+                        "Error occurred while consuming batch documents as a stream: {}",
+                        err
+                    );
+                    None
+                }
+            })
+        })))
+    }
+
+    async fn batch_stream_get_objects_at_with_errors<'a, T, S, I>(
+        &'a self,
+        parent: &str,
+        collection_id: &str,
+        document_ids: I,
+        return_only_fields: Option<Vec<String>>,
+    ) -> FirestoreResult<BoxStream<'a, FirestoreResult<(String, Option<T>)>>>
+    where
+        for<'de> T: Deserialize<'de> + Send + 'a,
+        S: AsRef<str> + Send,
+        I: IntoIterator<Item = S> + Send,
+    {
+        let doc_stream = self
+            .batch_stream_get_docs_at_with_errors(
+                parent,
+                collection_id,
+                document_ids,
+                return_only_fields,
+            )
+            .await?;
+
+        Ok(Box::pin(doc_stream.and_then(|(doc_id, maybe_doc)| {
+            future::ready({
+                maybe_doc
+                    .map(|doc| FirestoreDb::deserialize_doc_to::<T>(&doc))
+                    .transpose()
+                    .map(|obj| (doc_id, obj))
+            })
+        })))
+    }
+}
@@ -0,0 +1,173 @@
+use crate::errors::*;
+use crate::*;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::{future, StreamExt, TryStreamExt};
+use gcloud_sdk::google::firestore::v1::*;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use tracing::*;
+
+#[async_trait]
+impl FirestoreListingSupport for FirestoreInMemoryDb {
+    async fn list_doc(
+        &self,
+        params: FirestoreListDocParams,
+    ) -> FirestoreResult<FirestoreListDocResult> {
+        let query_params = FirestoreQueryParams::new(params.collection_id.as_str().into())
+            .opt_parent(params.parent.clone())
+            .opt_order_by(params.order_by.clone())
+            .opt_return_only_fields(params.return_only_fields.clone());
+
+        let all_docs = self.query_docs(&query_params).await?;
+        let (documents, page_token) = into_page(all_docs, params.page_size, &params.page_token)?;
+
+        Ok(FirestoreListDocResult::new(documents).opt_page_token(page_token))
+    }
+
+    async fn stream_list_doc(
+        &self,
+        params: FirestoreListDocParams,
+    ) -> FirestoreResult<BoxStream<Document>> {
+        let doc_stream = self.stream_list_doc_with_errors(params).await?;
+        Ok(Box::pin(doc_stream.filter_map(|doc_res| {
+            future::ready(match doc_res {
+                Ok(doc) => Some(doc),
+                Err(err) => {
+                    error!("Error occurred while consuming documents: {}", err);
+                    None
+                }
+            })
+        })))
+    }
+
+    async fn stream_list_doc_with_errors(
+        &self,
+        params: FirestoreListDocParams,
+    ) -> FirestoreResult<BoxStream<FirestoreResult<Document>>> {
+        let query_params = FirestoreQueryParams::new(params.collection_id.as_str().into())
+            .opt_parent(params.parent.clone())
+            .opt_order_by(params.order_by.clone())
+            .opt_return_only_fields(params.return_only_fields.clone());
+
+        let all_docs = self.query_docs(&query_params).await?;
+        Ok(futures::stream::iter(all_docs.into_iter().map(Ok)).boxed())
+    }
+
+    async fn stream_list_obj<T>(
+        &self,
+        params: FirestoreListDocParams,
+    ) -> FirestoreResult<BoxStream<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let doc_stream = self.stream_list_doc(params).await?;
+
+        Ok(Box::pin(doc_stream.filter_map(|doc| async move {
+            match FirestoreDb::deserialize_doc_to::<T>(&doc) {
+                Ok(obj) => Some(obj),
+                Err(err) => {
+                    error!(
+                        "Error occurred while consuming list document as a stream: {}",
+                        err
+                    );
+                    None
+                }
+            }
+        })))
+    }
+
+    async fn stream_list_obj_with_errors<T>(
+        &self,
+        params: FirestoreListDocParams,
+    ) -> FirestoreResult<BoxStream<FirestoreResult<T>>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let doc_stream = self.stream_list_doc_with_errors(params).await?;
+        Ok(Box::pin(doc_stream.and_then(|doc| async move {
+            FirestoreDb::deserialize_doc_to::<T>(&doc)
+        })))
+    }
+
+    async fn list_collection_ids(
+        &self,
+        params: FirestoreListCollectionIdsParams,
+    ) -> FirestoreResult<FirestoreListCollectionIdsResult> {
+        let all_collection_ids = self.collection_ids(&params);
+        let (collection_ids, page_token) =
+            into_page(all_collection_ids, params.page_size, &params.page_token)?;
+
+        Ok(FirestoreListCollectionIdsResult::new(collection_ids).opt_page_token(page_token))
+    }
+
+    async fn stream_list_collection_ids_with_errors(
+        &self,
+        params: FirestoreListCollectionIdsParams,
+    ) -> FirestoreResult<BoxStream<FirestoreResult<String>>> {
+        let collection_ids = self.collection_ids(&params);
+        Ok(futures::stream::iter(collection_ids.into_iter().map(Ok)).boxed())
+    }
+
+    async fn stream_list_collection_ids(
+        &self,
+        params: FirestoreListCollectionIdsParams,
+    ) -> FirestoreResult<BoxStream<String>> {
+        let collection_ids = self.collection_ids(&params);
+        Ok(futures::stream::iter(collection_ids).boxed())
+    }
+}
+
+impl FirestoreInMemoryDb {
+    fn collection_ids(&self, params: &FirestoreListCollectionIdsParams) -> Vec<String> {
+        let parent_prefix = format!(
+            "{}/",
+            params
+                .parent
+                .as_ref()
+                .unwrap_or_else(|| self.get_documents_path())
+        );
+
+        let collection_ids: BTreeSet<String> = self
+            .find_docs(|doc| doc.name.starts_with(parent_prefix.as_str()))
+            .into_iter()
+            .filter_map(|doc| {
+                doc.name[parent_prefix.len()..]
+                    .split('/')
+                    .next()
+                    .map(|collection_id| collection_id.to_string())
+            })
+            .collect();
+
+        collection_ids.into_iter().collect()
+    }
+}
+
+fn into_page<T>(
+    items: Vec<T>,
+    page_size: usize,
+    page_token: &Option<String>,
+) -> FirestoreResult<(Vec<T>, Option<String>)> {
+    let offset = match page_token {
+        Some(token) => token.parse::<usize>().map_err(|_| {
+            FirestoreError::InvalidParametersError(FirestoreInvalidParametersError::new(
+                FirestoreInvalidParametersPublicDetails::new(
+                    "page_token".to_string(),
+                    format!("Invalid page token: {token}"),
+                ),
+            ))
+        })?,
+        None => 0,
+    };
+
+    let total = items.len();
+    let page: Vec<T> = items.into_iter().skip(offset).take(page_size).collect();
+    let next_offset = offset + page.len();
+    let next_page_token = if next_offset < total && !page.is_empty() {
+        Some(next_offset.to_string())
+    } else {
+        None
+    };
+
+    Ok((page, next_page_token))
+}
@@ -0,0 +1,295 @@
+use crate::db::safe_document_path;
+use crate::in_memory_db::FirestoreInMemoryDocChange;
+use crate::timestamp_utils::to_timestamp;
+use crate::*;
+use async_trait::async_trait;
+use chrono::prelude::*;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use gcloud_sdk::google::firestore::v1::*;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::*;
+
+struct FirestoreInMemoryListenTarget {
+    target_id: i32,
+    target_type: FirestoreTargetType,
+    since_version: u64,
+}
+
+enum FirestoreInMemoryListenInput {
+    DocChange(FirestoreInMemoryDocChange),
+    TargetUpdate(FirestoreListenerTargetUpdate),
+}
+
+#[async_trait]
+impl FirestoreListenSupport for FirestoreInMemoryDb {
+    async fn listen_doc_changes<'a, 'b>(
+        &'a self,
+        targets: Vec<FirestoreListenerTargetParams>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+        self.listen_doc_changes_with_updates(targets, futures::stream::empty().boxed())
+            .await
+    }
+
+    /// Listens to the changes of the stored documents.
+    /// There is no history of changes in memory, so the resume tokens and read times
+    /// of the targets are ignored and the current state is always sent first.
+    async fn listen_doc_changes_with_updates<'a, 'b>(
+        &'a self,
+        targets: Vec<FirestoreListenerTargetParams>,
+        target_updates: BoxStream<'static, FirestoreListenerTargetUpdate>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<ListenResponse>>> {
+        let targets: Vec<(i32, FirestoreTargetType)> = targets
+            .into_iter()
+            .map(|target_params| {
+                let target_id: i32 = target_params.target.try_into()?;
+                Ok((target_id, target_params.target_type))
+            })
+            .collect::<FirestoreResult<Vec<(i32, FirestoreTargetType)>>>()?;
+
+        // Subscribing before reading the current state, so no changes are lost in between
+        let receiver = self.subscribe();
+
+        let read_time = Utc::now();
+        let mut initial_responses = Vec::new();
+        let mut listen_targets = Vec::with_capacity(targets.len());
+
+        for (target_id, target_type) in targets {
+            let (target_responses, listen_target) =
+                self.add_listen_target(target_id, target_type, read_time);
+            initial_responses.extend(target_responses);
+            listen_targets.push(listen_target);
+        }
+
+        let snapshot_version = listen_targets
+            .iter()
+            .map(|listen_target| listen_target.since_version)
+            .max()
+            .unwrap_or(0);
+
+        initial_responses.push(target_change_response(
+            target_change::TargetChangeType::NoChange,
+            vec![],
+            Some(snapshot_version),
+            read_time,
+        ));
+
+        let db = self.clone();
+        let inputs = futures::stream::select(
+            UnboundedReceiverStream::new(receiver).map(FirestoreInMemoryListenInput::DocChange),
+            target_updates.map(FirestoreInMemoryListenInput::TargetUpdate),
+        );
+        let changes_stream = inputs
+            .scan(listen_targets, move |listen_targets, input| {
+                futures::future::ready(Some(db.listen_input_to_responses(listen_targets, input)))
+            })
+            .flat_map(futures::stream::iter);
+
+        Ok(futures::stream::iter(initial_responses)
+            .chain(changes_stream)
+            .map(Ok)
+            .boxed())
+    }
+}
+
+impl FirestoreInMemoryDb {
+    fn add_listen_target(
+        &self,
+        target_id: i32,
+        target_type: FirestoreTargetType,
+        read_time: DateTime<Utc>,
+    ) -> (Vec<ListenResponse>, FirestoreInMemoryListenTarget) {
+        let mut responses = vec![target_change_response(
+            target_change::TargetChangeType::Add,
+            vec![target_id],
+            None,
+            read_time,
+        )];
+
+        let (docs, version) =
+            self.find_docs_with_version(|doc| self.target_matches_doc(&target_type, doc));
+
+        responses.extend(docs.into_iter().map(|doc| ListenResponse {
+            response_type: Some(listen_response::ResponseType::DocumentChange(
+                DocumentChange {
+                    document: Some(doc),
+                    target_ids: vec![target_id],
+                    removed_target_ids: vec![],
+                },
+            )),
+        }));
+
+        responses.push(target_change_response(
+            target_change::TargetChangeType::Current,
+            vec![target_id],
+            Some(version),
+            read_time,
+        ));
+
+        (
+            responses,
+            FirestoreInMemoryListenTarget {
+                target_id,
+                target_type,
+                since_version: version,
+            },
+        )
+    }
+
+    fn listen_input_to_responses(
+        &self,
+        listen_targets: &mut Vec<FirestoreInMemoryListenTarget>,
+        input: FirestoreInMemoryListenInput,
+    ) -> Vec<ListenResponse> {
+        match input {
+            FirestoreInMemoryListenInput::DocChange(change) => {
+                self.doc_change_to_responses(listen_targets, change)
+            }
+            FirestoreInMemoryListenInput::TargetUpdate(FirestoreListenerTargetUpdate::Add(
+                target_params,
+            )) => match target_params.target.try_into() {
+                Ok(target_id) => {
+                    listen_targets.retain(|listen_target| listen_target.target_id != target_id);
+                    let (responses, listen_target) =
+                        self.add_listen_target(target_id, target_params.target_type, Utc::now());
+                    listen_targets.push(listen_target);
+                    responses
+                }
+                Err(err) => {
+                    error!("Invalid listener target: {:?}", err);
+                    vec![]
+                }
+            },
+            FirestoreInMemoryListenInput::TargetUpdate(FirestoreListenerTargetUpdate::Remove(
+                target,
+            )) => match target.try_into() {
+                Ok(target_id) => {
+                    listen_targets.retain(|listen_target| listen_target.target_id != target_id);
+                    vec![target_change_response(
+                        target_change::TargetChangeType::Remove,
+                        vec![target_id],
+                        None,
+                        Utc::now(),
+                    )]
+                }
+                Err(err) => {
+                    error!("Invalid listener target: {:?}", err);
+                    vec![]
+                }
+            },
+        }
+    }
+
+    fn target_matches_doc(&self, target_type: &FirestoreTargetType, doc: &Document) -> bool {
+        match target_type {
+            FirestoreTargetType::Query(query_params) => self.query_matches_doc(query_params, doc),
+            FirestoreTargetType::Documents(collection_documents) => {
+                collection_documents.documents.iter().any(|document_id| {
+                    safe_document_path(
+                        collection_documents
+                            .parent
+                            .as_deref()
+                            .unwrap_or_else(|| self.get_documents_path()),
+                        collection_documents.collection.as_str(),
+                        document_id,
+                    )
+                    .map(|document_path| document_path == doc.name)
+                    .unwrap_or(false)
+                })
+            }
+        }
+    }
+
+    fn matching_target_ids(
+        &self,
+        listen_targets: &[&FirestoreInMemoryListenTarget],
+        doc: Option<&Document>,
+    ) -> Vec<i32> {
+        doc.map(|doc| {
+            listen_targets
+                .iter()
+                .filter(|listen_target| self.target_matches_doc(&listen_target.target_type, doc))
+                .map(|listen_target| listen_target.target_id)
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    fn doc_change_to_responses(
+        &self,
+        listen_targets: &[FirestoreInMemoryListenTarget],
+        change: FirestoreInMemoryDocChange,
+    ) -> Vec<ListenResponse> {
+        // Targets added after the change have already received the document state
+        let listen_targets: Vec<&FirestoreInMemoryListenTarget> = listen_targets
+            .iter()
+            .filter(|listen_target| listen_target.since_version < change.version)
+            .collect();
+        let old_target_ids = self.matching_target_ids(&listen_targets, change.old_doc.as_ref());
+        let new_target_ids = self.matching_target_ids(&listen_targets, change.new_doc.as_ref());
+        let removed_target_ids: Vec<i32> = old_target_ids
+            .iter()
+            .filter(|target_id| !new_target_ids.contains(target_id))
+            .copied()
+            .collect();
+
+        let response_type = match change.new_doc {
+            Some(new_doc) if !new_target_ids.is_empty() => Some(
+                listen_response::ResponseType::DocumentChange(DocumentChange {
+                    document: Some(new_doc),
+                    target_ids: new_target_ids,
+                    removed_target_ids,
+                }),
+            ),
+            Some(_) if !removed_target_ids.is_empty() => Some(
+                listen_response::ResponseType::DocumentRemove(DocumentRemove {
+                    document: change.name,
+                    removed_target_ids,
+                    read_time: Some(to_timestamp(change.read_time)),
+                }),
+            ),
+            None if !removed_target_ids.is_empty() => Some(
+                listen_response::ResponseType::DocumentDelete(DocumentDelete {
+                    document: change.name,
+                    removed_target_ids,
+                    read_time: Some(to_timestamp(change.read_time)),
+                }),
+            ),
+            _ => None,
+        };
+
+        match response_type {
+            Some(response_type) => vec![
+                ListenResponse {
+                    response_type: Some(response_type),
+                },
+                target_change_response(
+                    target_change::TargetChangeType::NoChange,
+                    vec![],
+                    Some(change.version),
+                    change.read_time,
+                ),
+            ],
+            None => vec![],
+        }
+    }
+}
+
+fn target_change_response(
+    target_change_type: target_change::TargetChangeType,
+    target_ids: Vec<i32>,
+    version: Option<u64>,
+    read_time: DateTime<Utc>,
+) -> ListenResponse {
+    ListenResponse {
+        response_type: Some(listen_response::ResponseType::TargetChange(TargetChange {
+            target_change_type: target_change_type.into(),
+            target_ids,
+            cause: None,
+            resume_token: version
+                .map(|version| version.to_be_bytes().to_vec())
+                .unwrap_or_default(),
+            read_time: Some(to_timestamp(read_time)),
+        })),
+    }
+}
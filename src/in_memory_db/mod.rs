@@ -0,0 +1,1010 @@
+#![allow(clippy::too_many_arguments)]
+
+mod get;
+
+mod create;
+
+mod update;
+
+mod delete;
+
+mod query;
+
+mod aggregated_query;
+
+mod list;
+
+mod listen_changes;
+
+use crate::db::safe_document_path;
+use crate::local_engine::FirestoreCacheQueryEngine;
+use crate::timestamp_utils::to_timestamp;
+use crate::*;
+use chrono::prelude::*;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use gcloud_sdk::google::firestore::v1::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Formatter;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// In-process stand-in for [`FirestoreDb`] keeping all the documents in memory.
+///
+/// It implements the same support traits as [`FirestoreDb`], so the fluent API and
+/// any code generic over those traits can be exercised in tests without the emulator.
+/// Writes are applied immediately and are visible to the active listeners.
+#[derive(Clone)]
+pub struct FirestoreInMemoryDb {
+    inner: Arc<FirestoreInMemoryDbInner>,
+}
+
+struct FirestoreInMemoryDbInner {
+    database_path: String,
+    doc_path: String,
+    options: FirestoreDbOptions,
+    state: RwLock<FirestoreInMemoryDbState>,
+    listeners: Mutex<Vec<UnboundedSender<FirestoreInMemoryDocChange>>>,
+}
+
+#[derive(Default)]
+struct FirestoreInMemoryDbState {
+    documents: BTreeMap<String, Document>,
+    version: u64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FirestoreInMemoryDocChange {
+    pub name: String,
+    pub old_doc: Option<Document>,
+    pub new_doc: Option<Document>,
+    pub version: u64,
+    pub read_time: DateTime<Utc>,
+}
+
+impl FirestoreInMemoryDb {
+    pub fn new<S>(google_project_id: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self::with_options(FirestoreDbOptions::new(
+            google_project_id.as_ref().to_string(),
+        ))
+    }
+
+    pub fn with_options(options: FirestoreDbOptions) -> Self {
+        let database_path = format!(
+            "projects/{}/databases/{}",
+            options.google_project_id, options.database_id
+        );
+        let doc_path = format!("{database_path}/documents");
+
+        Self {
+            inner: Arc::new(FirestoreInMemoryDbInner {
+                database_path,
+                doc_path,
+                options,
+                state: RwLock::new(FirestoreInMemoryDbState::default()),
+                listeners: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn get_database_path(&self) -> &String {
+        &self.inner.database_path
+    }
+
+    #[inline]
+    pub fn get_documents_path(&self) -> &String {
+        &self.inner.doc_path
+    }
+
+    #[inline]
+    pub fn parent_path<S>(
+        &self,
+        collection_name: &str,
+        document_id: S,
+    ) -> FirestoreResult<ParentPathBuilder>
+    where
+        S: AsRef<str>,
+    {
+        Ok(ParentPathBuilder::new(safe_document_path(
+            self.inner.doc_path.as_str(),
+            collection_name,
+            document_id.as_ref(),
+        )?))
+    }
+
+    #[inline]
+    pub fn get_options(&self) -> &FirestoreDbOptions {
+        &self.inner.options
+    }
+
+    /// Returns all the stored documents ordered by their names.
+    pub fn documents(&self) -> Vec<Document> {
+        self.read_state().documents.values().cloned().collect()
+    }
+
+    /// Removes all the stored documents. Active listeners receive the deletions.
+    pub fn clear(&self) {
+        let names: Vec<String> = self.read_state().documents.keys().cloned().collect();
+        for name in names {
+            self.write_doc(name.as_str(), None, |_| Ok(None)).ok();
+        }
+    }
+
+    fn read_state(&self) -> std::sync::RwLockReadGuard<'_, FirestoreInMemoryDbState> {
+        self.inner
+            .state
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn read_doc(&self, document_path: &str) -> Option<Document> {
+        self.read_state().documents.get(document_path).cloned()
+    }
+
+    pub(crate) fn find_docs<P>(&self, predicate: P) -> Vec<Document>
+    where
+        P: Fn(&Document) -> bool,
+    {
+        self.find_docs_with_version(predicate).0
+    }
+
+    /// Returns the matching documents together with the version of the state they were read at.
+    pub(crate) fn find_docs_with_version<P>(&self, predicate: P) -> (Vec<Document>, u64)
+    where
+        P: Fn(&Document) -> bool,
+    {
+        let state = self.read_state();
+        (
+            state
+                .documents
+                .values()
+                .filter(|doc| predicate(doc))
+                .cloned()
+                .collect(),
+            state.version,
+        )
+    }
+
+    /// Applies a change to a single document atomically and notifies the listeners.
+    /// The function receives the existing document and returns the new content
+    /// or `None` to delete it.
+    pub(crate) fn write_doc<F>(
+        &self,
+        document_path: &str,
+        precondition: Option<&FirestoreWritePrecondition>,
+        f: F,
+    ) -> FirestoreResult<Option<Document>>
+    where
+        F: FnOnce(Option<&Document>) -> FirestoreResult<Option<Document>>,
+    {
+        let mut state = self
+            .inner
+            .state
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let old_doc = state.documents.get(document_path).cloned();
+        check_precondition(document_path, old_doc.as_ref(), precondition)?;
+
+        let now: DateTime<Utc> = Utc::now();
+        let new_doc = f(old_doc.as_ref())?.map(|doc| Document {
+            name: document_path.to_string(),
+            create_time: old_doc
+                .as_ref()
+                .and_then(|existing| existing.create_time.clone())
+                .or_else(|| Some(to_timestamp(now))),
+            update_time: Some(to_timestamp(now)),
+            ..doc
+        });
+
+        if old_doc.is_none() && new_doc.is_none() {
+            return Ok(None);
+        }
+
+        match &new_doc {
+            Some(doc) => {
+                state
+                    .documents
+                    .insert(document_path.to_string(), doc.clone());
+            }
+            None => {
+                state.documents.remove(document_path);
+            }
+        }
+        state.version += 1;
+
+        let change = FirestoreInMemoryDocChange {
+            name: document_path.to_string(),
+            old_doc,
+            new_doc: new_doc.clone(),
+            version: state.version,
+            read_time: now,
+        };
+
+        let mut listeners = self
+            .inner
+            .listeners
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        listeners.retain(|sender| sender.send(change.clone()).is_ok());
+
+        Ok(new_doc)
+    }
+
+    pub(crate) fn subscribe(
+        &self,
+    ) -> tokio::sync::mpsc::UnboundedReceiver<FirestoreInMemoryDocChange> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.inner
+            .listeners
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    pub(crate) fn query_matches_doc(&self, params: &FirestoreQueryParams, doc: &Document) -> bool {
+        let parent = params
+            .parent
+            .as_ref()
+            .unwrap_or_else(|| self.get_documents_path());
+
+        let (collection_path, _) = split_doc_name(doc.name.as_str());
+        let (doc_parent, collection_id) = split_doc_name(collection_path);
+
+        let collection_matches = match &params.collection_id {
+            FirestoreQueryCollection::Single(single) => single == collection_id,
+            FirestoreQueryCollection::Group(group) => group.iter().any(|c| c == collection_id),
+        };
+
+        let parent_matches = doc_parent == parent
+            || (params.all_descendants.unwrap_or(false)
+                && doc_parent.starts_with(format!("{parent}/").as_str()));
+
+        collection_matches
+            && parent_matches
+            && FirestoreCacheQueryEngine::new(params).matches_doc(doc)
+    }
+
+    pub(crate) async fn query_docs(
+        &self,
+        params: &FirestoreQueryParams,
+    ) -> FirestoreResult<Vec<Document>> {
+        let found_docs = self.find_docs(|doc| self.query_matches_doc(params, doc));
+
+        let query_engine = FirestoreCacheQueryEngine::new(params);
+        let processed: Vec<Document> = query_engine
+            .process_query_stream(futures::stream::iter(found_docs.into_iter().map(Ok)).boxed())
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(processed
+            .into_iter()
+            .map(|doc| apply_return_only_fields(doc, &params.return_only_fields))
+            .collect())
+    }
+}
+
+impl std::fmt::Debug for FirestoreInMemoryDb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FirestoreInMemoryDb")
+            .field("options", &self.inner.options)
+            .field("database_path", &self.inner.database_path)
+            .field("doc_path", &self.inner.doc_path)
+            .finish()
+    }
+}
+
+pub(crate) fn split_doc_name(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(pos) => (&path[0..pos], &path[pos + 1..]),
+        None => ("", path),
+    }
+}
+
+fn check_precondition(
+    document_path: &str,
+    existing: Option<&Document>,
+    precondition: Option<&FirestoreWritePrecondition>,
+) -> FirestoreResult<()> {
+    match (precondition, existing) {
+        (Some(FirestoreWritePrecondition::Exists(true)), None) => Err(
+            gcloud_sdk::tonic::Status::not_found(format!("No document to update: {document_path}"))
+                .into(),
+        ),
+        (Some(FirestoreWritePrecondition::Exists(false)), Some(_)) => {
+            Err(gcloud_sdk::tonic::Status::already_exists(format!(
+                "Document already exists: {document_path}"
+            ))
+            .into())
+        }
+        (Some(FirestoreWritePrecondition::UpdateTime(update_time)), existing)
+            if existing.and_then(|doc| doc.update_time.as_ref())
+                != Some(&to_timestamp(*update_time)) =>
+        {
+            Err(gcloud_sdk::tonic::Status::failed_precondition(format!(
+                "The update time of the document doesn't match the precondition: {document_path}"
+            ))
+            .into())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn split_field_path(field_path: &str) -> Vec<String> {
+    field_path.split('.').map(|s| s.replace('`', "")).collect()
+}
+
+fn get_field_value<'a>(
+    fields: &'a HashMap<String, Value>,
+    field_path: &[String],
+) -> Option<&'a Value> {
+    field_path.split_first().and_then(|(head, tail)| {
+        fields.get(head).and_then(|value| {
+            if tail.is_empty() {
+                Some(value)
+            } else {
+                match &value.value_type {
+                    Some(value::ValueType::MapValue(map_value)) => {
+                        get_field_value(&map_value.fields, tail)
+                    }
+                    _ => None,
+                }
+            }
+        })
+    })
+}
+
+fn set_field_value(
+    fields: &mut HashMap<String, Value>,
+    field_path: &[String],
+    value: Option<Value>,
+) {
+    if let Some((head, tail)) = field_path.split_first() {
+        if tail.is_empty() {
+            match value {
+                Some(value) => {
+                    fields.insert(head.clone(), value);
+                }
+                None => {
+                    fields.remove(head);
+                }
+            }
+        } else {
+            let entry = fields.entry(head.clone()).or_insert_with(|| Value {
+                value_type: Some(value::ValueType::MapValue(MapValue::default())),
+            });
+            if !matches!(entry.value_type, Some(value::ValueType::MapValue(_))) {
+                entry.value_type = Some(value::ValueType::MapValue(MapValue::default()));
+            }
+            if let Some(value::ValueType::MapValue(map_value)) = &mut entry.value_type {
+                set_field_value(&mut map_value.fields, tail, value);
+            }
+        }
+    }
+}
+
+pub(crate) fn apply_return_only_fields(
+    doc: Document,
+    return_only_fields: &Option<Vec<String>>,
+) -> Document {
+    match return_only_fields {
+        Some(field_paths) => {
+            let mut fields = HashMap::new();
+            for field_path in field_paths {
+                let field_path = split_field_path(field_path);
+                if let Some(value) = get_field_value(&doc.fields, &field_path) {
+                    set_field_value(&mut fields, &field_path, Some(value.clone()));
+                }
+            }
+            Document { fields, ..doc }
+        }
+        None => doc,
+    }
+}
+
+// There are no indexes in memory, so the plan is always a collection scan
+pub(crate) async fn explain_query_results<F>(
+    options: &FirestoreExplainOptions,
+    query: F,
+) -> FirestoreResult<FirestoreExplainResult<Document>>
+where
+    F: std::future::Future<Output = FirestoreResult<Vec<Document>>>,
+{
+    let metrics =
+        FirestoreExplainMetrics::new().with_plan_summary(FirestorePlanSummary::new(vec![]));
+    if options.analyze {
+        let begin_query_utc: DateTime<Utc> = Utc::now();
+        let results = query.await?;
+        let execution_stats = FirestoreExecutionStats::new(
+            results.len() as i64,
+            results.len() as i64,
+            BTreeMap::new(),
+        )
+        .with_execution_duration(Utc::now().signed_duration_since(begin_query_utc));
+        Ok(FirestoreExplainResult::new(results)
+            .with_metrics(metrics.with_execution_stats(execution_stats)))
+    } else {
+        Ok(FirestoreExplainResult::new(vec![]).with_metrics(metrics))
+    }
+}
+
+pub(crate) fn apply_update_mask(
+    existing: Option<&Document>,
+    update: Document,
+    update_only: &Option<Vec<String>>,
+) -> Document {
+    match (existing, update_only) {
+        (existing, Some(field_paths)) => {
+            let mut fields = existing.map(|doc| doc.fields.clone()).unwrap_or_default();
+            for field_path in field_paths {
+                let field_path = split_field_path(field_path);
+                set_field_value(
+                    &mut fields,
+                    &field_path,
+                    get_field_value(&update.fields, &field_path).cloned(),
+                );
+            }
+            Document { fields, ..update }
+        }
+        (_, None) => update,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{path, paths, FirestoreQueryDirection};
+    use futures::TryStreamExt;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+    struct TestStructure {
+        some_id: String,
+        some_string: String,
+        some_num: u64,
+    }
+
+    fn test_obj(id: &str, some_num: u64) -> TestStructure {
+        TestStructure {
+            some_id: id.to_string(),
+            some_string: "Test".to_string(),
+            some_num,
+        }
+    }
+
+    async fn insert_test_objs(db: &FirestoreInMemoryDb, count: u64) -> FirestoreResult<()> {
+        for i in 0..count {
+            let id = format!("test-{i}");
+            let _: TestStructure = db
+                .fluent()
+                .insert()
+                .into("test")
+                .document_id(&id)
+                .object(&test_obj(&id, i))
+                .execute()
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn crud_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        insert_test_objs(&db, 1).await?;
+
+        let found: Option<TestStructure> = db
+            .fluent()
+            .select()
+            .by_id_in("test")
+            .obj()
+            .one("test-0")
+            .await?;
+        assert_eq!(found, Some(test_obj("test-0", 0)));
+
+        let duplicate: FirestoreResult<TestStructure> = db
+            .fluent()
+            .insert()
+            .into("test")
+            .document_id("test-0")
+            .object(&test_obj("test-0", 0))
+            .execute()
+            .await;
+        assert!(matches!(
+            duplicate,
+            Err(FirestoreError::DataConflictError(_))
+        ));
+
+        let updated: TestStructure = db
+            .fluent()
+            .update()
+            .fields(paths!(TestStructure::some_num))
+            .in_col("test")
+            .document_id("test-0")
+            .object(&TestStructure {
+                some_string: "Ignored".to_string(),
+                ..test_obj("test-0", 42)
+            })
+            .execute()
+            .await?;
+        assert_eq!(updated.some_num, 42);
+        assert_eq!(updated.some_string, "Test");
+
+        let merge_with_fields: FirestoreResult<TestStructure> = db
+            .fluent()
+            .update()
+            .fields(paths!(TestStructure::some_num))
+            .in_col("test")
+            .document_id("test-0")
+            .object(&test_obj("test-0", 43))
+            .merge()
+            .execute()
+            .await;
+        assert!(matches!(
+            merge_with_fields,
+            Err(FirestoreError::InvalidParametersError(_))
+        ));
+
+        db.fluent()
+            .delete()
+            .from("test")
+            .document_id("test-0")
+            .execute()
+            .await?;
+        assert!(db.documents().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        insert_test_objs(&db, 10).await?;
+
+        let found: Vec<TestStructure> = db
+            .fluent()
+            .select()
+            .from("test")
+            .filter(|q| q.for_all([q.field(path!(TestStructure::some_num)).greater_than(3)]))
+            .order_by([(
+                path!(TestStructure::some_num),
+                FirestoreQueryDirection::Descending,
+            )])
+            .limit(3)
+            .obj()
+            .query()
+            .await?;
+
+        assert_eq!(
+            found.iter().map(|obj| obj.some_num).collect::<Vec<_>>(),
+            vec![9, 8, 7]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn document_cursor_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        for i in 0..6 {
+            let id = format!("test-{i}");
+            let _: TestStructure = db
+                .fluent()
+                .insert()
+                .into("test")
+                .document_id(&id)
+                .object(&test_obj(&id, i / 2))
+                .execute()
+                .await?;
+        }
+        let order_by = [(
+            path!(TestStructure::some_num),
+            FirestoreQueryDirection::Descending,
+        )];
+        let ids = |docs: &[Document]| {
+            docs.iter()
+                .map(|doc| doc.name.rsplit('/').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let all_docs = db
+            .fluent()
+            .select()
+            .from("test")
+            .order_by(order_by.clone())
+            .query()
+            .await?;
+        assert_eq!(
+            ids(&all_docs),
+            vec!["test-5", "test-4", "test-3", "test-2", "test-1", "test-0"]
+        );
+
+        let after = db
+            .fluent()
+            .select()
+            .from("test")
+            .order_by(order_by.clone())
+            .start_after_doc(&all_docs[2])?
+            .query()
+            .await?;
+        assert_eq!(ids(&after), ids(&all_docs[3..]));
+
+        let before = db
+            .fluent()
+            .select()
+            .from("test")
+            .order_by(order_by.clone())
+            .end_before_doc(&all_docs[3])?
+            .query()
+            .await?;
+        assert_eq!(ids(&before), ids(&all_docs[..3]));
+
+        let mut paginated = Vec::new();
+        let mut page_token = None;
+        loop {
+            let page = db
+                .fluent()
+                .select()
+                .from("test")
+                .order_by(order_by.clone())
+                .paginate(4)
+                .opt_page_token(page_token)
+                .get_page()
+                .await?;
+            paginated.extend(page.items);
+            page_token = page.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+        assert_eq!(ids(&paginated), ids(&all_docs));
+
+        let mut limited = Vec::new();
+        let mut page_token = None;
+        loop {
+            let page = db
+                .fluent()
+                .select()
+                .from("test")
+                .order_by(order_by.clone())
+                .limit(5)
+                .paginate(2)
+                .opt_page_token(page_token)
+                .get_page()
+                .await?;
+            limited.extend(page.items);
+            page_token = page.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+        assert_eq!(ids(&limited), ids(&all_docs[..5]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn aggregated_query_test() -> FirestoreResult<()> {
+        #[derive(Debug, Deserialize)]
+        struct AggResult {
+            counter: usize,
+        }
+
+        let db = FirestoreInMemoryDb::new("test-project");
+        insert_test_objs(&db, 5).await?;
+
+        let result: Vec<AggResult> = db
+            .fluent()
+            .select()
+            .from("test")
+            .aggregate(|a| a.fields([a.field(path!(AggResult::counter)).count()]))
+            .obj()
+            .query()
+            .await?;
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].counter, 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn find_nearest_test() -> FirestoreResult<()> {
+        #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+        struct TestEmbedding {
+            some_id: String,
+            embedding: FirestoreVector,
+        }
+
+        let db = FirestoreInMemoryDb::new("test-project");
+        for (id, embedding) in [
+            ("test-0", vec![1.0, 0.0]),
+            ("test-1", vec![0.0, 1.0]),
+            ("test-2", vec![0.9, 0.1]),
+            ("test-3", vec![-1.0, 0.0]),
+        ] {
+            let _: TestEmbedding = db
+                .fluent()
+                .insert()
+                .into("test")
+                .document_id(id)
+                .object(&TestEmbedding {
+                    some_id: id.to_string(),
+                    embedding: embedding.into(),
+                })
+                .execute()
+                .await?;
+        }
+
+        let found: Vec<TestEmbedding> = db
+            .fluent()
+            .select()
+            .from("test")
+            .find_nearest(
+                path!(TestEmbedding::embedding),
+                vec![1.0, 0.0],
+                FirestoreFindNearestDistanceMeasure::Euclidean,
+                2,
+            )
+            .obj()
+            .query()
+            .await?;
+        assert_eq!(
+            found
+                .iter()
+                .map(|obj| obj.some_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["test-0", "test-2"]
+        );
+        assert_eq!(found[0].embedding, FirestoreVector(vec![1.0, 0.0]));
+
+        let found: Vec<TestEmbedding> = db
+            .fluent()
+            .select()
+            .from("test")
+            .find_nearest(
+                path!(TestEmbedding::embedding),
+                vec![0.0, 1.0],
+                FirestoreFindNearestDistanceMeasure::DotProduct,
+                1,
+            )
+            .obj()
+            .query()
+            .await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].some_id, "test-1");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn explain_query_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        insert_test_objs(&db, 5).await?;
+
+        let planned = db
+            .fluent()
+            .select()
+            .from("test")
+            .explain(false)
+            .obj::<TestStructure>()
+            .query()
+            .await?;
+        assert!(planned.results.is_empty());
+        let metrics = planned.metrics.expect("explain metrics");
+        assert!(metrics.plan_summary.is_some());
+        assert!(metrics.execution_stats.is_none());
+
+        let analyzed = db
+            .fluent()
+            .select()
+            .from("test")
+            .filter(|q| q.for_all([q.field(path!(TestStructure::some_num)).less_than(3)]))
+            .explain(true)
+            .obj::<TestStructure>()
+            .query()
+            .await?;
+        assert_eq!(analyzed.results.len(), 3);
+        let stats = analyzed
+            .metrics
+            .and_then(|metrics| metrics.execution_stats)
+            .expect("execution stats");
+        assert_eq!(stats.results_returned, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        insert_test_objs(&db, 5).await?;
+
+        let listed: Vec<TestStructure> = db
+            .fluent()
+            .list()
+            .from("test")
+            .page_size(2)
+            .obj()
+            .stream_all()
+            .await?
+            .collect()
+            .await;
+        assert_eq!(listed.len(), 5);
+
+        let collection_ids: Vec<String> = db
+            .fluent()
+            .list()
+            .collections()
+            .stream_all()
+            .await?
+            .collect()
+            .await;
+        assert_eq!(collection_ids, vec!["test".to_string()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn recursive_delete_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        insert_test_objs(&db, 3).await?;
+
+        let parent_path = db.parent_path("test", "test-0")?;
+        for i in 0..2 {
+            let id = format!("nested-{i}");
+            let _: TestStructure = db
+                .fluent()
+                .insert()
+                .into("nested")
+                .document_id(&id)
+                .parent(&parent_path)
+                .object(&test_obj(&id, i))
+                .execute()
+                .await?;
+        }
+
+        let deleted = db
+            .fluent()
+            .delete()
+            .recursive()
+            .from("test")
+            .document_id("test-0")
+            .execute()
+            .await?;
+        assert_eq!(deleted.deleted_documents, 3);
+        assert_eq!(db.documents().len(), 2);
+
+        let deleted = db
+            .fluent()
+            .delete()
+            .recursive()
+            .from("test")
+            .document_id("missing")
+            .execute()
+            .await?;
+        assert_eq!(deleted.deleted_documents, 0);
+
+        let deleted = db
+            .fluent()
+            .delete()
+            .recursive()
+            .collection("test")
+            .execute()
+            .await?;
+        assert_eq!(deleted.deleted_documents, 2);
+        assert!(db.documents().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn modify_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        insert_test_objs(&db, 1).await?;
+        let document_path = db.parent_path("test", "test-0")?.to_string();
+
+        let mut attempts = 0;
+        let modified: TestStructure = db
+            .fluent()
+            .update()
+            .in_col("test")
+            .document_id("test-0")
+            .modify(|current: TestStructure| {
+                attempts += 1;
+                // The cycle is retried by the builder only
+                assert_eq!(
+                    crate::db::current_request_retry_policy().map(|policy| policy.max_retries),
+                    Some(0)
+                );
+                if attempts == 1 {
+                    // A concurrent change makes the first attempt fail
+                    db.write_doc(&document_path, None, |doc| Ok(doc.cloned()))
+                        .unwrap();
+                }
+                TestStructure {
+                    some_num: current.some_num + 10,
+                    ..current
+                }
+            })
+            .retry_policy(
+                FirestoreRetryPolicy::new()
+                    .with_initial_interval(std::time::Duration::from_millis(1)),
+            )
+            .execute()
+            .await?;
+
+        assert_eq!(attempts, 2);
+        assert_eq!(modified.some_num, 10);
+
+        let missing: FirestoreResult<TestStructure> = db
+            .fluent()
+            .update()
+            .in_col("test")
+            .document_id("test-1")
+            .modify(|current: TestStructure| current)
+            .execute()
+            .await;
+        assert!(missing.is_err());
+
+        // Without a policy on the builder the database policy is used
+        let no_retries_db = FirestoreInMemoryDb::with_options(
+            FirestoreDbOptions::new("test-project".to_string())
+                .with_retry_policy(FirestoreRetryPolicy::no_retries()),
+        );
+        insert_test_objs(&no_retries_db, 1).await?;
+        let mut conflicting_attempts = 0;
+        let conflict: FirestoreResult<TestStructure> = no_retries_db
+            .fluent()
+            .update()
+            .in_col("test")
+            .document_id("test-0")
+            .modify(|current: TestStructure| {
+                conflicting_attempts += 1;
+                no_retries_db
+                    .write_doc(&document_path, None, |doc| Ok(doc.cloned()))
+                    .unwrap();
+                current
+            })
+            .execute()
+            .await;
+        assert!(conflict.is_err());
+        assert_eq!(conflicting_attempts, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listen_changes_test() -> FirestoreResult<()> {
+        let db = FirestoreInMemoryDb::new("test-project");
+        let target = FirestoreListenerTargetParams::new(
+            FirestoreListenerTarget::new(1),
+            FirestoreTargetType::Query(FirestoreQueryParams::new("test".into())),
+            HashMap::new(),
+        );
+
+        let mut changes = db.listen_doc_changes(vec![target]).await?;
+        insert_test_objs(&db, 1).await?;
+
+        let mut received_doc = None;
+        while let Some(response) = changes.try_next().await? {
+            if let Some(listen_response::ResponseType::DocumentChange(change)) =
+                response.response_type
+            {
+                received_doc = change.document;
+                break;
+            }
+        }
+
+        assert_eq!(
+            received_doc.map(|doc| doc.name),
+            Some(format!("{}/test/test-0", db.get_documents_path()))
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use crate::in_memory_db::explain_query_results;
+use crate::*;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::FutureExt;
+use futures::{future, StreamExt, TryStreamExt};
+use gcloud_sdk::google::firestore::v1::*;
+use serde::Deserialize;
+use tracing::*;
+
+#[async_trait]
+impl FirestoreQuerySupport for FirestoreInMemoryDb {
+    async fn query_doc(&self, params: FirestoreQueryParams) -> FirestoreResult<Vec<Document>> {
+        self.query_docs(&params).await
+    }
+
+    async fn stream_query_doc<'a>(
+        &'a self,
+        params: FirestoreQueryParams,
+    ) -> FirestoreResult<BoxStream<'a, Document>> {
+        let docs = self.query_docs(&params).await?;
+        Ok(futures::stream::iter(docs).boxed())
+    }
+
+    async fn stream_query_doc_with_errors<'a>(
+        &'a self,
+        params: FirestoreQueryParams,
+    ) -> FirestoreResult<BoxStream<'a, FirestoreResult<Document>>> {
+        let docs = self.query_docs(&params).await?;
+        Ok(futures::stream::iter(docs.into_iter().map(Ok)).boxed())
+    }
+
+    async fn query_obj<T>(&self, params: FirestoreQueryParams) -> FirestoreResult<Vec<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        self.query_docs(&params)
+            .await?
+            .iter()
+            .map(|doc| FirestoreDb::deserialize_doc_to(doc))
+            .collect()
+    }
+
+    async fn stream_query_obj<'a, T>(
+        &'a self,
+        params: FirestoreQueryParams,
+    ) -> FirestoreResult<BoxStream<'a, T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let doc_stream = self.stream_query_doc(params).await?;
+        Ok(Box::pin(doc_stream.filter_map(|doc| async move {
+            match FirestoreDb::deserialize_doc_to::<T>(&doc) {
+                Ok(obj) => Some(obj),
+                Err(err) => {
+                    error!(
+                        "Error occurred while converting query document in a stream: {}",
+                        err
+                    );
+                    None
+                }
+            }
+        })))
+    }
+
+    async fn stream_query_obj_with_errors<'a, T>(
+        &'a self,
+        params: FirestoreQueryParams,
+    ) -> FirestoreResult<BoxStream<'a, FirestoreResult<T>>>
+    where
+        for<'de> T: Deserialize<'de>,
+        T: Send + 'a,
+    {
+        let doc_stream = self.stream_query_doc_with_errors(params).await?;
+        Ok(Box::pin(doc_stream.and_then(|doc| {
+            future::ready(FirestoreDb::deserialize_doc_to::<T>(&doc))
+        })))
+    }
+
+    async fn explain_query_doc(
+        &self,
+        params: FirestoreQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<Document>> {
+        explain_query_results(&options, self.query_docs(&params)).await
+    }
+
+    async fn explain_query_obj<T>(
+        &self,
+        params: FirestoreQueryParams,
+        options: FirestoreExplainOptions,
+    ) -> FirestoreResult<FirestoreExplainResult<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let explain_result = self.explain_query_doc(params, options).await?;
+        Ok(FirestoreExplainResult::new(
+            explain_result
+                .results
+                .iter()
+                .map(|doc| FirestoreDb::deserialize_doc_to(doc))
+                .collect::<FirestoreResult<Vec<T>>>()?,
+        )
+        .opt_metrics(explain_result.metrics))
+    }
+
+    fn stream_partition_cursors_with_errors(
+        &self,
+        _params: FirestorePartitionQueryParams,
+    ) -> BoxFuture<'_, FirestoreResult<PeekableBoxStream<'_, FirestoreResult<FirestoreQueryCursor>>>>
+    {
+        // The whole collection is always a single partition in memory
+        async move { Ok(futures::stream::empty().boxed().peekable()) }.boxed()
+    }
+
+    async fn stream_partition_query_doc_with_errors(
+        &self,
+        _parallelism: usize,
+        partition_params: FirestorePartitionQueryParams,
+    ) -> FirestoreResult<BoxStream<FirestoreResult<(FirestorePartition, Document)>>> {
+        let docs = self.query_docs(&partition_params.query_params).await?;
+        Ok(futures::stream::iter(
+            docs.into_iter()
+                .map(|doc| Ok((FirestorePartition::new(), doc))),
+        )
+        .boxed())
+    }
+
+    async fn stream_partition_query_obj_with_errors<'a, T>(
+        &'a self,
+        parallelism: usize,
+        partition_params: FirestorePartitionQueryParams,
+    ) -> FirestoreResult<BoxStream<'a, FirestoreResult<(FirestorePartition, T)>>>
+    where
+        for<'de> T: Deserialize<'de>,
+        T: Send + 'a,
+    {
+        let doc_stream = self
+            .stream_partition_query_doc_with_errors(parallelism, partition_params)
+            .await?;
+
+        Ok(Box::pin(doc_stream.and_then(|(partition, doc)| {
+            future::ready(FirestoreDb::deserialize_doc_to::<T>(&doc).map(|obj| (partition, obj)))
+        })))
+    }
+}
@@ -0,0 +1,99 @@
+use crate::db::safe_document_path;
+use crate::in_memory_db::{apply_return_only_fields, apply_update_mask};
+use crate::*;
+use async_trait::async_trait;
+use gcloud_sdk::google::firestore::v1::*;
+use serde::{Deserialize, Serialize};
+
+#[async_trait]
+impl FirestoreUpdateSupport for FirestoreInMemoryDb {
+    async fn update_obj<I, O, S>(
+        &self,
+        collection_id: &str,
+        document_id: S,
+        obj: &I,
+        update_only: Option<Vec<String>>,
+        return_only_fields: Option<Vec<String>>,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> FirestoreResult<O>
+    where
+        I: Serialize + Sync + Send,
+        for<'de> O: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        self.update_obj_at(
+            self.get_documents_path().as_str(),
+            collection_id,
+            document_id,
+            obj,
+            update_only,
+            return_only_fields,
+            precondition,
+        )
+        .await
+    }
+
+    async fn update_obj_at<I, O, S>(
+        &self,
+        parent: &str,
+        collection_id: &str,
+        document_id: S,
+        obj: &I,
+        update_only: Option<Vec<String>>,
+        return_only_fields: Option<Vec<String>>,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> FirestoreResult<O>
+    where
+        I: Serialize + Sync + Send,
+        for<'de> O: Deserialize<'de>,
+        S: AsRef<str> + Send,
+    {
+        let firestore_doc = FirestoreDb::serialize_to_doc(
+            safe_document_path(parent, collection_id, document_id.as_ref())?.as_str(),
+            obj,
+        )?;
+
+        let doc = self
+            .update_doc(
+                collection_id,
+                firestore_doc,
+                update_only,
+                return_only_fields,
+                precondition,
+            )
+            .await?;
+
+        FirestoreDb::deserialize_doc_to(&doc)
+    }
+
+    async fn update_doc(
+        &self,
+        _collection_id: &str,
+        firestore_doc: Document,
+        update_only: Option<Vec<String>>,
+        return_only_fields: Option<Vec<String>>,
+        precondition: Option<FirestoreWritePrecondition>,
+    ) -> FirestoreResult<Document> {
+        let document_path = firestore_doc.name.clone();
+
+        let doc = self.write_doc(document_path.as_str(), precondition.as_ref(), |existing| {
+            Ok(Some(apply_update_mask(
+                existing,
+                firestore_doc,
+                &update_only,
+            )))
+        })?;
+
+        Ok(doc
+            .map(|doc| apply_return_only_fields(doc, &return_only_fields))
+            .unwrap_or_default())
+    }
+
+    fn get_retry_policy(&self) -> FirestoreRetryPolicy {
+        crate::db::current_request_retry_policy()
+            .or_else(|| self.get_options().retry_policy.clone())
+            .unwrap_or_else(|| {
+                FirestoreRetryPolicy::new().with_max_retries(self.get_options().max_retries)
+            })
+    }
+}
@@ -134,6 +134,11 @@ pub use db::*;
 mod firestore_serde;
 pub use firestore_serde::*;
 
+#[cfg(feature = "json")]
+mod firestore_json;
+#[cfg(feature = "json")]
+pub use firestore_json::*;
+
 mod struct_path_macro;
 use crate::errors::FirestoreError;
 pub use struct_path_macro::*;
@@ -150,6 +155,11 @@ pub use firestore_document_functions::*;
 mod fluent_api;
 pub use fluent_api::*;
 
+mod local_engine;
+
+mod in_memory_db;
+pub use in_memory_db::*;
+
 pub extern crate struct_path;
 
 #[cfg(feature = "caching")]
@@ -244,7 +244,7 @@ impl<'a> FirestoreCacheFilterEngine<'a> {
     }
 }
 
-pub(super) enum CompareOp {
+pub(crate) enum CompareOp {
     Equal,
     NotEqual,
     LessThan,
@@ -257,7 +257,7 @@ pub(super) enum CompareOp {
     NotIn,
 }
 
-pub(super) fn compare_values(
+pub(crate) fn compare_values(
     op: CompareOp,
     a: &gcloud_sdk::google::firestore::v1::value::ValueType,
     b: &gcloud_sdk::google::firestore::v1::value::ValueType,
@@ -350,7 +350,7 @@ pub(super) fn compare_values(
             gcloud_sdk::google::firestore::v1::value::ValueType::DoubleValue(b_val),
         ) => a_val >= b_val,
 
-        // For TimestampValue, assumes it's a numerical timestamp; if it's a string or date type, adjust accordingly
+        // For TimestampValue
         (
             CompareOp::Equal,
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(a_val),
@@ -367,27 +367,27 @@ pub(super) fn compare_values(
             CompareOp::LessThan,
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(a_val),
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(b_val),
-        ) => a_val.seconds < b_val.seconds && a_val.nanos < b_val.nanos,
+        ) => (a_val.seconds, a_val.nanos) < (b_val.seconds, b_val.nanos),
 
         (
             CompareOp::LessThanOrEqual,
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(a_val),
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(b_val),
-        ) => a_val.seconds <= b_val.seconds && a_val.nanos <= b_val.nanos,
+        ) => (a_val.seconds, a_val.nanos) <= (b_val.seconds, b_val.nanos),
 
         (
             CompareOp::GreaterThan,
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(a_val),
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(b_val),
-        ) => a_val.seconds > b_val.seconds && a_val.nanos > b_val.nanos,
+        ) => (a_val.seconds, a_val.nanos) > (b_val.seconds, b_val.nanos),
 
         (
             CompareOp::GreaterThanOrEqual,
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(a_val),
             gcloud_sdk::google::firestore::v1::value::ValueType::TimestampValue(b_val),
-        ) => a_val.seconds >= b_val.seconds && a_val.nanos >= b_val.nanos,
+        ) => (a_val.seconds, a_val.nanos) >= (b_val.seconds, b_val.nanos),
 
-        // For StringType only Equal, NotEqual operations make sense in a general context
+        // For StringValue
         (
             CompareOp::Equal,
             gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(a_val),
@@ -400,22 +400,77 @@ pub(super) fn compare_values(
             gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(b_val),
         ) => a_val != b_val,
 
+        (
+            CompareOp::LessThan,
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(b_val),
+        ) => a_val < b_val,
+
+        (
+            CompareOp::LessThanOrEqual,
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(b_val),
+        ) => a_val <= b_val,
+
+        (
+            CompareOp::GreaterThan,
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(b_val),
+        ) => a_val > b_val,
+
+        (
+            CompareOp::GreaterThanOrEqual,
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(b_val),
+        ) => a_val >= b_val,
+
+        // For ReferenceValue, compared by the path segments
+        (
+            CompareOp::Equal,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(b_val),
+        ) => a_val == b_val,
+
+        (
+            CompareOp::NotEqual,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(b_val),
+        ) => a_val != b_val,
+
+        (
+            CompareOp::LessThan,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(b_val),
+        ) => a_val.split('/').lt(b_val.split('/')),
+
+        (
+            CompareOp::LessThanOrEqual,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(b_val),
+        ) => a_val.split('/').le(b_val.split('/')),
+
+        (
+            CompareOp::GreaterThan,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(b_val),
+        ) => a_val.split('/').gt(b_val.split('/')),
+
+        (
+            CompareOp::GreaterThanOrEqual,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(a_val),
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(b_val),
+        ) => a_val.split('/').ge(b_val.split('/')),
+
         //  Array Operation
         (
             CompareOp::ArrayContains,
             gcloud_sdk::google::firestore::v1::value::ValueType::ArrayValue(a_val),
-            gcloud_sdk::google::firestore::v1::value::ValueType::ArrayValue(b_val),
+            b_val,
         ) => a_val
             .values
             .iter()
             .flat_map(|v| &v.value_type)
-            .any(|a_val| {
-                b_val
-                    .values
-                    .iter()
-                    .flat_map(|v| &v.value_type)
-                    .all(|b_val| compare_values(CompareOp::Equal, a_val, b_val))
-            }),
+            .any(|a_val| compare_values(CompareOp::Equal, a_val, b_val)),
 
         (
             CompareOp::ArrayContainsAny,
@@ -435,23 +490,23 @@ pub(super) fn compare_values(
 
         (
             CompareOp::In,
-            gcloud_sdk::google::firestore::v1::value::ValueType::ArrayValue(a_val),
-            b_val,
-        ) => a_val
+            a_val,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ArrayValue(b_val),
+        ) => b_val
             .values
             .iter()
             .flat_map(|v| &v.value_type)
-            .any(|a_val| compare_values(CompareOp::Equal, a_val, b_val)),
+            .any(|b_val| compare_values(CompareOp::Equal, a_val, b_val)),
 
         (
             CompareOp::NotIn,
-            gcloud_sdk::google::firestore::v1::value::ValueType::ArrayValue(a_val),
-            b_val,
-        ) => a_val
+            a_val,
+            gcloud_sdk::google::firestore::v1::value::ValueType::ArrayValue(b_val),
+        ) => !b_val
             .values
             .iter()
             .flat_map(|v| &v.value_type)
-            .any(|a_val| !compare_values(CompareOp::Equal, a_val, b_val)),
+            .any(|b_val| compare_values(CompareOp::Equal, a_val, b_val)),
 
         // Any other combinations result in false
         _ => false,
@@ -0,0 +1,4 @@
+pub(crate) mod filter;
+
+pub(crate) mod query;
+pub(crate) use query::FirestoreCacheQueryEngine;
@@ -0,0 +1,419 @@
+use crate::db::firestore_order_by_with_document_name;
+use crate::firestore_serde::firestore_vector_from_value;
+use crate::local_engine::filter::*;
+use crate::*;
+use futures::stream::BoxStream;
+use futures::stream::StreamExt;
+use futures::{future, TryStreamExt};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+#[derive(Clone)]
+pub struct FirestoreCacheQueryEngine {
+    pub query: FirestoreQueryParams,
+}
+
+impl FirestoreCacheQueryEngine {
+    pub fn new(query: &FirestoreQueryParams) -> Self {
+        Self {
+            query: query.clone(),
+        }
+    }
+
+    #[cfg(feature = "caching")]
+    pub fn params_supported(&self) -> bool {
+        self.query.all_descendants.iter().all(|x| !*x)
+    }
+
+    pub fn matches_doc(&self, doc: &FirestoreDocument) -> bool {
+        if let Some(filter) = &self.query.filter {
+            let filter_engine = FirestoreCacheFilterEngine::new(filter);
+            filter_engine.matches_doc(doc)
+        } else {
+            true
+        }
+    }
+
+    pub async fn sort_stream<'b>(
+        &self,
+        input: BoxStream<'b, FirestoreResult<FirestoreDocument>>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<FirestoreDocument>>> {
+        if let Some(order_by) = &self.query.order_by {
+            // The results with equal values are ordered by the document names like on the server
+            let order_by =
+                firestore_order_by_with_document_name(order_by, self.query.filter.as_ref());
+            let mut collected: Vec<FirestoreDocument> = input.try_collect().await?;
+            collected.sort_by(|doc_a, doc_b| {
+                for sort_field in order_by.iter() {
+                    let ordering = compare_ordered_values(
+                        doc_order_value(doc_a, &sort_field.field_name).as_deref(),
+                        doc_order_value(doc_b, &sort_field.field_name).as_deref(),
+                        &sort_field.direction,
+                    );
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                Ordering::Equal
+            });
+            Ok(futures::stream::iter(collected.into_iter().map(Ok)).boxed())
+        } else {
+            Ok(input)
+        }
+    }
+
+    pub async fn limit_stream<'b>(
+        &self,
+        input: BoxStream<'b, FirestoreResult<FirestoreDocument>>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<FirestoreDocument>>> {
+        if let Some(limit) = self.query.limit {
+            Ok(input
+                .scan(0_u32, move |index, doc| {
+                    if *index < limit {
+                        *index += 1;
+                        future::ready(Some(doc))
+                    } else {
+                        future::ready(None)
+                    }
+                })
+                .boxed())
+        } else {
+            Ok(input)
+        }
+    }
+
+    pub async fn offset_stream<'b>(
+        &self,
+        input: BoxStream<'b, FirestoreResult<FirestoreDocument>>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<FirestoreDocument>>> {
+        if let Some(offset) = self.query.offset {
+            Ok(input.skip(offset as usize).boxed())
+        } else {
+            Ok(input)
+        }
+    }
+
+    pub async fn start_at_stream<'b>(
+        &self,
+        input: BoxStream<'b, FirestoreResult<FirestoreDocument>>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<FirestoreDocument>>> {
+        if let (Some(start_at), Some(order_by)) = (&self.query.start_at, &self.query.order_by) {
+            let start_at = start_at.clone();
+            let order_by = order_by.clone();
+            Ok(input
+                .skip_while(move |doc_res| match doc_res {
+                    Ok(doc) => {
+                        let ordering = compare_doc_with_cursor(doc, &start_at, &order_by);
+                        future::ready(match start_at {
+                            FirestoreQueryCursor::BeforeValue(_) => ordering == Ordering::Less,
+                            FirestoreQueryCursor::AfterValue(_) => ordering != Ordering::Greater,
+                        })
+                    }
+                    Err(_) => future::ready(false),
+                })
+                .boxed())
+        } else {
+            Ok(input)
+        }
+    }
+
+    pub async fn end_at_stream<'b>(
+        &self,
+        input: BoxStream<'b, FirestoreResult<FirestoreDocument>>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<FirestoreDocument>>> {
+        if let (Some(end_at), Some(order_by)) = (&self.query.end_at, &self.query.order_by) {
+            let end_at = end_at.clone();
+            let order_by = order_by.clone();
+            Ok(input
+                .take_while(move |doc_res| match doc_res {
+                    Ok(doc) => {
+                        let ordering = compare_doc_with_cursor(doc, &end_at, &order_by);
+                        future::ready(match end_at {
+                            FirestoreQueryCursor::BeforeValue(_) => ordering == Ordering::Less,
+                            FirestoreQueryCursor::AfterValue(_) => ordering != Ordering::Greater,
+                        })
+                    }
+                    Err(_) => future::ready(false),
+                })
+                .boxed())
+        } else {
+            Ok(input)
+        }
+    }
+
+    pub async fn find_nearest_stream<'b>(
+        &self,
+        input: BoxStream<'b, FirestoreResult<FirestoreDocument>>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<FirestoreDocument>>> {
+        if let Some(find_nearest) = &self.query.find_nearest {
+            let collected: Vec<FirestoreDocument> = input.try_collect().await?;
+            let query_vector = &find_nearest.query_vector.0;
+            let mut nearest: Vec<(f64, FirestoreDocument)> = collected
+                .into_iter()
+                .filter_map(|doc| {
+                    firestore_doc_get_field_by_path(&doc, &find_nearest.field_name)
+                        .and_then(firestore_vector_from_value)
+                        .and_then(|doc_vector| {
+                            vector_distance(
+                                find_nearest.distance_measure,
+                                query_vector,
+                                &doc_vector,
+                            )
+                        })
+                        .map(|distance| (distance, doc))
+                })
+                .collect();
+
+            // Dot product is a similarity, so the larger values are the nearest ones
+            nearest.sort_by(|(distance_a, _), (distance_b, _)| {
+                match find_nearest.distance_measure {
+                    FirestoreFindNearestDistanceMeasure::DotProduct => {
+                        distance_b.total_cmp(distance_a)
+                    }
+                    _ => distance_a.total_cmp(distance_b),
+                }
+            });
+            nearest.truncate(find_nearest.neighbors_limit as usize);
+
+            Ok(futures::stream::iter(nearest.into_iter().map(|(_, doc)| Ok(doc))).boxed())
+        } else {
+            Ok(input)
+        }
+    }
+
+    pub async fn process_query_stream<'b>(
+        &self,
+        input: BoxStream<'b, FirestoreResult<FirestoreDocument>>,
+    ) -> FirestoreResult<BoxStream<'b, FirestoreResult<FirestoreDocument>>> {
+        let input = self.sort_stream(input).await?;
+        let input = self.find_nearest_stream(input).await?;
+        let input = self.start_at_stream(input).await?;
+        let input = self.end_at_stream(input).await?;
+        let input = self.offset_stream(input).await?;
+        let input = self.limit_stream(input).await?;
+        Ok(input)
+    }
+}
+
+fn vector_distance(
+    distance_measure: FirestoreFindNearestDistanceMeasure,
+    vector_a: &[f64],
+    vector_b: &[f64],
+) -> Option<f64> {
+    if vector_a.len() != vector_b.len() {
+        return None;
+    }
+
+    let dot_product: f64 = vector_a.iter().zip(vector_b).map(|(a, b)| a * b).sum();
+    match distance_measure {
+        FirestoreFindNearestDistanceMeasure::Euclidean => Some(
+            vector_a
+                .iter()
+                .zip(vector_b)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f64>()
+                .sqrt(),
+        ),
+        FirestoreFindNearestDistanceMeasure::Cosine => {
+            let magnitude_a = vector_a.iter().map(|a| a * a).sum::<f64>().sqrt();
+            let magnitude_b = vector_b.iter().map(|b| b * b).sum::<f64>().sqrt();
+            if magnitude_a == 0.0 || magnitude_b == 0.0 {
+                None
+            } else {
+                Some(1.0 - dot_product / (magnitude_a * magnitude_b))
+            }
+        }
+        FirestoreFindNearestDistanceMeasure::DotProduct => Some(dot_product),
+    }
+}
+
+/// Field value used for ordering, including the document name for `__name__`.
+fn doc_order_value<'d>(
+    doc: &'d FirestoreDocument,
+    field_name: &str,
+) -> Option<Cow<'d, gcloud_sdk::google::firestore::v1::value::ValueType>> {
+    if field_name == FIRESTORE_DOCUMENT_NAME_FIELD {
+        Some(Cow::Owned(
+            gcloud_sdk::google::firestore::v1::value::ValueType::ReferenceValue(doc.name.clone()),
+        ))
+    } else {
+        firestore_doc_get_field_by_path(doc, field_name).map(Cow::Borrowed)
+    }
+}
+
+fn compare_ordered_values(
+    a: Option<&gcloud_sdk::google::firestore::v1::value::ValueType>,
+    b: Option<&gcloud_sdk::google::firestore::v1::value::ValueType>,
+    direction: &FirestoreQueryDirection,
+) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let ordering = if compare_values(CompareOp::Equal, a, b) {
+                Ordering::Equal
+            } else if compare_values(CompareOp::LessThan, a, b) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+            match direction {
+                FirestoreQueryDirection::Ascending => ordering,
+                FirestoreQueryDirection::Descending => ordering.reverse(),
+            }
+        }
+        _ => Ordering::Equal,
+    }
+}
+
+/// Position of the document relative to the cursor in the query order.
+/// Cursors shorter than the ordering are compared by the prefix.
+fn compare_doc_with_cursor(
+    doc: &FirestoreDocument,
+    cursor: &FirestoreQueryCursor,
+    order_by: &[FirestoreQueryOrder],
+) -> Ordering {
+    let values = match cursor {
+        FirestoreQueryCursor::BeforeValue(values) | FirestoreQueryCursor::AfterValue(values) => {
+            values
+        }
+    };
+    for (value, ordered_field) in values.iter().zip(order_by) {
+        let ordering = compare_ordered_values(
+            doc_order_value(doc, &ordered_field.field_name).as_deref(),
+            value.value.value_type.as_ref(),
+            &ordered_field.direction,
+        );
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gcloud_sdk::google::firestore::v1::value::ValueType;
+    use gcloud_sdk::google::firestore::v1::Value;
+
+    fn timestamp_value(seconds: i64, nanos: i32) -> FirestoreValue {
+        FirestoreValue::from(Value {
+            value_type: Some(ValueType::TimestampValue(
+                gcloud_sdk::prost_types::Timestamp { seconds, nanos },
+            )),
+        })
+    }
+
+    fn test_doc(id: &str, some_num: i64, some_string: &str) -> FirestoreDocument {
+        FirestoreDb::serialize_map_to_doc(
+            format!("projects/p/databases/(default)/documents/test/{id}"),
+            [
+                ("some_num", some_num.into()),
+                ("some_string", some_string.into()),
+                ("tags", vec!["a", "b"].into()),
+                ("created_at", timestamp_value(10, 900)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn matches(doc: &FirestoreDocument, filter: FirestoreQueryFilterCompare) -> bool {
+        FirestoreCacheQueryEngine::new(
+            &FirestoreQueryParams::new("test".into())
+                .with_filter(FirestoreQueryFilter::Compare(Some(filter))),
+        )
+        .matches_doc(doc)
+    }
+
+    #[test]
+    fn compare_filters_test() {
+        let doc = test_doc("doc-1", 1, "b");
+
+        // Timestamps are compared by seconds first and nanos only for equal seconds
+        assert!(matches(
+            &doc,
+            FirestoreQueryFilterCompare::LessThan("created_at".into(), timestamp_value(11, 100))
+        ));
+        assert!(!matches(
+            &doc,
+            FirestoreQueryFilterCompare::GreaterThan("created_at".into(), timestamp_value(11, 100))
+        ));
+
+        assert!(matches(
+            &doc,
+            FirestoreQueryFilterCompare::GreaterThan("some_string".into(), "a".into())
+        ));
+        assert!(!matches(
+            &doc,
+            FirestoreQueryFilterCompare::LessThanOrEqual("some_string".into(), "a".into())
+        ));
+
+        assert!(matches(
+            &doc,
+            FirestoreQueryFilterCompare::ArrayContains("tags".into(), "b".into())
+        ));
+        assert!(!matches(
+            &doc,
+            FirestoreQueryFilterCompare::ArrayContains("tags".into(), "c".into())
+        ));
+
+        assert!(matches(
+            &doc,
+            FirestoreQueryFilterCompare::In("some_string".into(), vec!["a", "b"].into())
+        ));
+        assert!(!matches(
+            &doc,
+            FirestoreQueryFilterCompare::In("some_string".into(), vec!["c"].into())
+        ));
+        assert!(matches(
+            &doc,
+            FirestoreQueryFilterCompare::NotIn("some_string".into(), vec!["a", "c"].into())
+        ));
+        assert!(!matches(
+            &doc,
+            FirestoreQueryFilterCompare::NotIn("some_string".into(), vec!["a", "b"].into())
+        ));
+    }
+
+    #[tokio::test]
+    async fn process_query_stream_test() -> FirestoreResult<()> {
+        let docs = vec![
+            test_doc("doc-1", 2, "a"),
+            test_doc("doc-2", 1, "b"),
+            test_doc("doc-3", 1, "a"),
+            test_doc("doc-4", 3, "c"),
+            test_doc("doc-5", 2, "b"),
+        ];
+
+        // The first ordered field takes precedence, the following fields only break the ties.
+        // The offset is applied after the cursors and before the limit.
+        let engine = FirestoreCacheQueryEngine::new(
+            &FirestoreQueryParams::new("test".into())
+                .with_order_by(vec![
+                    FirestoreQueryOrder::new(
+                        "some_num".to_string(),
+                        FirestoreQueryDirection::Ascending,
+                    ),
+                    FirestoreQueryOrder::new(
+                        "some_string".to_string(),
+                        FirestoreQueryDirection::Descending,
+                    ),
+                ])
+                .with_start_at(FirestoreQueryCursor::AfterValue(vec![1.into(), "a".into()]))
+                .with_offset(1)
+                .with_limit(2),
+        );
+
+        let result: Vec<FirestoreDocument> = engine
+            .process_query_stream(futures::stream::iter(docs.into_iter().map(Ok)).boxed())
+            .await?
+            .try_collect()
+            .await?;
+
+        let ids: Vec<&str> = result
+            .iter()
+            .filter_map(|doc| doc.name.rsplit('/').next())
+            .collect();
+        assert_eq!(ids, vec!["doc-1", "doc-4"]);
+        Ok(())
+    }
+}
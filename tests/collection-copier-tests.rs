@@ -0,0 +1,162 @@
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+mod common;
+use crate::common::setup;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+struct MyTestStructure {
+    some_id: String,
+    some_num: i64,
+}
+
+const TEST_SOURCE_COLLECTION_NAME: &str = "integration-test-copier-source";
+const TEST_TARGET_COLLECTION_NAME: &str = "integration-test-copier-target";
+const TEST_CHILD_COLLECTION_NAME: &str = "children";
+
+async fn populate_collections(
+    db: &FirestoreDb,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    for collection_name in [TEST_SOURCE_COLLECTION_NAME, TEST_TARGET_COLLECTION_NAME] {
+        db.fluent()
+            .delete()
+            .recursive()
+            .collection(collection_name)
+            .execute()
+            .await?;
+    }
+
+    for i in 1..=3 {
+        let my_struct = MyTestStructure {
+            some_id: format!("test-{i}"),
+            some_num: i,
+        };
+        db.fluent()
+            .update()
+            .in_col(TEST_SOURCE_COLLECTION_NAME)
+            .document_id(&my_struct.some_id)
+            .object(&my_struct)
+            .execute::<()>()
+            .await?;
+    }
+
+    db.fluent()
+        .update()
+        .in_col(TEST_CHILD_COLLECTION_NAME)
+        .document_id("test-child")
+        .parent(db.parent_path(TEST_SOURCE_COLLECTION_NAME, "test-1")?)
+        .object(&MyTestStructure {
+            some_id: "test-child".to_string(),
+            some_num: 10,
+        })
+        .execute::<()>()
+        .await?;
+
+    // The document existing in the target before copying
+    db.fluent()
+        .update()
+        .in_col(TEST_TARGET_COLLECTION_NAME)
+        .document_id("test-2")
+        .object(&MyTestStructure {
+            some_id: "test-2".to_string(),
+            some_num: 100,
+        })
+        .execute::<()>()
+        .await?;
+
+    Ok(())
+}
+
+async fn get_doc(
+    db: &FirestoreDb,
+    collection_name: &str,
+    document_id: &str,
+) -> Result<Option<MyTestStructure>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(db
+        .fluent()
+        .select()
+        .by_id_in(collection_name)
+        .obj()
+        .one(document_id)
+        .await?)
+}
+
+#[tokio::test]
+async fn collection_copier_tests() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let db = setup().await?;
+
+    // Overwriting the existing documents without the subcollections
+    populate_collections(&db).await?;
+
+    let result = db
+        .create_collection_copier(&db)
+        .copy_collection(TEST_SOURCE_COLLECTION_NAME, TEST_TARGET_COLLECTION_NAME)
+        .await?;
+    assert_eq!(result.copied_documents, 3);
+    assert_eq!(result.skipped_documents, 0);
+    assert_eq!(result.deleted_documents, 0);
+    assert!(result.failures.is_empty());
+
+    assert_eq!(
+        get_doc(&db, TEST_TARGET_COLLECTION_NAME, "test-2")
+            .await?
+            .map(|doc| doc.some_num),
+        Some(2)
+    );
+    let target_children: Vec<MyTestStructure> = db
+        .fluent()
+        .select()
+        .from(TEST_CHILD_COLLECTION_NAME)
+        .parent(db.parent_path(TEST_TARGET_COLLECTION_NAME, "test-1")?)
+        .obj()
+        .query()
+        .await?;
+    assert!(target_children.is_empty());
+
+    // Skipping the existing documents, copying recursively and deleting the copied documents
+    populate_collections(&db).await?;
+
+    let result = db
+        .create_collection_copier_with_options(
+            &db,
+            FirestoreCollectionCopierOptions::new()
+                .with_recursive(true)
+                .with_existing_documents(FirestoreCopyExistingPolicy::Skip)
+                .with_delete_after_copy(true),
+        )
+        .copy_collection(TEST_SOURCE_COLLECTION_NAME, TEST_TARGET_COLLECTION_NAME)
+        .await?;
+    assert_eq!(result.copied_documents, 3);
+    assert_eq!(result.skipped_documents, 1);
+    assert_eq!(result.deleted_documents, 3);
+    assert!(result.failures.is_empty());
+
+    assert_eq!(
+        get_doc(&db, TEST_TARGET_COLLECTION_NAME, "test-2")
+            .await?
+            .map(|doc| doc.some_num),
+        Some(100)
+    );
+    let target_children: Vec<MyTestStructure> = db
+        .fluent()
+        .select()
+        .from(TEST_CHILD_COLLECTION_NAME)
+        .parent(db.parent_path(TEST_TARGET_COLLECTION_NAME, "test-1")?)
+        .obj()
+        .query()
+        .await?;
+    assert_eq!(target_children.len(), 1);
+
+    // The source is kept only for the document that wasn't copied
+    assert!(get_doc(&db, TEST_SOURCE_COLLECTION_NAME, "test-1")
+        .await?
+        .is_none());
+    assert!(get_doc(&db, TEST_SOURCE_COLLECTION_NAME, "test-2")
+        .await?
+        .is_some());
+    assert!(get_doc(&db, TEST_SOURCE_COLLECTION_NAME, "test-3")
+        .await?
+        .is_none());
+
+    Ok(())
+}
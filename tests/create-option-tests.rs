@@ -70,5 +70,17 @@ async fn crud_tests() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     assert_eq!(Some(object_updated), find_it_again);
 
+    // Create only fails for the existing document
+    assert!(db
+        .fluent()
+        .update()
+        .in_col(TEST_COLLECTION_NAME)
+        .document_id(&my_struct1.some_id)
+        .object(&my_struct1)
+        .create_only()
+        .execute::<MyTestStructure>()
+        .await
+        .is_err());
+
     Ok(())
 }
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+mod common;
+use crate::common::setup;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+struct MyTestStructure {
+    some_id: String,
+    some_num: i64,
+    some_double: f64,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    created_at: DateTime<Utc>,
+    #[serde(with = "firestore::serialize_as_reference")]
+    parent: String,
+}
+
+#[tokio::test]
+async fn export_import_test() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let db = setup().await?;
+
+    const TEST_COLLECTION_NAME: &'static str = "integration-test-ndjson";
+
+    let parent_path = db.parent_path(TEST_COLLECTION_NAME, "parent")?;
+    let my_structs: Vec<MyTestStructure> = (0..10)
+        .map(|i| {
+            Ok(MyTestStructure {
+                some_id: format!("test-{i}"),
+                some_num: i,
+                some_double: i as f64,
+                created_at: DateTime::parse_from_rfc3339("2023-01-02T03:04:05.123456Z")?
+                    .with_timezone(&Utc),
+                parent: parent_path.to_string(),
+            })
+        })
+        .collect::<Result<_, chrono::ParseError>>()?;
+
+    db.fluent()
+        .delete()
+        .recursive()
+        .collection(TEST_COLLECTION_NAME)
+        .execute()
+        .await?;
+
+    for my_struct in &my_structs {
+        db.fluent()
+            .update()
+            .in_col(TEST_COLLECTION_NAME)
+            .document_id(&my_struct.some_id)
+            .object(my_struct)
+            .execute::<()>()
+            .await?;
+    }
+    db.fluent()
+        .update()
+        .in_col("children")
+        .document_id("child")
+        .parent(&parent_path)
+        .object(&my_structs[0])
+        .execute::<()>()
+        .await?;
+
+    let mut exported: Vec<u8> = Vec::new();
+    let export_result = db
+        .export_collection_ndjson(
+            TEST_COLLECTION_NAME,
+            &mut exported,
+            FirestoreExportOptions::new().with_recursive(true),
+        )
+        .await?;
+    assert_eq!(export_result.exported_documents, 11);
+
+    let dry_run = db
+        .import_ndjson(
+            exported.as_slice(),
+            FirestoreImportOptions::new().with_mode(FirestoreImportMode::DryRun),
+        )
+        .await?;
+    assert_eq!(dry_run.read_documents, 11);
+    assert_eq!(dry_run.imported_documents, 0);
+
+    let create_only = db
+        .import_ndjson(
+            exported.as_slice(),
+            FirestoreImportOptions::new().with_mode(FirestoreImportMode::CreateOnly),
+        )
+        .await?;
+    assert_eq!(create_only.skipped_documents, 11);
+
+    db.fluent()
+        .delete()
+        .recursive()
+        .collection(TEST_COLLECTION_NAME)
+        .execute()
+        .await?;
+
+    let imported = db
+        .import_ndjson(exported.as_slice(), FirestoreImportOptions::new())
+        .await?;
+    assert_eq!(imported.imported_documents, 11);
+
+    let child: Option<MyTestStructure> = db
+        .fluent()
+        .select()
+        .by_id_in("children")
+        .parent(&parent_path)
+        .obj()
+        .one("child")
+        .await?;
+    assert_eq!(child, Some(my_structs[0].clone()));
+
+    Ok(())
+}
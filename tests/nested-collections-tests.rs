@@ -1,4 +1,5 @@
 use crate::common::setup;
+use firestore::*;
 use serde::{Deserialize, Serialize};
 
 mod common;
@@ -95,3 +96,94 @@ async fn crud_tests() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn recursive_delete_tests() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    const TEST_PARENT_COLLECTION_NAME: &'static str = "integration-nested-delete-test";
+    const TEST_CHILD_COLLECTION_NAME: &'static str = "integration-test-childs";
+
+    let db = setup().await?;
+
+    let parent_struct = MyParentStructure {
+        some_id: "test-parent".to_string(),
+        some_string: "Test".to_string(),
+    };
+    let child_struct = MyChildStructure {
+        some_id: "test-child".to_string(),
+        another_string: "TestChild".to_string(),
+    };
+    let parent_path = db.parent_path(TEST_PARENT_COLLECTION_NAME, &parent_struct.some_id)?;
+
+    db.fluent()
+        .delete()
+        .recursive()
+        .collection(TEST_PARENT_COLLECTION_NAME)
+        .execute()
+        .await?;
+
+    // A child without an existing parent document
+    let _: MyChildStructure = db
+        .fluent()
+        .insert()
+        .into(TEST_CHILD_COLLECTION_NAME)
+        .document_id(&child_struct.some_id)
+        .parent(&parent_path)
+        .object(&child_struct)
+        .execute()
+        .await?;
+
+    // The failed root precondition keeps the children
+    let result = db
+        .fluent()
+        .delete()
+        .recursive()
+        .from(TEST_PARENT_COLLECTION_NAME)
+        .precondition(FirestoreWritePrecondition::Exists(true))
+        .document_id(&parent_struct.some_id)
+        .execute()
+        .await;
+    assert!(result.is_err());
+
+    let find_child: Option<MyChildStructure> = db
+        .fluent()
+        .select()
+        .by_id_in(TEST_CHILD_COLLECTION_NAME)
+        .parent(&parent_path)
+        .obj()
+        .one(&child_struct.some_id)
+        .await?;
+    assert_eq!(find_child, Some(child_struct.clone()));
+
+    let _: MyParentStructure = db
+        .fluent()
+        .insert()
+        .into(TEST_PARENT_COLLECTION_NAME)
+        .document_id(&parent_struct.some_id)
+        .object(&parent_struct)
+        .execute()
+        .await?;
+
+    let result = db
+        .fluent()
+        .delete()
+        .recursive()
+        .from(TEST_PARENT_COLLECTION_NAME)
+        .precondition(FirestoreWritePrecondition::Exists(true))
+        .document_id(&parent_struct.some_id)
+        .execute()
+        .await?;
+    assert_eq!(result.deleted_documents, 2);
+    assert!(result.failures.is_empty());
+
+    let find_child: Option<MyChildStructure> = db
+        .fluent()
+        .select()
+        .by_id_in(TEST_CHILD_COLLECTION_NAME)
+        .parent(&parent_path)
+        .obj()
+        .one(&child_struct.some_id)
+        .await?;
+    assert_eq!(find_child, None);
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use crate::common::setup;
+use firestore::errors::FirestoreError;
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+mod common;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+struct MyTestStructure {
+    some_id: String,
+    some_num: u64,
+}
+
+#[tokio::test]
+async fn transaction_read_set_tests() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    const TEST_COLLECTION_NAME: &'static str = "integration-test-transaction-reads";
+
+    let db = setup().await?;
+
+    let my_struct = MyTestStructure {
+        some_id: "test-0".to_string(),
+        some_num: 42,
+    };
+
+    db.fluent()
+        .update()
+        .in_col(TEST_COLLECTION_NAME)
+        .document_id(&my_struct.some_id)
+        .object(&my_struct)
+        .execute::<MyTestStructure>()
+        .await?;
+
+    db.fluent()
+        .delete()
+        .from(TEST_COLLECTION_NAME)
+        .document_id("test-missing")
+        .execute()
+        .await?;
+
+    let mut transaction = db.begin_transaction().await?;
+
+    let found: Option<MyTestStructure> = transaction
+        .get_obj(TEST_COLLECTION_NAME, &my_struct.some_id)
+        .await?;
+    assert_eq!(found.as_ref(), Some(&my_struct));
+
+    let missing: Option<MyTestStructure> = transaction
+        .get_obj(TEST_COLLECTION_NAME, "test-missing")
+        .await?;
+    assert_eq!(missing, None);
+
+    let queried: Vec<MyTestStructure> = transaction
+        .query_obj(FirestoreQueryParams::new(TEST_COLLECTION_NAME.into()))
+        .await?;
+    assert!(queried.contains(&my_struct));
+
+    transaction.update_object(
+        TEST_COLLECTION_NAME,
+        &my_struct.some_id,
+        &MyTestStructure {
+            some_num: my_struct.some_num + 1,
+            ..my_struct.clone()
+        },
+        None,
+        None,
+        vec![],
+    )?;
+
+    // Reads after writes are refused
+    let read_after_write: FirestoreResult<Option<MyTestStructure>> = transaction
+        .get_obj(TEST_COLLECTION_NAME, &my_struct.some_id)
+        .await;
+    assert!(matches!(
+        read_after_write,
+        Err(FirestoreError::InvalidParametersError(_))
+    ));
+
+    let response = transaction.commit().await?;
+
+    let document_path = db.parent_path(TEST_COLLECTION_NAME, &my_struct.some_id)?;
+    assert!(response.read_documents.contains(&document_path.to_string()));
+    assert!(response.read_documents.contains(
+        &db.parent_path(TEST_COLLECTION_NAME, "test-missing")?
+            .to_string()
+    ));
+    assert_eq!(response.written_documents, vec![document_path.to_string()]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transaction_insert_tests() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    const TEST_COLLECTION_NAME: &'static str = "integration-test-transaction-inserts";
+
+    let db = setup().await?;
+
+    let my_struct = MyTestStructure {
+        some_id: "test-1".to_string(),
+        some_num: 1,
+    };
+
+    db.fluent()
+        .delete()
+        .from(TEST_COLLECTION_NAME)
+        .document_id(&my_struct.some_id)
+        .execute()
+        .await?;
+
+    let mut transaction = db.begin_transaction().await?;
+
+    db.fluent()
+        .insert()
+        .into(TEST_COLLECTION_NAME)
+        .document_id(&my_struct.some_id)
+        .object(&my_struct)
+        .add_to_transaction(&mut transaction)?;
+
+    let insert = db
+        .fluent()
+        .insert()
+        .into(TEST_COLLECTION_NAME)
+        .generate_document_id()
+        .object(&my_struct);
+    let generated_id = insert.document_id().to_string();
+    insert.add_to_transaction(&mut transaction)?;
+
+    let response = transaction.commit().await?;
+    assert_eq!(response.written_documents.len(), 2);
+    assert!(response
+        .written_documents
+        .iter()
+        .any(|document_path| document_path.ends_with(&format!("/{generated_id}"))));
+
+    let found: Option<MyTestStructure> = db
+        .fluent()
+        .select()
+        .by_id_in(TEST_COLLECTION_NAME)
+        .obj()
+        .one(&generated_id)
+        .await?;
+    assert_eq!(found, Some(my_struct.clone()));
+
+    // Inserting an existing document fails on commit
+    let mut transaction = db.begin_transaction().await?;
+
+    db.fluent()
+        .insert()
+        .into(TEST_COLLECTION_NAME)
+        .document_id(&my_struct.some_id)
+        .object(&my_struct)
+        .add_to_transaction(&mut transaction)?;
+
+    assert_eq!(transaction.written_documents().len(), 1);
+    assert!(matches!(
+        transaction.commit().await,
+        Err(FirestoreError::DataConflictError(_))
+    ));
+
+    Ok(())
+}
+
+const TEST_TYPED_COLLECTION_NAME: &str = "integration-test-typed-transaction";
+
+async fn increment_num(
+    db: FirestoreDb,
+    transaction: &mut FirestoreTransaction<'_>,
+) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let current: Option<MyTestStructure> = transaction
+        .get_obj(TEST_TYPED_COLLECTION_NAME, "test-0")
+        .await?;
+    let current = current.ok_or("The document doesn't exist")?;
+
+    let some_num = current.some_num + 1;
+    db.fluent()
+        .update()
+        .in_col(TEST_TYPED_COLLECTION_NAME)
+        .document_id(&current.some_id)
+        .object(&MyTestStructure {
+            some_num,
+            ..current
+        })
+        .add_to_transaction(transaction)?;
+
+    Ok(some_num)
+}
+
+async fn missing_document(
+    _db: FirestoreDb,
+    _transaction: &mut FirestoreTransaction<'_>,
+) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    Err("The document doesn't exist".into())
+}
+
+#[tokio::test]
+async fn typed_transaction_tests() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let db = setup().await?;
+
+    db.fluent()
+        .update()
+        .in_col(TEST_TYPED_COLLECTION_NAME)
+        .document_id("test-0")
+        .object(&MyTestStructure {
+            some_id: "test-0".to_string(),
+            some_num: 1,
+        })
+        .execute::<()>()
+        .await?;
+
+    // The transaction future can be spawned
+    let spawned_db = db.clone();
+    let some_num =
+        tokio::spawn(async move { spawned_db.run_typed_transaction(increment_num).await })
+            .await??;
+    assert_eq!(some_num, 2);
+
+    assert!(matches!(
+        db.run_typed_transaction(missing_document).await,
+        Err(FirestoreError::ErrorInTransaction(_))
+    ));
+
+    Ok(())
+}
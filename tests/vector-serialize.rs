@@ -0,0 +1,95 @@
+use firestore::*;
+use serde::{Deserialize, Serialize};
+
+mod common;
+use crate::common::setup;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+struct MyVectorStructure {
+    some_id: String,
+    embedding: FirestoreVector,
+    #[serde(with = "firestore::serialize_as_vector")]
+    embedding_f64: Vec<f64>,
+    #[serde(with = "firestore::serialize_as_vector")]
+    embedding_f32: [f32; 64],
+}
+
+fn test_structure(id: &str) -> MyVectorStructure {
+    let mut embedding_f32 = [0.0; 64];
+    for (i, v) in embedding_f32.iter_mut().enumerate() {
+        *v = i as f32 / 64.0;
+    }
+    MyVectorStructure {
+        some_id: id.to_string(),
+        embedding: FirestoreVector(vec![1.0, 2.0, 3.0]),
+        embedding_f64: vec![0.5, -0.5],
+        embedding_f32,
+    }
+}
+
+#[test]
+fn vector_document_round_trip() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let my_struct = test_structure("test-1");
+
+    let doc = FirestoreDb::serialize_to_doc("test-doc", &my_struct)?;
+    let embedding = doc.fields.get("embedding").expect("embedding field");
+    match &embedding.value_type {
+        Some(gcloud_sdk::google::firestore::v1::value::ValueType::MapValue(map_value)) => {
+            assert!(matches!(
+                map_value.fields.get("__type__").and_then(|v| v.value_type.as_ref()),
+                Some(gcloud_sdk::google::firestore::v1::value::ValueType::StringValue(v)) if v == "__vector__"
+            ));
+        }
+        other => panic!("Unexpected vector encoding: {:?}", other),
+    }
+
+    let deserialized: MyVectorStructure = FirestoreDb::deserialize_doc_to(&doc)?;
+    assert_eq!(deserialized, my_struct);
+
+    // Vectors with the wrong number of dimensions are rejected
+    let mut short_doc = doc.clone();
+    short_doc.fields.insert(
+        "embedding_f32".to_string(),
+        short_doc.fields.get("embedding").unwrap().clone(),
+    );
+    assert!(FirestoreDb::deserialize_doc_to::<MyVectorStructure>(&short_doc).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let db = setup().await?;
+
+    const TEST_COLLECTION_NAME: &'static str = "integration-test-vectors";
+
+    let my_struct = test_structure("test-1");
+
+    db.fluent()
+        .delete()
+        .from(TEST_COLLECTION_NAME)
+        .document_id(&my_struct.some_id)
+        .execute()
+        .await?;
+
+    let inserted: MyVectorStructure = db
+        .fluent()
+        .insert()
+        .into(TEST_COLLECTION_NAME)
+        .document_id(&my_struct.some_id)
+        .object(&my_struct)
+        .execute()
+        .await?;
+    assert_eq!(inserted, my_struct);
+
+    let find_it_again: Option<MyVectorStructure> = db
+        .fluent()
+        .select()
+        .by_id_in(TEST_COLLECTION_NAME)
+        .obj()
+        .one(&my_struct.some_id)
+        .await?;
+    assert_eq!(find_it_again, Some(my_struct));
+
+    Ok(())
+}